@@ -1,11 +1,25 @@
 use tauri_specta::{collect_commands, Builder};
 
 pub fn generate_bindings() -> Builder<tauri::Wry> {
-    use crate::commands::{notifications, preferences, pty, quick_pane, recovery};
+    use crate::commands::{
+        env_profiles, environment, notifications, playback, preferences, pty, quick_pane, recovery,
+        secrets,
+    };
 
     Builder::<tauri::Wry>::new().commands(collect_commands![
         preferences::load_preferences,
         preferences::save_preferences,
+        preferences::get_default_allowed_shells,
+        environment::detect_environment,
+        environment::refresh_login_environment,
+        env_profiles::list_env_profiles,
+        env_profiles::save_env_profile,
+        env_profiles::delete_env_profile,
+        secrets::list_secrets,
+        secrets::set_secret,
+        secrets::delete_secret,
+        secrets::unlock_secrets,
+        secrets::set_secrets_passphrase,
         notifications::send_native_notification,
         recovery::save_emergency_data,
         recovery::load_emergency_data,
@@ -16,9 +30,25 @@ pub fn generate_bindings() -> Builder<tauri::Wry> {
         quick_pane::get_default_quick_pane_shortcut,
         quick_pane::update_quick_pane_shortcut,
         pty::pty_spawn,
+        pty::pty_attach,
         pty::pty_write,
         pty::pty_resize,
         pty::pty_kill,
+        pty::pty_get_scrollback,
+        pty::pty_list,
+        pty::pty_set_title,
+        pty::pty_snapshot,
+        pty::pty_foreground_process,
+        pty::pty_signal,
+        pty::pty_record_start,
+        pty::pty_record_stop,
+        pty::search_sessions,
+        playback::playback_start,
+        playback::playback_pause,
+        playback::playback_resume,
+        playback::playback_seek,
+        playback::playback_step,
+        playback::playback_stop,
     ])
 }
 
@@ -0,0 +1,91 @@
+//! Environment profile commands.
+//!
+//! Lists, saves and deletes the named environment profiles that sessions
+//! can be spawned with. Secret values are never returned to the frontend.
+
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::env_profiles::{self, ProfilesLock};
+use crate::pty_manager::PtyManager;
+use crate::types::{validate_env_profile, EnvProfile};
+
+/// Gets the path to the environment profiles file.
+fn get_env_profiles_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+
+    // Ensure the directory exists
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("env_profiles.json"))
+}
+
+/// Saves the profiles and applies them to sessions spawned afterwards.
+/// The caller holds the `ProfilesLock`.
+fn write_env_profiles(app: &AppHandle, profiles: &[EnvProfile]) -> Result<(), String> {
+    env_profiles::write(&get_env_profiles_path(app)?, profiles)?;
+    app.state::<PtyManager>().set_env_profiles(profiles);
+    Ok(())
+}
+
+/// Load the saved environment profiles, or none on any failure.
+/// Used at startup to configure the PTY manager.
+pub fn load_env_profiles(app: &AppHandle) -> Vec<EnvProfile> {
+    get_env_profiles_path(app)
+        .and_then(|path| env_profiles::read(&path))
+        .unwrap_or_else(|e| {
+            log::warn!("{e}");
+            Vec::new()
+        })
+}
+
+/// Lists the saved environment profiles, without their secret values.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_env_profiles(app: AppHandle) -> Result<Vec<EnvProfile>, String> {
+    let profiles = env_profiles::read(&get_env_profiles_path(&app)?)?;
+    Ok(profiles.iter().map(env_profiles::redact).collect())
+}
+
+/// Saves an environment profile, replacing the profile with the same name.
+/// Secret variables without a value keep their saved value.
+#[tauri::command]
+#[specta::specta]
+pub async fn save_env_profile(app: AppHandle, mut profile: EnvProfile) -> Result<(), String> {
+    profile.name = profile.name.trim().to_string();
+    validate_env_profile(&profile)?;
+
+    let profiles_lock = app.state::<ProfilesLock>();
+    let _guard = profiles_lock.lock();
+    let mut profiles = env_profiles::read(&get_env_profiles_path(&app)?)?;
+    let saved = profiles.iter().position(|p| p.name == profile.name);
+    env_profiles::restore_secrets(&mut profile, saved.map(|i| &profiles[i]))?;
+
+    log::info!("Saving environment profile: {}", profile.name);
+    match saved {
+        Some(i) => profiles[i] = profile,
+        None => profiles.push(profile),
+    }
+    write_env_profiles(&app, &profiles)
+}
+
+/// Deletes an environment profile.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_env_profile(app: AppHandle, name: String) -> Result<(), String> {
+    let profiles_lock = app.state::<ProfilesLock>();
+    let _guard = profiles_lock.lock();
+    let mut profiles = env_profiles::read(&get_env_profiles_path(&app)?)?;
+    let count = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == count {
+        return Err(format!("Environment profile not found: {name}"));
+    }
+
+    log::info!("Deleting environment profile: {name}");
+    write_env_profiles(&app, &profiles)
+}
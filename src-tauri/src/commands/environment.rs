@@ -0,0 +1,48 @@
+//! Environment detection commands.
+//!
+//! Reports the installed shells and Claude CLI so the frontend can guide
+//! the user through setup before the first session is spawned, and
+//! refreshes the login shell environment that sessions are spawned with.
+
+use tauri::{AppHandle, Manager};
+
+use crate::commands::preferences::load_allowed_shells;
+use crate::environment;
+use crate::pty_manager::PtyManager;
+use crate::types::EnvironmentInfo;
+
+/// Detects the installed shells, the user's login shell and the Claude CLI.
+/// Runs the shells and the CLI, so detection happens on a blocking thread.
+#[tauri::command]
+#[specta::specta]
+pub async fn detect_environment(app: AppHandle) -> Result<EnvironmentInfo, String> {
+    let allowed_shells = load_allowed_shells(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let manager = app.state::<PtyManager>();
+        let login_env = manager.login_environment();
+        let env = login_env.get().or_else(|| {
+            login_env
+                .refresh()
+                .inspect_err(|e| log::warn!("Failed to resolve the login shell environment: {e}"))
+                .ok()
+        });
+        environment::detect(&allowed_shells, env.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Environment detection failed: {e}"))
+}
+
+/// Runs the login shell again and caches its environment, e.g. after the
+/// user installed a tool. Applies to sessions spawned afterwards.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_login_environment(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<PtyManager>()
+            .login_environment()
+            .refresh()
+            .map(|_| ())
+    })
+    .await
+    .map_err(|e| format!("Login environment refresh failed: {e}"))?
+}
@@ -3,8 +3,12 @@
 //! Each submodule contains related commands and their helper functions.
 //! Import specific commands via their submodule (e.g., `commands::preferences::greet`).
 
+pub mod env_profiles;
+pub mod environment;
 pub mod notifications;
+pub mod playback;
 pub mod preferences;
 pub mod pty;
 pub mod quick_pane;
 pub mod recovery;
+pub mod secrets;
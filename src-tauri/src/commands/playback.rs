@@ -0,0 +1,71 @@
+//! Playback commands.
+//!
+//! Play back session recordings from the `recordings` directory of the app
+//! data through a channel, as if they were live sessions, and control the
+//! playback: pause, resume, seek and step.
+
+use tauri::ipc::Channel;
+use tauri::{AppHandle, State};
+
+use super::pty::get_recordings_dir;
+use crate::playback::PlaybackManager;
+use crate::types::{validate_filename, PlaybackInfo, PlaybackOptions, PtyEvent};
+
+/// Starts playing back a recording, given its file name (`RecordingInfo::name`).
+/// Output and resizes are sent to the channel as `PtyEvent`s, followed by a
+/// `PlaybackState` event whenever the playback is paused, sought or ends.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_start(
+    app: AppHandle,
+    state: State<'_, PlaybackManager>,
+    name: String,
+    on_event: Channel<PtyEvent>,
+    options: Option<PlaybackOptions>,
+) -> Result<PlaybackInfo, String> {
+    validate_filename(&name)?;
+    let path = get_recordings_dir(&app)
+        .map_err(|e| e.to_string())?
+        .join(name);
+    state.start(&path, options.unwrap_or_default(), on_event)
+}
+
+/// Pauses a playback.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_pause(state: State<'_, PlaybackManager>, id: String) -> Result<(), String> {
+    state.pause(&id)
+}
+
+/// Resumes a paused playback.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_resume(state: State<'_, PlaybackManager>, id: String) -> Result<(), String> {
+    state.resume(&id)
+}
+
+/// Seeks a playback to `position_ms`. The terminal is reset and the output
+/// up to that position is sent at once.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_seek(
+    state: State<'_, PlaybackManager>,
+    id: String,
+    position_ms: u32,
+) -> Result<(), String> {
+    state.seek(&id, position_ms)
+}
+
+/// Pauses a playback and plays its next event.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_step(state: State<'_, PlaybackManager>, id: String) -> Result<(), String> {
+    state.step(&id)
+}
+
+/// Stops a playback.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_stop(state: State<'_, PlaybackManager>, id: String) -> Result<(), String> {
+    state.stop(&id)
+}
@@ -5,7 +5,10 @@
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
-use crate::types::{validate_theme, AppPreferences};
+use crate::pty_manager::{default_allowed_shells, PtyManager};
+use crate::redaction::{self, Redactor};
+use crate::types::{validate_allowed_shells, validate_theme, AppPreferences, RedactionSettings};
+use crate::utils::fs::replace_private;
 
 /// Gets the path to the preferences file.
 fn get_preferences_path(app: &AppHandle) -> Result<PathBuf, String> {
@@ -21,9 +24,8 @@ fn get_preferences_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join("preferences.json"))
 }
 
-/// Load the saved quick pane shortcut from preferences, returning None on any failure.
-/// Used at startup before the full preferences system is available.
-pub fn load_quick_pane_shortcut(app: &AppHandle) -> Option<String> {
+/// Reads the saved preferences, returning None if there are none or on any failure.
+fn read_saved_preferences(app: &AppHandle) -> Option<AppPreferences> {
     let path = get_preferences_path(app).ok()?;
     if !path.exists() {
         return None;
@@ -31,11 +33,41 @@ pub fn load_quick_pane_shortcut(app: &AppHandle) -> Option<String> {
     let contents = std::fs::read_to_string(&path)
         .inspect_err(|e| log::warn!("Failed to read preferences: {e}"))
         .ok()?;
-    let prefs: AppPreferences = serde_json::from_str(&contents)
+    serde_json::from_str(&contents)
         .inspect_err(|e| log::warn!("Failed to parse preferences: {e}"))
-        .ok()?;
-    prefs.quick_pane_shortcut
+        .ok()
+}
+
+/// Load the saved quick pane shortcut from preferences, returning None on any failure.
+/// Used at startup before the full preferences system is available.
+pub fn load_quick_pane_shortcut(app: &AppHandle) -> Option<String> {
+    read_saved_preferences(app)?.quick_pane_shortcut
+}
+
+/// Load the saved shell allowlist, falling back to the default on any failure.
+/// Used at startup to configure the PTY manager.
+pub fn load_allowed_shells(app: &AppHandle) -> Vec<String> {
+    read_saved_preferences(app)
+        .map(|prefs| prefs.allowed_shells)
+        .unwrap_or_else(default_allowed_shells)
 }
+
+/// Load the saved redaction settings, falling back to the default on any
+/// failure. Used at startup to configure redaction.
+pub fn load_redaction_settings(app: &AppHandle) -> RedactionSettings {
+    read_saved_preferences(app)
+        .map(|prefs| prefs.redaction)
+        .unwrap_or_default()
+}
+
+/// Returns the default shell allowlist (installed shells from /etc/shells)
+/// for frontend use.
+#[tauri::command]
+#[specta::specta]
+pub fn get_default_allowed_shells() -> Vec<String> {
+    default_allowed_shells()
+}
+
 /// Loads user preferences from disk.
 /// Returns default preferences if the file doesn't exist.
 #[tauri::command]
@@ -71,6 +103,15 @@ pub async fn save_preferences(app: AppHandle, preferences: AppPreferences) -> Re
     // Validate theme value
     validate_theme(&preferences.theme)?;
 
+    // Validate shells added since the last save
+    let previous_shells = read_saved_preferences(&app)
+        .map(|prefs| prefs.allowed_shells)
+        .unwrap_or_default();
+    validate_allowed_shells(&preferences.allowed_shells, &previous_shells)?;
+
+    // Validate the redaction patterns
+    let redactor = Redactor::new(&preferences.redaction)?;
+
     log::debug!("Saving preferences to disk: {preferences:?}");
     let prefs_path = get_preferences_path(&app)?;
 
@@ -79,22 +120,14 @@ pub async fn save_preferences(app: AppHandle, preferences: AppPreferences) -> Re
         format!("Failed to serialize preferences: {e}")
     })?;
 
-    // Write to a temporary file first, then rename (atomic operation)
-    let temp_path = prefs_path.with_extension("tmp");
-
-    std::fs::write(&temp_path, json_content).map_err(|e| {
+    replace_private(&prefs_path, json_content.as_bytes()).map_err(|e| {
         log::error!("Failed to write preferences file: {e}");
         format!("Failed to write preferences file: {e}")
     })?;
 
-    if let Err(rename_err) = std::fs::rename(&temp_path, &prefs_path) {
-        log::error!("Failed to finalize preferences file: {rename_err}");
-        // Clean up the temp file to avoid leaving orphaned files on disk
-        if let Err(remove_err) = std::fs::remove_file(&temp_path) {
-            log::warn!("Failed to remove temp file after rename failure: {remove_err}");
-        }
-        return Err(format!("Failed to finalize preferences file: {rename_err}"));
-    }
+    app.state::<PtyManager>()
+        .set_allowed_shells(&preferences.allowed_shells);
+    redaction::set_active(redactor);
 
     log::info!("Successfully saved preferences to {prefs_path:?}");
     Ok(())
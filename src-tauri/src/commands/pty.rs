@@ -1,63 +1,233 @@
 //! PTY management commands for the terminal UI.
 //!
-//! Provides Tauri commands to spawn, write to, resize, and kill PTY sessions.
+//! Provides Tauri commands to spawn, attach to, write to, resize, signal, list,
+//! record, search and kill PTY sessions, and to fetch their buffered
+//! scrollback, rendered screen and foreground process.
 //! Output is streamed to the frontend via Tauri Channel API.
+//!
+//! `PtyManager` locks per session internally, so commands for different
+//! sessions never wait on each other.
+
+use std::path::PathBuf;
 
-use std::sync::{Mutex, MutexGuard, PoisonError};
 use tauri::ipc::Channel;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 use crate::pty_manager::PtyManager;
-use crate::types::{PtyError, PtyEvent, SpawnOptions};
-
-/// Recovers from a poisoned mutex by logging a warning and returning the inner value.
-fn recover_lock(err: PoisonError<MutexGuard<'_, PtyManager>>) -> MutexGuard<'_, PtyManager> {
-    log::warn!("PtyManager mutex was poisoned, recovering: {err}");
-    err.into_inner()
-}
+use crate::types::{
+    AttachInfo, ForegroundProcess, KillOptions, PtyError, PtyEvent, RecordOptions, RecordingInfo,
+    ScreenSnapshot, ScrollbackSnapshot, SearchMatch, SearchOptions, SessionInfo, Signal,
+    SnapshotFormat, SpawnOptions,
+};
 
 /// Spawns a new PTY session and begins streaming output via the channel.
 /// Returns the session ID.
 #[tauri::command]
 #[specta::specta]
 pub fn pty_spawn(
-    state: State<'_, Mutex<PtyManager>>,
+    state: State<'_, PtyManager>,
     on_event: Channel<PtyEvent>,
     options: SpawnOptions,
 ) -> Result<String, PtyError> {
-    let mut manager = state.lock().unwrap_or_else(recover_lock);
-    manager.spawn(options, on_event)
+    state.spawn(options, on_event)
+}
+
+/// Attaches a new channel to an existing PTY session, e.g. after a webview reload.
+/// Buffered output newer than `since` is replayed before live output resumes.
+/// If some of it was already evicted, the result is marked as truncated.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_attach(
+    state: State<'_, PtyManager>,
+    session_id: String,
+    on_event: Channel<PtyEvent>,
+    since: Option<u32>,
+) -> Result<AttachInfo, PtyError> {
+    state.attach(&session_id, on_event, since)
 }
 
 /// Writes data to a PTY session's stdin.
+/// The data is queued for the session's writer thread, so backpressure from
+/// a session that is not reading its input cannot freeze the main thread.
 #[tauri::command]
 #[specta::specta]
 pub fn pty_write(
-    state: State<'_, Mutex<PtyManager>>,
+    state: State<'_, PtyManager>,
     session_id: String,
     data: Vec<u8>,
 ) -> Result<(), PtyError> {
-    let mut manager = state.lock().unwrap_or_else(recover_lock);
-    manager.write(&session_id, &data)
+    state.write(&session_id, &data)
 }
 
 /// Resizes a PTY session.
 #[tauri::command]
 #[specta::specta]
 pub fn pty_resize(
-    state: State<'_, Mutex<PtyManager>>,
+    state: State<'_, PtyManager>,
     session_id: String,
     cols: u16,
     rows: u16,
 ) -> Result<(), PtyError> {
-    let manager = state.lock().unwrap_or_else(recover_lock);
-    manager.resize(&session_id, cols, rows)
+    state.resize(&session_id, cols, rows)
+}
+
+/// Returns the buffered output of a PTY session.
+/// Used to rebuild the terminal before resuming the live output stream.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_get_scrollback(
+    state: State<'_, PtyManager>,
+    session_id: String,
+) -> Result<ScrollbackSnapshot, PtyError> {
+    state.scrollback(&session_id)
 }
 
 /// Kills a PTY session and cleans up resources.
+/// Returns right away; the session's processes are given `options.timeout_ms`
+/// to exit after SIGHUP/SIGTERM before they are killed.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_kill(
+    state: State<'_, PtyManager>,
+    session_id: String,
+    options: Option<KillOptions>,
+) -> Result<(), PtyError> {
+    state.kill_with_options(&session_id, options.unwrap_or_default())
+}
+
+/// Lists all PTY sessions, oldest first.
+/// Includes sessions spawned before a webview reload.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_list(state: State<'_, PtyManager>) -> Vec<SessionInfo> {
+    state.list()
+}
+
+/// Sets or clears the user-assigned title of a PTY session.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_set_title(
+    state: State<'_, PtyManager>,
+    session_id: String,
+    title: Option<String>,
+) -> Result<(), PtyError> {
+    state.set_title(&session_id, title)
+}
+
+/// Returns the rendered screen of a PTY session, as text or as cells.
+/// The session must have been spawned with `track_screen` enabled.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_snapshot(
+    state: State<'_, PtyManager>,
+    session_id: String,
+    format: SnapshotFormat,
+) -> Result<ScreenSnapshot, PtyError> {
+    state.snapshot(&session_id, format)
+}
+
+/// Returns the process in the foreground of a PTY session (e.g. `claude`
+/// running, or the shell idle at its prompt). None if it cannot be determined.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_foreground_process(
+    state: State<'_, PtyManager>,
+    session_id: String,
+) -> Result<Option<ForegroundProcess>, PtyError> {
+    state.foreground_process(&session_id)
+}
+
+/// Sends a signal to the foreground process group of a PTY session (the
+/// running job, or the shell if it is idle). Not supported on Windows.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_signal(
+    state: State<'_, PtyManager>,
+    session_id: String,
+    signal: Signal,
+) -> Result<(), PtyError> {
+    state.signal(&session_id, signal)
+}
+
+/// Gets the directory recordings are saved to, creating it if needed.
+pub fn get_recordings_dir(app: &AppHandle) -> Result<PathBuf, PtyError> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| PtyError::IoError {
+        message: format!("Failed to get app data directory: {e}"),
+    })?;
+
+    let recordings_dir = app_data_dir.join("recordings");
+    std::fs::create_dir_all(&recordings_dir).map_err(|e| PtyError::IoError {
+        message: format!("Failed to create recordings directory: {e}"),
+    })?;
+
+    Ok(recordings_dir)
+}
+
+/// Gets the directory session transcripts are written to, creating it if needed.
+pub fn get_transcripts_dir(app: &AppHandle) -> Result<PathBuf, PtyError> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| PtyError::IoError {
+        message: format!("Failed to get app data directory: {e}"),
+    })?;
+
+    let transcripts_dir = app_data_dir.join("transcripts");
+    std::fs::create_dir_all(&transcripts_dir).map_err(|e| PtyError::IoError {
+        message: format!("Failed to create transcripts directory: {e}"),
+    })?;
+
+    Ok(transcripts_dir)
+}
+
+/// Gets the directory the shell integration scripts are written to.
+/// It is created when the scripts are first written.
+pub fn get_shell_integration_dir(app: &AppHandle) -> Result<PathBuf, PtyError> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| PtyError::IoError {
+        message: format!("Failed to get app data directory: {e}"),
+    })?;
+
+    Ok(app_data_dir.join("shell-integration"))
+}
+
+/// Starts recording a PTY session to an asciicast v2 file in the
+/// `recordings` directory of the app data. Output and resizes are always
+/// recorded, input only if `options.record_input` is set.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_record_start(
+    app: AppHandle,
+    state: State<'_, PtyManager>,
+    session_id: String,
+    options: Option<RecordOptions>,
+) -> Result<RecordingInfo, PtyError> {
+    let dir = get_recordings_dir(&app)?;
+    state.start_recording(&session_id, &dir, options.unwrap_or_default())
+}
+
+/// Stops recording a PTY session and returns the finished recording.
+#[tauri::command]
+#[specta::specta]
+pub fn pty_record_stop(
+    state: State<'_, PtyManager>,
+    session_id: String,
+) -> Result<RecordingInfo, PtyError> {
+    state.stop_recording(&session_id)
+}
+
+/// Searches the scrollback of running sessions and the transcripts of
+/// running and past sessions, newest session first.
+/// The search runs on a blocking thread, since transcripts can be large.
 #[tauri::command]
 #[specta::specta]
-pub fn pty_kill(state: State<'_, Mutex<PtyManager>>, session_id: String) -> Result<(), PtyError> {
-    let mut manager = state.lock().unwrap_or_else(recover_lock);
-    manager.kill(&session_id)
+pub async fn search_sessions(
+    app: AppHandle,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<Vec<SearchMatch>, PtyError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<PtyManager>()
+            .search(&query, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| PtyError::SystemError {
+        message: format!("Search task failed: {e}"),
+    })?
 }
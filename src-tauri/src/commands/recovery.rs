@@ -8,7 +8,9 @@ use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Manager};
 
+use crate::redaction;
 use crate::types::{validate_filename, RecoveryError, MAX_RECOVERY_DATA_BYTES};
+use crate::utils::fs::replace_private;
 
 /// Gets the path to the recovery directory, creating it if necessary.
 fn get_recovery_dir(app: &AppHandle) -> Result<PathBuf, String> {
@@ -33,13 +35,16 @@ fn get_recovery_dir(app: &AppHandle) -> Result<PathBuf, String> {
 pub async fn save_emergency_data(
     app: AppHandle,
     filename: String,
-    data: Value,
+    mut data: Value,
 ) -> Result<(), RecoveryError> {
     log::info!("Saving emergency data to file: {filename}");
 
     // Validate filename with proper security checks
     validate_filename(&filename).map_err(|e| RecoveryError::ValidationError { message: e })?;
 
+    // Recovery data may contain session output
+    redaction::active().redact_json(&mut data);
+
     // Serialize to pretty JSON once for both size validation and writing
     let json_content = serde_json::to_string_pretty(&data).map_err(|e| {
         log::error!("Failed to serialize emergency data: {e}");
@@ -58,27 +63,13 @@ pub async fn save_emergency_data(
     let recovery_dir = get_recovery_dir(&app).map_err(|e| RecoveryError::IoError { message: e })?;
     let file_path = recovery_dir.join(format!("{filename}.json"));
 
-    // Write to a temporary file first, then rename (atomic operation)
-    let temp_path = file_path.with_extension("tmp");
-
-    std::fs::write(&temp_path, json_content).map_err(|e| {
+    replace_private(&file_path, json_content.as_bytes()).map_err(|e| {
         log::error!("Failed to write emergency data file: {e}");
         RecoveryError::IoError {
             message: e.to_string(),
         }
     })?;
 
-    if let Err(rename_err) = std::fs::rename(&temp_path, &file_path) {
-        log::error!("Failed to finalize emergency data file: {rename_err}");
-        // Clean up the temp file to avoid leaving orphaned files on disk
-        if let Err(remove_err) = std::fs::remove_file(&temp_path) {
-            log::warn!("Failed to remove temp file after rename failure: {remove_err}");
-        }
-        return Err(RecoveryError::IoError {
-            message: rename_err.to_string(),
-        });
-    }
-
     log::info!("Successfully saved emergency data to {file_path:?}");
     Ok(())
 }
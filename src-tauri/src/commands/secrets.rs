@@ -0,0 +1,86 @@
+//! Secrets vault commands.
+//!
+//! Stores, lists and deletes the named secrets that session environments
+//! can reference as `${secret:NAME}`. Secret values go into the vault and
+//! never come back out to the frontend.
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::pty_manager::PtyManager;
+use crate::types::{validate_secret_name, SecretsStatus};
+
+/// Opens the secrets vault in the app data directory.
+/// Used at startup; the vault is unavailable if this fails.
+pub fn open_secrets_vault(app: &AppHandle) -> Result<(), String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+
+    // Ensure the directory exists
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    app.state::<PtyManager>().secrets().open(&app_data_dir)
+}
+
+/// Lists the names of the stored secrets and whether the vault is locked.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_secrets(state: State<'_, PtyManager>) -> Result<SecretsStatus, String> {
+    state.secrets().status()
+}
+
+/// Stores a secret, replacing the secret with the same name.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_secret(
+    state: State<'_, PtyManager>,
+    name: String,
+    value: String,
+) -> Result<(), String> {
+    validate_secret_name(&name)?;
+    if value.contains('\0') {
+        return Err("Secret value cannot contain a NUL character".to_string());
+    }
+
+    log::info!("Storing secret: {name}");
+    state.secrets().set(&name, value)
+}
+
+/// Deletes a secret.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_secret(state: State<'_, PtyManager>, name: String) -> Result<(), String> {
+    log::info!("Deleting secret: {name}");
+    state.secrets().delete(&name)
+}
+
+/// Unlocks a vault protected by a passphrase, until the app quits.
+#[tauri::command]
+#[specta::specta]
+pub async fn unlock_secrets(
+    state: State<'_, PtyManager>,
+    passphrase: String,
+) -> Result<(), String> {
+    state.secrets().unlock(&passphrase)
+}
+
+/// Sets the vault's passphrase, or removes it with None.
+/// The vault must be unlocked.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_secrets_passphrase(
+    state: State<'_, PtyManager>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    log::info!(
+        "{} the secrets vault passphrase",
+        if passphrase.is_some() {
+            "Setting"
+        } else {
+            "Removing"
+        }
+    );
+    state.secrets().set_passphrase(passphrase.as_deref())
+}
@@ -0,0 +1,149 @@
+//! Named environment profiles for sessions.
+//!
+//! Profiles are saved to a JSON file in the app data directory, readable
+//! only by the user. Secret values stay in the backend: profiles are
+//! redacted before they are sent to the frontend, and a secret saved
+//! without a value keeps the value it had. Values can also reference the
+//! secrets vault as `${secret:NAME}`, which is resolved at spawn time.
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::types::EnvProfile;
+use crate::utils::fs::replace_private;
+
+/// Serializes changes to the saved profiles, so that concurrent saves
+/// cannot overwrite each other's changes. Managed as app state.
+#[derive(Default)]
+pub struct ProfilesLock(Mutex<()>);
+
+impl ProfilesLock {
+    /// Locks the saved profiles until the guard is dropped. Held from
+    /// reading the profiles until the changed profiles are written.
+    pub fn lock(&self) -> MutexGuard<'_, ()> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Reads the saved profiles. A missing file means there are none.
+pub fn read(path: &Path) -> Result<Vec<EnvProfile>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read environment profiles: {e}")),
+    };
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse environment profiles: {e}"))
+}
+
+/// Writes the profiles, replacing the file atomically (temp file + rename).
+/// On Unix the file is only readable by the user.
+pub fn write(path: &Path, profiles: &[EnvProfile]) -> Result<(), String> {
+    let json_content = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize environment profiles: {e}"))?;
+
+    replace_private(path, json_content.as_bytes())
+        .map_err(|e| format!("Failed to write environment profiles: {e}"))
+}
+
+/// Returns a copy of the profile without its secret values.
+pub fn redact(profile: &EnvProfile) -> EnvProfile {
+    let mut profile = profile.clone();
+    for variable in profile.variables.iter_mut().filter(|v| v.secret) {
+        variable.value = None;
+    }
+    profile
+}
+
+/// Fills in the secret values the frontend left out from the saved profile
+/// of the same name.
+pub fn restore_secrets(profile: &mut EnvProfile, saved: Option<&EnvProfile>) -> Result<(), String> {
+    for variable in profile.variables.iter_mut().filter(|v| v.value.is_none()) {
+        let saved_value = saved
+            .filter(|_| variable.secret)
+            .and_then(|saved| saved.variables.iter().find(|v| v.key == variable.key))
+            .and_then(|v| v.value.clone());
+        variable.value =
+            Some(saved_value.ok_or_else(|| format!("Missing value for {}", variable.key))?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EnvVariable;
+
+    fn variable(key: &str, value: Option<&str>, secret: bool) -> EnvVariable {
+        EnvVariable {
+            key: key.to_string(),
+            value: value.map(str::to_string),
+            secret,
+        }
+    }
+
+    fn profile(variables: Vec<EnvVariable>) -> EnvProfile {
+        EnvProfile {
+            name: "work".to_string(),
+            variables,
+        }
+    }
+
+    #[test]
+    fn test_redact_hides_secret_values() {
+        let saved = profile(vec![
+            variable("ANTHROPIC_API_KEY", Some("sk-secret"), true),
+            variable("HTTPS_PROXY", Some("http://proxy:8080"), false),
+        ]);
+
+        let redacted = redact(&saved);
+        assert_eq!(redacted.variables[0].value, None);
+        assert_eq!(
+            redacted.variables[1].value.as_deref(),
+            Some("http://proxy:8080")
+        );
+        assert!(!serde_json::to_string(&redacted)
+            .unwrap()
+            .contains("sk-secret"));
+    }
+
+    #[test]
+    fn test_restore_secrets_keeps_saved_values() {
+        let saved = profile(vec![variable("ANTHROPIC_API_KEY", Some("sk-secret"), true)]);
+
+        let mut edited = redact(&saved);
+        edited
+            .variables
+            .push(variable("HTTPS_PROXY", Some("http://proxy:8080"), false));
+        restore_secrets(&mut edited, Some(&saved)).unwrap();
+        assert_eq!(edited.variables[0].value.as_deref(), Some("sk-secret"));
+
+        // A new secret, or one that is no longer secret, needs a value
+        let mut missing = profile(vec![variable("OTHER_KEY", None, true)]);
+        assert!(restore_secrets(&mut missing, Some(&saved)).is_err());
+        let mut missing = profile(vec![variable("ANTHROPIC_API_KEY", None, false)]);
+        assert!(restore_secrets(&mut missing, Some(&saved)).is_err());
+    }
+
+    #[test]
+    fn test_write_and_read_profiles() {
+        let dir = std::env::temp_dir().join(format!("env-profiles-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("env_profiles.json");
+
+        assert!(read(&path).unwrap().is_empty());
+
+        let profiles = vec![profile(vec![variable("A", Some("1"), true)])];
+        write(&path, &profiles).unwrap();
+        assert_eq!(read(&path).unwrap(), profiles);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+//! Discovery of the user's shells and Claude CLI.
+//!
+//! Used by the frontend to show what is installed before the first session
+//! is spawned. Shells and the CLI are asked for their versions, and the CLI
+//! is looked up on the PATH of the user's login shell (see `login_env`).
+
+mod login_env;
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::pty_manager::{default_allowed_shells, find_program, verify_shell, ShellAllowlist};
+use crate::types::{ClaudeCliInfo, ClaudeInstallMethod, EnvironmentInfo, ShellInfo};
+use login_env::login_shell;
+
+pub use login_env::LoginEnvironment;
+
+/// How long a `--version` check may take
+const VERSION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Detects the installed shells, the login shell and the Claude CLI.
+/// `allowed_shells` is the user's shell allowlist, and `login_env` the
+/// environment of the login shell, if it could be resolved.
+pub fn detect(
+    allowed_shells: &[String],
+    login_env: Option<&HashMap<String, String>>,
+) -> EnvironmentInfo {
+    let allowlist = ShellAllowlist::new(allowed_shells);
+    let mut paths: Vec<String> = allowed_shells.to_vec();
+    for shell in default_allowed_shells() {
+        if !paths.contains(&shell) {
+            paths.push(shell);
+        }
+    }
+
+    let shells = paths
+        .into_iter()
+        .filter(|path| verify_shell(path).is_ok())
+        .map(|path| ShellInfo {
+            name: file_name(&path),
+            version: version(Command::new(&path)),
+            allowed: allowlist.allows(&path),
+            path,
+        })
+        .collect();
+
+    let path = match login_env.and_then(|env| env.get("PATH")) {
+        Some(path) => Some(path.into()),
+        None => {
+            log::warn!("The login shell's PATH is not known, using the app's PATH");
+            std::env::var_os("PATH")
+        }
+    };
+
+    EnvironmentInfo {
+        shells,
+        login_shell: login_shell(),
+        claude: detect_claude(path.as_deref()),
+    }
+}
+
+/// Looks up the Claude CLI on `path` and asks it for its version.
+fn detect_claude(path: Option<&OsStr>) -> Option<ClaudeCliInfo> {
+    let executable = find_program("claude", path)?;
+
+    // The CLI may be a Node.js script, which needs `node` from the same PATH
+    let mut command = Command::new(&executable);
+    if let Some(path) = path {
+        command.env("PATH", path);
+    }
+
+    Some(ClaudeCliInfo {
+        path: executable.to_string_lossy().into_owned(),
+        version: version(command),
+        install_method: install_method(&executable),
+    })
+}
+
+/// Guesses how the Claude CLI was installed from the canonical path of its
+/// executable.
+fn install_method(executable: &Path) -> ClaudeInstallMethod {
+    let path = executable.to_string_lossy();
+    if path.contains("/.claude/local/") {
+        ClaudeInstallMethod::Local
+    } else if path.contains("/node_modules/") {
+        ClaudeInstallMethod::Npm
+    } else if ["/Cellar/", "/Caskroom/", "/linuxbrew/"]
+        .iter()
+        .any(|dir| path.contains(dir))
+    {
+        ClaudeInstallMethod::Homebrew
+    } else if path.contains("/.local/share/claude/") {
+        ClaudeInstallMethod::Native
+    } else {
+        ClaudeInstallMethod::Other
+    }
+}
+
+/// Runs `command --version` and returns the first line of its output.
+fn version(mut command: Command) -> Option<String> {
+    command.arg("--version");
+    let output = run_with_timeout(command, VERSION_TIMEOUT)?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Runs a command with no input and collects its output, giving up after
+/// `timeout`.
+///
+/// On Unix the command runs in a new session, without a controlling
+/// terminal an interactive shell could take over. Its process group is
+/// killed on timeout so that background processes started by shell startup
+/// files do not keep the output pipes open.
+fn run_with_timeout(mut command: Command, timeout: Duration) -> Option<Output> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid(2) is async-signal-safe
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    let child = command
+        .spawn()
+        .inspect_err(|e| log::debug!("Failed to run {:?}: {e}", command.get_program()))
+        .ok()?;
+    let pid = child.id();
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(child.wait_with_output());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(output) => output.ok(),
+        Err(RecvTimeoutError::Timeout) => {
+            log::debug!("{:?} timed out", command.get_program());
+            #[cfg(unix)]
+            // SAFETY: killpg(2) has no memory safety requirements
+            unsafe {
+                libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+            }
+            #[cfg(not(unix))]
+            let _ = pid;
+            None
+        }
+        Err(RecvTimeoutError::Disconnected) => None,
+    }
+}
+
+/// Returns the last component of a path.
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_method() {
+        for (path, method) in [
+            (
+                "/home/u/.local/share/claude/versions/1.0.0",
+                ClaudeInstallMethod::Native,
+            ),
+            ("/home/u/.claude/local/claude", ClaudeInstallMethod::Local),
+            (
+                "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js",
+                ClaudeInstallMethod::Npm,
+            ),
+            (
+                "/opt/homebrew/Caskroom/claude-code/1.0.0/claude",
+                ClaudeInstallMethod::Homebrew,
+            ),
+            ("/usr/bin/claude", ClaudeInstallMethod::Other),
+        ] {
+            assert_eq!(install_method(Path::new(path)), method, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_version_reads_first_line() {
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", "printf '\\nsh 1.2\\nmore\\n'", "sh"]);
+        assert_eq!(version(command).as_deref(), Some("sh 1.2"));
+
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", "exit 2", "sh"]);
+        assert_eq!(version(command), None);
+    }
+
+    #[test]
+    fn test_run_with_timeout_kills_process_group() {
+        let mut command = Command::new("/bin/sh");
+        // The background sleep keeps stdout open after the shell is killed
+        command.args(["-c", "sleep 30 & sleep 30"]);
+
+        let start = std::time::Instant::now();
+        assert!(run_with_timeout(command, Duration::from_millis(200)).is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_detect_lists_allowed_shells() {
+        let info = detect(&["/bin/sh".to_string()], None);
+        let sh = info.shells.iter().find(|s| s.path == "/bin/sh").unwrap();
+        assert!(sh.allowed);
+        assert_eq!(sh.name, "sh");
+    }
+
+    #[test]
+    fn test_detect_matches_allowed_shells_by_canonical_path() {
+        // Another path to the same shell, as with /bin vs /usr/bin or a
+        // symlinked shell
+        let dir = std::env::temp_dir().join(format!("detect-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let link = dir.join("sh");
+        std::os::unix::fs::symlink("/bin/sh", &link).unwrap();
+        let link = link.to_string_lossy().into_owned();
+
+        // /bin/sh is listed from /etc/shells
+        let info = detect(&[link], None);
+        let sh = info.shells.iter().find(|s| s.path == "/bin/sh").unwrap();
+        assert!(sh.allowed, "/bin/sh resolves to the allowed symlink");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
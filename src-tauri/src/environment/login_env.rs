@@ -0,0 +1,178 @@
+//! Environment of the user's login shell.
+//!
+//! Apps started from a desktop launcher do not inherit the environment of
+//! the user's shell, so tools installed through nvm, asdf, mise and the like
+//! are not on their PATH. The login shell is run once, interactively so that
+//! its rc files are read too, and the environment it ends up with is cached
+//! and merged into the environment of new sessions.
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use super::run_with_timeout;
+
+/// How long the login shell may take to print its environment. Startup
+/// files of interactive shells (nvm, conda, ...) can be slow.
+const LOGIN_SHELL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Printed on a line of its own before the environment, so output from
+/// startup files can be told apart
+const ENV_MARKER: &str = "__CLAUDE_GUI_ENV__";
+
+/// Variables that describe the shell that printed the environment rather
+/// than the user's environment
+const IGNORED_VARS: &[&str] = &["_", "PWD", "OLDPWD", "SHLVL"];
+
+/// Cached environment of the user's login shell
+#[derive(Default)]
+pub struct LoginEnvironment {
+    env: Mutex<Option<Arc<HashMap<String, String>>>>,
+}
+
+impl LoginEnvironment {
+    /// Returns the cached environment, or None if it has not been resolved.
+    pub fn get(&self) -> Option<Arc<HashMap<String, String>>> {
+        self.env
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the cached environment.
+    pub fn set(&self, env: HashMap<String, String>) -> Arc<HashMap<String, String>> {
+        let env = Arc::new(env);
+        *self.env.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::clone(&env));
+        env
+    }
+
+    /// Runs the login shell and caches its environment. The cached
+    /// environment is kept if the shell fails.
+    pub fn refresh(&self) -> Result<Arc<HashMap<String, String>>, String> {
+        let shell = login_shell()
+            .or_else(|| std::env::var("SHELL").ok())
+            .ok_or_else(|| "Could not determine the login shell".to_string())?;
+        let env = resolve(&shell)?;
+        log::info!(
+            "Resolved the environment of login shell {shell} ({} variables)",
+            env.len()
+        );
+        Ok(self.set(env))
+    }
+}
+
+/// Returns the user's login shell from the password database.
+#[cfg(unix)]
+pub fn login_shell() -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buf: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        // SAFETY: passwd is plain data, and getpwuid_r only writes to it and
+        // to `buf`, whose length is passed along. The strings it points to
+        // live in `buf`, which outlives their use below.
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        let rc = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut pwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+
+        if rc == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() || pwd.pw_shell.is_null() {
+            return None;
+        }
+
+        // SAFETY: pw_shell is a NUL-terminated string in `buf`
+        let shell = unsafe { CStr::from_ptr(pwd.pw_shell) };
+        let shell = shell.to_string_lossy().into_owned();
+        return (!shell.is_empty()).then_some(shell);
+    }
+}
+
+/// Returns the user's login shell from the password database.
+#[cfg(not(unix))]
+pub fn login_shell() -> Option<String> {
+    None
+}
+
+/// Runs `shell` as an interactive login shell and returns its environment.
+fn resolve(shell: &str) -> Result<HashMap<String, String>, String> {
+    let mut command = Command::new(shell);
+    command.args(["-l", "-i", "-c", &format!("echo {ENV_MARKER}; exec env -0")]);
+    let output = run_with_timeout(command, LOGIN_SHELL_TIMEOUT)
+        .ok_or_else(|| format!("Login shell {shell} failed to run or timed out"))?;
+
+    parse_env(&output.stdout)
+        .ok_or_else(|| format!("Login shell {shell} did not print its environment"))
+}
+
+/// Parses the output of `echo ENV_MARKER; env -0`, skipping anything
+/// printed by startup files before the marker.
+fn parse_env(output: &[u8]) -> Option<HashMap<String, String>> {
+    let marker = format!("{ENV_MARKER}\n");
+    let start = output
+        .windows(marker.len())
+        .position(|window| window == marker.as_bytes())?
+        + marker.len();
+
+    let env = output[start..]
+        .split(|&b| b == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (key, value) = entry.split_once('=')?;
+            (!key.is_empty() && !IGNORED_VARS.contains(&key))
+                .then(|| (key.to_string(), value.to_string()))
+        })
+        .collect();
+    Some(env)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_env_skips_startup_output() {
+        let output = b"Welcome!\n__CLAUDE_GUI_ENV__\nPATH=/usr/bin:/bin\0MULTI=a\nb\0SHLVL=2\0=x\0NOVALUE\0EMPTY=\0";
+        let env = parse_env(output).unwrap();
+
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["PATH"], "/usr/bin:/bin");
+        assert_eq!(env["MULTI"], "a\nb");
+        assert_eq!(env["EMPTY"], "");
+        assert!(!env.contains_key("SHLVL"));
+    }
+
+    #[test]
+    fn test_parse_env_requires_marker() {
+        assert!(parse_env(b"PATH=/usr/bin\0").is_none());
+    }
+
+    #[test]
+    fn test_resolve_login_shell_environment() {
+        let env = resolve("/bin/sh").unwrap();
+        assert!(env.contains_key("PATH"));
+        assert!(!env.contains_key("PWD"));
+
+        assert!(resolve("/nonexistent/shell").is_err());
+    }
+
+    #[test]
+    fn test_set_replaces_cached_environment() {
+        let login_env = LoginEnvironment::default();
+        assert!(login_env.get().is_none());
+
+        login_env.set(HashMap::from([("A".to_string(), "1".to_string())]));
+        assert_eq!(login_env.get().unwrap()["A"], "1");
+    }
+}
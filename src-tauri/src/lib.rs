@@ -6,7 +6,12 @@
 
 mod bindings;
 mod commands;
+mod env_profiles;
+mod environment;
+mod playback;
 mod pty_manager;
+mod redaction;
+mod secrets;
 mod types;
 mod utils;
 
@@ -69,14 +74,12 @@ pub fn run() {
                 })
                 .targets([
                     // Always log to stdout for development
-                    tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Stdout),
+                    redacted_log_target(tauri_plugin_log::TargetKind::Stdout),
                     // Log to webview console for development
-                    tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Webview),
+                    redacted_log_target(tauri_plugin_log::TargetKind::Webview),
                     // Log to system logs on macOS (appears in Console.app)
                     #[cfg(target_os = "macos")]
-                    tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::LogDir {
-                        file_name: None,
-                    }),
+                    redacted_log_target(tauri_plugin_log::TargetKind::LogDir { file_name: None }),
                 ])
                 .build(),
         );
@@ -88,7 +91,9 @@ pub fn run() {
     }
 
     app_builder
-        .manage(std::sync::Mutex::new(pty_manager::PtyManager::new()))
+        .manage(pty_manager::PtyManager::new())
+        .manage(playback::PlaybackManager::default())
+        .manage(env_profiles::ProfilesLock::default())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_persisted_scope::init())
         .plugin(tauri_plugin_dialog::init())
@@ -125,6 +130,57 @@ pub fn run() {
                 )?;
             }
 
+            // Apply the saved redaction settings before any session is spawned
+            let redaction_settings = commands::preferences::load_redaction_settings(app.handle());
+            match redaction::Redactor::new(&redaction_settings) {
+                Ok(redactor) => redaction::set_active(redactor),
+                Err(e) => log::error!("Failed to apply the redaction settings: {e}"),
+            }
+
+            // Apply the saved shell allowlist before any session is spawned
+            let allowed_shells = commands::preferences::load_allowed_shells(app.handle());
+            app.state::<pty_manager::PtyManager>()
+                .set_allowed_shells(&allowed_shells);
+
+            // Load the saved environment profiles
+            let env_profiles = commands::env_profiles::load_env_profiles(app.handle());
+            app.state::<pty_manager::PtyManager>()
+                .set_env_profiles(&env_profiles);
+
+            // Open the secrets vault. Sessions referencing secrets fail to
+            // spawn if it is unavailable.
+            if let Err(e) = commands::secrets::open_secrets_vault(app.handle()) {
+                log::error!("Failed to open the secrets vault: {e}");
+            }
+
+            // Sessions spawned with a transcript write it to the app data
+            match commands::pty::get_transcripts_dir(app.handle()) {
+                Ok(dir) => app
+                    .state::<pty_manager::PtyManager>()
+                    .set_transcripts_dir(dir),
+                Err(e) => log::error!("Failed to set up session transcripts: {e}"),
+            }
+
+            // Shell integration scripts are written to the app data, so they
+            // are reused across runs instead of piling up in the temp dir
+            match commands::pty::get_shell_integration_dir(app.handle()) {
+                Ok(dir) => app
+                    .state::<pty_manager::PtyManager>()
+                    .set_shell_integration_dir(dir),
+                Err(e) => log::error!("Failed to set up shell integration: {e}"),
+            }
+
+            // Resolve the login shell's environment in the background, since
+            // its startup files can take seconds. Sessions spawned before it
+            // is ready get the app's own environment.
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let manager = handle.state::<pty_manager::PtyManager>();
+                if let Err(e) = manager.login_environment().refresh() {
+                    log::warn!("Failed to resolve the login shell environment: {e}");
+                }
+            });
+
             // Create the quick pane window (hidden) - must be done on main thread
             if let Err(e) = commands::quick_pane::init_quick_pane(app.handle()) {
                 log::error!("Failed to create quick pane: {e}");
@@ -140,3 +196,10 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// A log target that redacts secrets from messages.
+fn redacted_log_target(kind: tauri_plugin_log::TargetKind) -> tauri_plugin_log::Target {
+    tauri_plugin_log::Target::new(kind).format(|out, message, _record| {
+        out.finish(format_args!("{}", redaction::redact_log(message)));
+    })
+}
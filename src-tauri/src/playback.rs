@@ -0,0 +1,668 @@
+//! Playback of recorded sessions.
+//!
+//! Streams an asciicast v2 recording (see `pty_manager::recording`) through
+//! a `Channel<PtyEvent>`, the same way a live session's output is streamed,
+//! so the terminal can replay a past session without spawning a shell.
+//!
+//! Each playback runs on its own thread, driven by control messages from
+//! the commands. Seeking resets the terminal and replays the output up to
+//! the new position at once, since terminal output cannot be rewound.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tauri::ipc::Channel;
+use uuid::Uuid;
+
+use crate::types::{PlaybackInfo, PlaybackOptions, PtyEvent};
+
+/// Maximum number of concurrent playbacks
+const MAX_PLAYBACKS: usize = 10;
+
+/// Maximum size of a recording that can be played back (500MB)
+const MAX_RECORDING_BYTES: u64 = 524_288_000;
+
+/// Playback speeds outside this range are clamped to it
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 64.0;
+
+/// Maximum size of an output event sent when seeking (64KB)
+const MAX_SEEK_CHUNK_BYTES: usize = 65_536;
+
+/// Resets the terminal before the output up to a seek position is replayed
+const RESET_TERMINAL: &[u8] = b"\x1bc";
+
+/// A recorded event that is played back.
+#[derive(Debug, Clone, PartialEq)]
+enum EventData {
+    Output(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Event {
+    /// Time of the event in seconds, with idle time skipped
+    time: f64,
+    data: EventData,
+}
+
+/// A parsed recording.
+#[derive(Debug)]
+struct Recording {
+    cols: u16,
+    rows: u16,
+    events: Vec<Event>,
+}
+
+/// Message from a command to a playback thread.
+enum Control {
+    Pause,
+    Resume,
+    /// Seek to a position in seconds
+    Seek(f64),
+    /// Pause and play the next event
+    Step,
+    Stop,
+}
+
+struct Playback {
+    control: Sender<Control>,
+    thread: JoinHandle<()>,
+}
+
+/// Plays back recordings.
+#[derive(Default)]
+pub struct PlaybackManager {
+    playbacks: Mutex<HashMap<String, Playback>>,
+}
+
+impl PlaybackManager {
+    /// Starts playing back the recording at `path`, streaming it to `on_event`.
+    pub fn start(
+        &self,
+        path: &Path,
+        options: PlaybackOptions,
+        on_event: Channel<PtyEvent>,
+    ) -> Result<PlaybackInfo, String> {
+        let size = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read recording: {e}"))?
+            .len();
+        if size > MAX_RECORDING_BYTES {
+            return Err("Recording is too large to play back".to_string());
+        }
+        let contents = std::fs::read(path).map_err(|e| format!("Failed to read recording: {e}"))?;
+        let mut recording = parse(&String::from_utf8_lossy(&contents))?;
+        if let Some(max_idle_ms) = options.max_idle_ms {
+            skip_idle(&mut recording.events, f64::from(max_idle_ms) / 1000.0);
+        }
+
+        let mut playbacks = self.lock();
+        // Playbacks whose channel has gone away stop on their own
+        playbacks.retain(|_, playback| !playback.thread.is_finished());
+        if playbacks.len() >= MAX_PLAYBACKS {
+            return Err(format!(
+                "Maximum number of playbacks reached ({MAX_PLAYBACKS})"
+            ));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let info = PlaybackInfo {
+            id: id.clone(),
+            cols: recording.cols,
+            rows: recording.rows,
+            duration_ms: to_ms(recording.events.last().map_or(0.0, |e| e.time)),
+        };
+
+        let speed = options.speed.unwrap_or(1.0);
+        let speed = if speed.is_finite() {
+            speed.clamp(MIN_SPEED, MAX_SPEED)
+        } else {
+            1.0
+        };
+        let mut player = Player::new(recording, speed, on_event);
+        player.paused = options.paused;
+
+        let (control, control_rx) = mpsc::channel();
+        let thread_id = id.clone();
+        let thread = std::thread::spawn(move || {
+            player.run(&control_rx);
+            log::debug!("Playback finished: {thread_id}");
+        });
+        playbacks.insert(id.clone(), Playback { control, thread });
+
+        log::info!("Playback started: {id} ({})", path.display());
+        Ok(info)
+    }
+
+    pub fn pause(&self, id: &str) -> Result<(), String> {
+        self.send(id, Control::Pause)
+    }
+
+    pub fn resume(&self, id: &str) -> Result<(), String> {
+        self.send(id, Control::Resume)
+    }
+
+    /// Seeks to `position_ms`. A playing playback keeps playing from there.
+    pub fn seek(&self, id: &str, position_ms: u32) -> Result<(), String> {
+        self.send(id, Control::Seek(f64::from(position_ms) / 1000.0))
+    }
+
+    /// Pauses the playback and plays the next event.
+    pub fn step(&self, id: &str) -> Result<(), String> {
+        self.send(id, Control::Step)
+    }
+
+    /// Stops the playback. Nothing more is sent to its channel.
+    pub fn stop(&self, id: &str) -> Result<(), String> {
+        let playback = self
+            .lock()
+            .remove(id)
+            .ok_or_else(|| format!("Playback not found: {id}"))?;
+        let _ = playback.control.send(Control::Stop);
+        log::info!("Playback stopped: {id}");
+        Ok(())
+    }
+
+    fn send(&self, id: &str, control: Control) -> Result<(), String> {
+        let mut playbacks = self.lock();
+        let playback = playbacks
+            .get(id)
+            .ok_or_else(|| format!("Playback not found: {id}"))?;
+        if playback.control.send(control).is_err() {
+            playbacks.remove(id);
+            return Err(format!("Playback has stopped: {id}"));
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Playback>> {
+        self.playbacks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for PlaybackManager {
+    fn drop(&mut self) {
+        for playback in self.lock().values() {
+            let _ = playback.control.send(Control::Stop);
+        }
+    }
+}
+
+/// Playback state, owned by the playback thread.
+struct Player {
+    events: Vec<Event>,
+    /// Terminal size from the header, before any resize event
+    size: (u16, u16),
+    /// Index of the next event to play
+    next: usize,
+    /// Position in seconds, as of `anchor`
+    position: f64,
+    /// When `position` was reached, while playing
+    anchor: Instant,
+    speed: f64,
+    paused: bool,
+    /// Sequence number of the last output event sent
+    seq: u32,
+    channel: Channel<PtyEvent>,
+}
+
+impl Player {
+    fn new(recording: Recording, speed: f64, channel: Channel<PtyEvent>) -> Self {
+        Self {
+            events: recording.events,
+            size: (recording.cols, recording.rows),
+            next: 0,
+            position: 0.0,
+            anchor: Instant::now(),
+            speed,
+            paused: false,
+            seq: 0,
+            channel,
+        }
+    }
+
+    /// Plays the recording until it is stopped or the channel goes away.
+    fn run(&mut self, control: &Receiver<Control>) {
+        if self.send_state().is_err() {
+            return;
+        }
+        self.anchor = Instant::now();
+
+        loop {
+            let deadline = match self.events.get(self.next) {
+                Some(event) if !self.paused => self.deadline(event.time),
+                _ => None,
+            };
+            let message = match deadline {
+                Some(deadline) => {
+                    match control.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(message) => message,
+                        Err(RecvTimeoutError::Timeout) => {
+                            if self.play_next().is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                // Paused, at the end or the next event is too far away to
+                // wait for: wait for a command
+                None => match control.recv() {
+                    Ok(message) => message,
+                    Err(_) => return,
+                },
+            };
+
+            let result = match message {
+                Control::Pause => {
+                    self.position = self.current_position();
+                    self.paused = true;
+                    self.send_state()
+                }
+                Control::Resume => {
+                    self.paused = false;
+                    self.anchor = Instant::now();
+                    self.send_state()
+                }
+                Control::Seek(position) => self.seek(position),
+                Control::Step => {
+                    self.paused = true;
+                    match self.events.get(self.next) {
+                        Some(_) => self.play_next(),
+                        None => self.send_state(),
+                    }
+                }
+                Control::Stop => return,
+            };
+            if result.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// When the event at `time` is due while playing, or None if that is
+    /// too far away to be represented.
+    fn deadline(&self, time: f64) -> Option<Instant> {
+        let wait = (time - self.position).max(0.0) / self.speed;
+        Duration::try_from_secs_f64(wait)
+            .ok()
+            .and_then(|wait| self.anchor.checked_add(wait))
+    }
+
+    /// Position in seconds, accounting for the time played since `anchor`.
+    fn current_position(&self) -> f64 {
+        if self.paused {
+            return self.position;
+        }
+        let end = self
+            .events
+            .get(self.next)
+            .map_or(self.position, |event| event.time);
+        (self.position + self.anchor.elapsed().as_secs_f64() * self.speed).min(end)
+    }
+
+    /// Plays the next event and moves the position to its time.
+    fn play_next(&mut self) -> tauri::Result<()> {
+        let event = self.events[self.next].clone();
+        self.next += 1;
+        self.position = event.time;
+        self.anchor = Instant::now();
+        self.send_event(event.data)?;
+
+        if self.paused || self.next == self.events.len() {
+            self.send_state()?;
+        }
+        Ok(())
+    }
+
+    /// Resets the terminal and replays everything up to `position` at once,
+    /// starting with the terminal size at that position.
+    fn seek(&mut self, position: f64) -> tauri::Result<()> {
+        let end = self.events.last().map_or(0.0, |e| e.time);
+        let position = position.clamp(0.0, end);
+        let next = self.events.partition_point(|e| e.time <= position);
+
+        let mut output = RESET_TERMINAL.to_vec();
+        let (mut cols, mut rows) = self.size;
+        for event in &self.events[..next] {
+            match &event.data {
+                EventData::Output(data) => output.extend_from_slice(data),
+                EventData::Resize { cols: c, rows: r } => (cols, rows) = (*c, *r),
+            }
+        }
+
+        self.send_event(EventData::Resize { cols, rows })?;
+        // Chunks may split a character or escape sequence, which the
+        // frontend's streaming decoder and xterm handle
+        for chunk in output.chunks(MAX_SEEK_CHUNK_BYTES) {
+            self.send_event(EventData::Output(chunk.to_vec()))?;
+        }
+
+        self.next = next;
+        self.position = position;
+        self.anchor = Instant::now();
+        self.send_state()
+    }
+
+    fn send_event(&mut self, data: EventData) -> tauri::Result<()> {
+        let event = match data {
+            EventData::Output(data) => {
+                self.seq = self.seq.wrapping_add(1);
+                PtyEvent::Output {
+                    data,
+                    seq: self.seq,
+                }
+            }
+            EventData::Resize { cols, rows } => PtyEvent::Resize { cols, rows },
+        };
+        self.channel.send(event)
+    }
+
+    fn send_state(&self) -> tauri::Result<()> {
+        self.channel.send(PtyEvent::PlaybackState {
+            position_ms: to_ms(self.current_position()),
+            paused: self.paused,
+            finished: self.next == self.events.len(),
+        })
+    }
+}
+
+/// Parses an asciicast v2 recording. Input and marker events are skipped,
+/// as are malformed lines, such as a last line cut off by a crash.
+fn parse(contents: &str) -> Result<Recording, String> {
+    let mut lines = contents.lines();
+    let header: Value = lines
+        .next()
+        .and_then(|line| serde_json::from_str(line).ok())
+        .ok_or_else(|| "Invalid recording: missing header".to_string())?;
+    if header["version"] != 2 {
+        return Err("Unsupported recording: only asciicast v2 is supported".to_string());
+    }
+    let size = |key: &str| header[key].as_u64().and_then(|n| u16::try_from(n).ok());
+    let (Some(cols), Some(rows)) = (size("width"), size("height")) else {
+        return Err("Invalid recording: missing terminal size".to_string());
+    };
+
+    let mut events = Vec::new();
+    let mut skipped = 0;
+    let mut last_time: f64 = 0.0;
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let Some((time, data)) = parse_event(line) else {
+            skipped += 1;
+            continue;
+        };
+        if let Some(data) = data {
+            // Times must not go backwards
+            last_time = last_time.max(time);
+            events.push(Event {
+                time: last_time,
+                data,
+            });
+        }
+    }
+    if skipped > 0 {
+        log::warn!("Skipped {skipped} malformed events in recording");
+    }
+
+    Ok(Recording { cols, rows, events })
+}
+
+/// Parses an event line. Returns None for a malformed line, and no data for
+/// events that are not played back.
+fn parse_event(line: &str) -> Option<(f64, Option<EventData>)> {
+    let Value::Array(fields) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    let [time, kind, data] = fields.as_slice() else {
+        return None;
+    };
+    let time = time.as_f64().filter(|t| t.is_finite() && *t >= 0.0)?;
+    let data = data.as_str()?;
+
+    let data = match kind.as_str()? {
+        "o" => Some(EventData::Output(data.as_bytes().to_vec())),
+        "r" => {
+            let (cols, rows) = data.split_once('x')?;
+            Some(EventData::Resize {
+                cols: cols.parse().ok()?,
+                rows: rows.parse().ok()?,
+            })
+        }
+        _ => None,
+    };
+    Some((time, data))
+}
+
+/// Shortens pauses between events to at most `max_idle` seconds.
+fn skip_idle(events: &mut [Event], max_idle: f64) {
+    let mut previous = 0.0;
+    let mut skipped = 0.0;
+    for event in events {
+        let gap = event.time - previous;
+        previous = event.time;
+        if gap > max_idle {
+            skipped += gap - max_idle;
+        }
+        event.time -= skipped;
+    }
+}
+
+fn to_ms(seconds: f64) -> u32 {
+    (seconds * 1000.0).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::ipc::InvokeResponseBody;
+
+    const RECORDING: &str = r#"{"version":2,"width":80,"height":24,"timestamp":0}
+[0.5,"o","hello "]
+[1.0,"i","ignored"]
+[1.5,"r","100x30"]
+not an event
+[10.0,"o","world"]
+[10.5,"o","trunc"#;
+
+    fn test_channel() -> (Channel<PtyEvent>, mpsc::Receiver<PtyEvent>) {
+        let (tx, rx) = mpsc::channel();
+        let channel = Channel::new(move |body: InvokeResponseBody| {
+            if let InvokeResponseBody::Json(json_str) = body {
+                if let Ok(event) = serde_json::from_str::<PtyEvent>(&json_str) {
+                    let _ = tx.send(event);
+                }
+            }
+            Ok(())
+        });
+        (channel, rx)
+    }
+
+    fn output(text: &str) -> EventData {
+        EventData::Output(text.as_bytes().to_vec())
+    }
+
+    /// Collects events until the playback reports its state.
+    fn events_until_state(rx: &mpsc::Receiver<PtyEvent>) -> (Vec<PtyEvent>, PtyEvent) {
+        let mut events = Vec::new();
+        loop {
+            match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+                state @ PtyEvent::PlaybackState { .. } => return (events, state),
+                event => events.push(event),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_recording() {
+        let recording = parse(RECORDING).unwrap();
+        assert_eq!((recording.cols, recording.rows), (80, 24));
+        let events: Vec<(f64, EventData)> = recording
+            .events
+            .into_iter()
+            .map(|e| (e.time, e.data))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (0.5, output("hello ")),
+                (
+                    1.5,
+                    EventData::Resize {
+                        cols: 100,
+                        rows: 30
+                    }
+                ),
+                (10.0, output("world")),
+            ]
+        );
+
+        assert!(parse(r#"{"version":1,"width":80,"height":24}"#).is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn test_skip_idle() {
+        let mut events: Vec<Event> = [0.5, 1.0, 10.0, 10.5, 20.0]
+            .into_iter()
+            .map(|time| Event {
+                time,
+                data: output("x"),
+            })
+            .collect();
+        skip_idle(&mut events, 2.0);
+        let times: Vec<f64> = events.iter().map(|e| e.time).collect();
+        assert_eq!(times, vec![0.5, 1.0, 3.0, 3.5, 5.5]);
+    }
+
+    #[test]
+    fn test_seek_and_step() {
+        let recording = parse(RECORDING).unwrap();
+        let (channel, rx) = test_channel();
+        let mut player = Player::new(recording, 1.0, channel);
+        player.paused = true;
+
+        player.seek(5.0).unwrap();
+        let (events, state) = events_until_state(&rx);
+        let PtyEvent::Resize {
+            cols: 100,
+            rows: 30,
+        } = events[0]
+        else {
+            panic!("Expected a resize first: {events:?}");
+        };
+        let PtyEvent::Output { data, .. } = &events[1] else {
+            panic!("Expected output: {events:?}");
+        };
+        assert_eq!(data, b"\x1bchello ");
+        let PtyEvent::PlaybackState {
+            position_ms: 5000,
+            paused: true,
+            finished: false,
+        } = state
+        else {
+            panic!("Unexpected state: {state:?}");
+        };
+
+        // Stepping plays the next event and moves to its time
+        player.play_next().unwrap();
+        let (events, state) = events_until_state(&rx);
+        let PtyEvent::Output { data, .. } = &events[0] else {
+            panic!("Expected output: {events:?}");
+        };
+        assert_eq!(data, b"world");
+        assert!(matches!(
+            state,
+            PtyEvent::PlaybackState {
+                position_ms: 10000,
+                finished: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_seek_back_before_resize_restores_header_size() {
+        let recording = parse(RECORDING).unwrap();
+        let (channel, rx) = test_channel();
+        let mut player = Player::new(recording, 1.0, channel);
+        player.paused = true;
+
+        player.seek(5.0).unwrap();
+        events_until_state(&rx);
+
+        player.seek(1.0).unwrap();
+        let (events, _) = events_until_state(&rx);
+        let PtyEvent::Resize { cols: 80, rows: 24 } = events[0] else {
+            panic!("Expected the header size first: {events:?}");
+        };
+        let PtyEvent::Output { data, .. } = &events[1] else {
+            panic!("Expected output: {events:?}");
+        };
+        assert_eq!(data, b"\x1bchello ");
+    }
+
+    #[test]
+    fn test_distant_event_does_not_panic() {
+        let recording =
+            parse("{\"version\":2,\"width\":80,\"height\":24}\n[1e20,\"o\",\"late\"]\n").unwrap();
+        let (channel, rx) = test_channel();
+        let (control_tx, control_rx) = mpsc::channel();
+        let player = std::thread::spawn(move || {
+            Player::new(recording, 1.0, channel).run(&control_rx);
+        });
+
+        let (_, state) = events_until_state(&rx);
+        assert!(matches!(
+            state,
+            PtyEvent::PlaybackState { paused: false, .. }
+        ));
+        control_tx.send(Control::Stop).unwrap();
+        player.join().expect("Playback thread panicked");
+    }
+
+    #[test]
+    fn test_playback_streams_recording() {
+        let dir = std::env::temp_dir().join(format!("playback-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("recording.cast");
+        std::fs::write(&path, RECORDING).unwrap();
+
+        let manager = PlaybackManager::default();
+        let (channel, rx) = test_channel();
+        let options = PlaybackOptions {
+            speed: Some(4.0),
+            max_idle_ms: Some(500),
+            paused: false,
+        };
+        let start = Instant::now();
+        let info = manager.start(&path, options, channel).unwrap();
+        assert_eq!((info.cols, info.rows), (80, 24));
+        assert_eq!(info.duration_ms, 1500);
+
+        let mut output = String::new();
+        loop {
+            match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+                PtyEvent::Output { data, .. } => output.push_str(&String::from_utf8_lossy(&data)),
+                PtyEvent::PlaybackState { finished: true, .. } => break,
+                _ => {}
+            }
+        }
+        assert_eq!(output, "hello world");
+        // 1.5 seconds at 4x speed
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed >= Duration::from_millis(350) && elapsed < Duration::from_secs(3),
+            "Playback took {elapsed:?}"
+        );
+
+        manager.stop(&info.id).unwrap();
+        assert!(manager.pause(&info.id).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
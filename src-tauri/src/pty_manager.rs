@@ -1,48 +1,119 @@
 //! PTY session manager using portable-pty.
 //!
 //! Manages pseudo-terminal sessions for the terminal UI.
-//! Each session runs a shell process and streams output via Tauri Channel API.
+//! Each session runs a shell process, or an allowed program directly, and
+//! streams output via Tauri Channel API.
+//!
+//! # Locking
+//!
+//! The manager lock only guards the session map. Each session locks its
+//! master and killer separately, and its input is queued for a writer
+//! thread, so a write blocked by backpressure on one session never stalls
+//! commands for other sessions, and writes are never reordered.
 //!
 //! # Security
 //!
-//! - Shell commands are validated against a whitelist of known shells
+//! - Shell commands are validated against the user's shell allowlist,
+//!   compared by canonical path
+//! - Programs run without a shell are limited to a built-in list, looked up
+//!   by name, and their arguments are validated per program
 //! - Working directories are canonicalized to prevent path traversal
-//! - Dangerous environment variables (LD_PRELOAD, etc.) are blocked
+//! - Dangerous environment variables (LD_PRELOAD, etc.) are blocked, both
+//!   from the frontend and from the login shell's environment
 //! - Maximum session limit prevents resource exhaustion
 
+mod coalesce;
+mod foreground;
+mod framing;
+mod osc;
+mod process;
+mod programs;
+mod recording;
+mod screen;
+mod scrollback;
+mod search;
+mod shell_integration;
+mod shells;
+mod transcript;
+
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::io::{Read, Write};
 use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
 use tauri::ipc::Channel;
 use uuid::Uuid;
 
-use crate::types::{PtyError, PtyEvent, SessionInfo, SpawnOptions};
+use crate::environment::LoginEnvironment;
+use crate::redaction::{self, RedactStream};
+use crate::secrets::SecretsVault;
+use crate::types::{
+    AttachInfo, EnvProfile, ForegroundProcess, KillOptions, PtyError, PtyEvent, RecordOptions,
+    RecordingInfo, ScreenSnapshot, ScrollbackSnapshot, SearchMatch, SearchOptions, SearchSource,
+    SessionInfo, Signal, SnapshotFormat, SpawnOptions,
+};
+use crate::utils::platform::normalize_path_for_serialization;
+use coalesce::Coalescer;
+use framing::Framer;
+use osc::OscScanner;
+use process::{kill_process, wait_for_exit, ProcessExit};
+use recording::Recorder;
+use screen::Screen;
+use scrollback::Scrollback;
+use search::SessionSearch;
+use shell_integration::CommandTracker;
+use transcript::Transcript;
+
+pub use programs::find_program;
+pub use shells::{default_allowed_shells, verify_shell, ShellAllowlist};
 
 /// Size of the read buffer for PTY output (4KB)
 const READ_BUFFER_SIZE: usize = 4096;
 
+/// How long streaming output is collected before it is sent as one event
+const COALESCE_WINDOW: Duration = Duration::from_millis(8);
+
+/// Maximum size of a coalesced output event (64KB)
+const MAX_BATCH_BYTES: usize = 65_536;
+
+/// How long an incomplete UTF-8 character or escape sequence at the end of
+/// the output, or the possible start of a secret when live output is
+/// redacted, is held back before it is sent anyway
+const HOLD_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// How often the foreground process of each session is checked
+const FOREGROUND_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long killed sessions are given to exit before they are killed forcefully
+const DEFAULT_KILL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest time killed sessions can be given to exit
+const MAX_KILL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often terminating sessions are checked for exit
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Default scrollback buffer size per session (1MB)
+const DEFAULT_SCROLLBACK_BYTES: u32 = 1_048_576;
+
+/// Maximum configurable scrollback buffer size per session (16MB)
+const MAX_SCROLLBACK_BYTES: u32 = 16_777_216;
+
+/// Maximum input queued for a session that is not reading it (16MB)
+const MAX_QUEUED_INPUT_BYTES: usize = 16_777_216;
+
 /// Maximum number of concurrent PTY sessions
 const MAX_SESSIONS: usize = 10;
 
-/// Allowed shell commands (absolute paths only)
-const ALLOWED_SHELLS: &[&str] = &[
-    "/bin/bash",
-    "/bin/zsh",
-    "/bin/sh",
-    "/bin/fish",
-    "/usr/bin/bash",
-    "/usr/bin/zsh",
-    "/usr/bin/fish",
-    "/usr/local/bin/bash",
-    "/usr/local/bin/zsh",
-    "/usr/local/bin/fish",
-    "/opt/homebrew/bin/bash",
-    "/opt/homebrew/bin/zsh",
-    "/opt/homebrew/bin/fish",
-];
+/// Maximum length of a user-assigned session title (in characters)
+const MAX_TITLE_LENGTH: usize = 200;
 
 /// Environment variables that must not be overridden by the frontend
 const BLOCKED_ENV_VARS: &[&str] = &[
@@ -53,46 +124,320 @@ const BLOCKED_ENV_VARS: &[&str] = &[
     "DYLD_FALLBACK_LIBRARY_PATH",
 ];
 
+/// Message sent from the reader thread to the emitter thread.
+enum ReaderMessage {
+    /// Raw output read from the PTY
+    Data(Vec<u8>),
+    /// The reader stopped, with the read error if there was one
+    Finished(Option<String>),
+}
+
+/// Output state shared between a session and its emitter and reaper threads.
+///
+/// Scrollback and channel live under one lock so that replaying buffered
+/// output on attach cannot interleave with live output from the emitter.
+struct SessionOutput {
+    /// Recent output, replayed to newly attached channels
+    scrollback: Scrollback,
+    /// Headless terminal model, if screen tracking is enabled
+    screen: Option<Screen>,
+    /// Finds OSC sequences in the output
+    osc: OscScanner,
+    /// Turns OSC 133 marks into command events
+    commands: CommandTracker,
+    /// Current working directory, as last reported by the shell (OSC 7)
+    cwd: Option<String>,
+    /// Title last set by the program in the terminal (OSC 0/2)
+    terminal_title: Option<String>,
+    /// Channel of the attached frontend, or None once it has gone away
+    channel: Option<Channel<PtyEvent>>,
+    /// Final event (Exit or Error) sent when the session stopped,
+    /// replayed to channels attached afterwards
+    final_event: Option<PtyEvent>,
+    /// Whether the reader thread has stopped (EOF or read error)
+    reader_done: bool,
+    /// Exit status of the child, once reaped
+    exit: Option<ProcessExit>,
+    /// Transcript the output is written to, if enabled. Shared so that it
+    /// can be synced without holding this lock.
+    transcript: Option<Arc<Mutex<Transcript>>>,
+}
+
+impl SessionOutput {
+    /// Sends an event to the attached channel, detaching it if the send fails.
+    /// The session keeps running and buffering output until a new channel attaches.
+    fn send(&mut self, event: PtyEvent) {
+        if let Some(channel) = &self.channel {
+            if let Err(e) = channel.send(event) {
+                log::warn!("PTY channel.send failed, detaching channel: {e:?}");
+                self.channel = None;
+            }
+        }
+    }
+
+    /// Buffers output and forwards it to the attached channel.
+    ///
+    /// Output is split after each shell integration mark, so the resulting
+    /// event is sent right after the output that preceded it.
+    fn push_output(&mut self, data: Vec<u8>) {
+        if let Some(screen) = &mut self.screen {
+            screen.process(&data);
+        }
+
+        let now = Instant::now();
+        let mut marks = Vec::new();
+        for sequence in self.osc.scan(&data) {
+            if let Some(event) = self.handle_osc(&sequence.payload, now) {
+                marks.push((sequence.end, event));
+            }
+        }
+
+        if marks.is_empty() {
+            self.push_chunk(data);
+            return;
+        }
+
+        let mut start = 0;
+        for (end, event) in marks {
+            self.push_chunk(data[start..end].to_vec());
+            start = end;
+            if let Some(transcript) = &self.transcript {
+                lock(transcript).mark(&event);
+            }
+            self.send(event);
+        }
+        if start < data.len() {
+            self.push_chunk(data[start..].to_vec());
+        }
+    }
+
+    /// Interprets an OSC sequence from the output, returning the event it
+    /// results in, if any.
+    fn handle_osc(&mut self, payload: &str, now: Instant) -> Option<PtyEvent> {
+        if let Some(cwd) = osc::working_directory(payload) {
+            // Shells report the directory before every prompt
+            if self.cwd.as_deref() == Some(cwd.as_str()) {
+                return None;
+            }
+            self.cwd = Some(cwd.clone());
+            return Some(PtyEvent::CwdChanged { cwd });
+        }
+
+        if let Some(title) = osc::window_title(payload) {
+            // Prompts commonly set the same title every time they are drawn
+            let current = self.terminal_title.as_deref().unwrap_or("");
+            if current == title {
+                return None;
+            }
+            self.terminal_title = (!title.is_empty()).then(|| title.clone());
+            return Some(PtyEvent::TitleChanged { title });
+        }
+
+        self.commands.handle(payload, now)
+    }
+
+    /// Buffers a chunk of output and forwards it to the attached channel.
+    fn push_chunk(&mut self, data: Vec<u8>) {
+        if let Some(transcript) = &self.transcript {
+            lock(transcript).write(&data);
+        }
+        let seq = self.scrollback.push(&data);
+        self.send(PtyEvent::Output { data, seq });
+    }
+
+    /// Records the final event of the session and forwards it.
+    fn finish(&mut self, event: PtyEvent) {
+        self.final_event = Some(event.clone());
+        self.send(event);
+    }
+
+    /// Marks the reader as stopped, forwarding the read error if there was one.
+    fn reader_finished(&mut self, error: Option<String>) {
+        self.reader_done = true;
+        if let Some(transcript) = self.transcript.take() {
+            lock(&transcript).finish();
+        }
+        if let Some(message) = error {
+            self.finish(PtyEvent::Error { message });
+        }
+        self.send_exit_if_ready();
+    }
+
+    /// Records the exit status of the reaped child process.
+    fn process_exited(&mut self, exit: ProcessExit) {
+        self.exit = Some(exit);
+        self.send_exit_if_ready();
+    }
+
+    /// Sends Exit once the child has been reaped and the reader has drained
+    /// all output, so that Exit is always the last event of a session.
+    fn send_exit_if_ready(&mut self) {
+        if !self.reader_done {
+            return;
+        }
+        if let Some(exit) = &self.exit {
+            let event = PtyEvent::Exit {
+                code: exit.code,
+                signal: exit.signal.clone(),
+            };
+            self.finish(event);
+        }
+    }
+}
+
 /// An active PTY session with its associated resources.
+///
+/// Shared as `Arc<PtySession>` so commands can operate on a session
+/// without holding the session map lock.
 pub struct PtySession {
-    /// Writer half of the PTY master (for sending input)
-    writer: Box<dyn Write + Send>,
-    /// Child process handle
-    child: Box<dyn Child + Send + Sync>,
+    /// Input queued for the writer thread, which owns the writer half of
+    /// the PTY master
+    input: mpsc::Sender<Vec<u8>>,
+    /// Number of bytes queued for the writer thread that are not written yet
+    queued_input: Arc<AtomicUsize>,
+    /// Handle for terminating the child process.
+    /// The child itself is owned by the reaper thread.
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    /// Whether the reaper has reaped the child, after which it must not be
+    /// signalled. See `process`.
+    reaped: Arc<Mutex<bool>>,
     /// Handle to the reader thread.
     /// Intentionally detached on drop — the thread exits naturally when the
     /// master PTY is dropped (EOF/EIO on read). Joining is not required.
     _reader_thread: JoinHandle<()>,
-    /// Process ID (used by list(), will be exposed via future commands)
-    #[allow(dead_code)]
+    /// Handle to the emitter thread, which batches output from the reader.
+    /// Intentionally detached on drop — the thread exits once the reader stops.
+    _emitter_thread: JoinHandle<()>,
+    /// Handle to the writer thread, which writes queued input in order.
+    /// Intentionally detached on drop — the thread exits once the input
+    /// queue is closed and a blocked write has failed.
+    _writer_thread: JoinHandle<()>,
+    /// Handle to the reaper thread, which waits for the child to exit.
+    /// Intentionally detached on drop — the thread exits once the child is reaped.
+    _reaper_thread: JoinHandle<()>,
+    /// Foreground watcher thread handle.
+    /// Exits once the child is reaped or the session is dropped.
+    _foreground_thread: JoinHandle<()>,
+    /// Process ID of the child
     pid: Option<u32>,
-    /// The master PTY handle (kept alive to prevent EOF)
-    _master: Box<dyn MasterPty + Send>,
+    /// Executable the session was spawned with (shell or resolved program)
+    shell: String,
+    /// Program the session runs directly, if it is not a shell session
+    program: Option<String>,
+    /// Arguments passed to the executable
+    args: Vec<String>,
+    /// Working directory the session was started in
+    cwd: Option<String>,
+    /// When the session was spawned
+    created_at: SystemTime,
+    /// User-assigned title
+    title: Mutex<Option<String>>,
+    /// The master PTY handle (kept alive to prevent EOF, used for resizing).
+    /// The foreground watcher only holds a weak reference, so dropping the
+    /// session still closes the master.
+    master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    /// Scrollback and attached channel, shared with the emitter thread
+    output: Arc<Mutex<SessionOutput>>,
+    /// Recording in progress, shared with the reader thread
+    recording: Arc<Mutex<Option<Recorder>>>,
 }
 
 /// Manages multiple PTY sessions.
 pub struct PtyManager {
-    sessions: HashMap<String, PtySession>,
+    sessions: Mutex<HashMap<String, Arc<PtySession>>>,
+    /// Shells sessions may be spawned with, from the user's preferences
+    allowed_shells: Mutex<ShellAllowlist>,
+    /// Environment of the user's login shell, merged into new sessions
+    login_env: LoginEnvironment,
+    /// Variables of the saved environment profiles, by profile name
+    env_profiles: Mutex<HashMap<String, HashMap<String, String>>>,
+    /// Secrets that session environments can reference
+    secrets: SecretsVault,
+    /// Directory session transcripts are written to, once known
+    transcripts_dir: Mutex<Option<PathBuf>>,
+    /// Directory the shell integration scripts are written to, once known
+    shell_integration_dir: Mutex<Option<PathBuf>>,
 }
 
 impl PtyManager {
     pub fn new() -> Self {
         Self {
-            sessions: HashMap::new(),
+            sessions: Mutex::new(HashMap::new()),
+            allowed_shells: Mutex::new(ShellAllowlist::new(&default_allowed_shells())),
+            login_env: LoginEnvironment::default(),
+            env_profiles: Mutex::new(HashMap::new()),
+            secrets: SecretsVault::default(),
+            transcripts_dir: Mutex::new(None),
+            shell_integration_dir: Mutex::new(None),
         }
     }
 
+    /// Replaces the environment profiles sessions can be spawned with.
+    /// Applies to sessions spawned afterwards.
+    pub fn set_env_profiles(&self, profiles: &[EnvProfile]) {
+        *lock(&self.env_profiles) = profiles
+            .iter()
+            .map(|profile| {
+                let variables = profile
+                    .variables
+                    .iter()
+                    .filter_map(|v| Some((v.key.clone(), v.value.clone()?)))
+                    .collect();
+                (profile.name.clone(), variables)
+            })
+            .collect();
+    }
+
+    /// Cache of the login shell's environment. It starts out empty and is
+    /// filled by `LoginEnvironment::refresh`; sessions spawned before then
+    /// only get the app's own environment.
+    pub fn login_environment(&self) -> &LoginEnvironment {
+        &self.login_env
+    }
+
+    /// Vault of the secrets referenced by `${secret:NAME}` environment
+    /// values. Until it is opened, sessions cannot use secrets.
+    pub fn secrets(&self) -> &SecretsVault {
+        &self.secrets
+    }
+
+    /// Sets the directory session transcripts are written to. Until it is
+    /// set, sessions cannot be spawned with a transcript.
+    pub fn set_transcripts_dir(&self, dir: PathBuf) {
+        *lock(&self.transcripts_dir) = Some(dir);
+    }
+
+    /// Sets the directory the shell integration scripts are written to.
+    /// Until it is set, shell integration is not loaded.
+    pub fn set_shell_integration_dir(&self, dir: PathBuf) {
+        *lock(&self.shell_integration_dir) = Some(dir);
+    }
+
+    /// Replaces the shell allowlist. Applies to sessions spawned afterwards.
+    pub fn set_allowed_shells(&self, shells: &[String]) {
+        *lock(&self.allowed_shells) = ShellAllowlist::new(shells);
+    }
+
+    /// Looks up a session. The session map is only locked for the lookup.
+    fn get(&self, session_id: &str) -> Result<Arc<PtySession>, PtyError> {
+        lock(&self.sessions)
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| PtyError::SessionNotFound {
+                session_id: session_id.to_string(),
+            })
+    }
+
     /// Spawns a new PTY session and starts streaming output via the channel.
     pub fn spawn(
-        &mut self,
+        &self,
         options: SpawnOptions,
         on_event: Channel<PtyEvent>,
     ) -> Result<String, PtyError> {
-        // Enforce session limit
-        if self.sessions.len() >= MAX_SESSIONS {
-            return Err(PtyError::ResourceLimit {
-                message: format!("Maximum number of sessions ({MAX_SESSIONS}) reached"),
-            });
+        // Enforce session limit (checked again on insert, since the map
+        // is not locked while the process is being spawned)
+        if lock(&self.sessions).len() >= MAX_SESSIONS {
+            return Err(session_limit_error());
         }
 
         let session_id = Uuid::new_v4().to_string();
@@ -110,109 +455,234 @@ impl PtyManager {
                 message: e.to_string(),
             })?;
 
-        // Determine and validate shell command
-        let shell = options
-            .command
-            .unwrap_or_else(|| std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string()));
+        // Environment of the user's login shell, if it has been resolved
+        let login_env = self.login_env.get();
+
+        // Determine and validate the executable: an allowed program, which
+        // is subject to the program policy rather than the shell allowlist,
+        // or a shell. Programs are looked up on the login shell's PATH.
+        let shell = if let Some(program) = &options.program {
+            if options.command.is_some() {
+                return Err(PtyError::ValidationError {
+                    message: "A session cannot have both a command and a program".to_string(),
+                });
+            }
+            programs::validate(program, &options.args)
+                .map_err(|message| PtyError::ValidationError { message })?;
+            let search_path = login_env
+                .as_ref()
+                .and_then(|env| env.get("PATH"))
+                .map(OsString::from)
+                .or_else(|| std::env::var_os("PATH"));
+            let path = find_program(program, search_path.as_deref()).ok_or_else(|| {
+                PtyError::SpawnError {
+                    message: format!("Program not found: {program}"),
+                }
+            })?;
+            normalize_path_for_serialization(&path)
+        } else {
+            let shell = options.command.unwrap_or_else(|| {
+                std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string())
+            });
+            if !lock(&self.allowed_shells).allows(&shell) {
+                return Err(PtyError::ValidationError {
+                    message: format!("Shell not allowed: {shell}"),
+                });
+            }
+            shell
+        };
+
+        let profile_env = match &options.profile {
+            Some(name) => Some(lock(&self.env_profiles).get(name).cloned().ok_or_else(|| {
+                PtyError::ValidationError {
+                    message: format!("Unknown environment profile: {name}"),
+                }
+            })?),
+            None => None,
+        };
 
-        validate_shell(&shell)?;
+        // Secret references are only resolved here, so that secret values
+        // never pass through the frontend
+        let resolve = |env: &HashMap<String, String>| {
+            self.secrets
+                .resolve_env(env)
+                .map_err(|message| PtyError::ValidationError { message })
+        };
+        let profile_env = profile_env.as_ref().map(resolve).transpose()?;
+        let env = resolve(&options.env)?;
+
+        let scrollback_limit = options
+            .scrollback_limit
+            .unwrap_or(DEFAULT_SCROLLBACK_BYTES)
+            .clamp(READ_BUFFER_SIZE as u32, MAX_SCROLLBACK_BYTES);
+
+        let injection = if !options.shell_integration {
+            None
+        } else if options.program.is_some() || !options.args.is_empty() {
+            log::warn!("Shell integration is only loaded for shells without arguments");
+            None
+        } else if let Some(dir) = lock(&self.shell_integration_dir).clone() {
+            let user_zdotdir = user_zdotdir(&env, profile_env.as_ref(), login_env.as_deref());
+            shell_integration::injection(&shell, &dir, user_zdotdir.as_deref())
+        } else {
+            log::warn!("Shell integration is not available");
+            None
+        };
 
-        let mut cmd = CommandBuilder::new(&shell);
-        if options.args.is_empty() {
+        let mut integration_env = Vec::new();
+        let args = if options.program.is_some() {
+            options.args
+        } else if let Some(injection) = injection {
+            // The integration scripts load the login startup files themselves
+            integration_env = injection.env;
+            injection.args
+        } else if options.args.is_empty() {
             // Run as login shell when no args are provided.
             // CommandBuilder::new() sets is_default_prog=false, so portable-pty
             // won't prefix argv[0] with '-'. The -l flag ensures shell startup
             // files (.zprofile, .bash_profile, etc.) are loaded.
-            cmd.arg("-l");
+            vec!["-l".to_string()]
         } else {
-            for arg in &options.args {
-                cmd.arg(arg);
-            }
+            options.args
+        };
+
+        let mut cmd = CommandBuilder::new(&shell);
+        for arg in &args {
+            cmd.arg(arg);
         }
 
         // Set working directory (validated)
-        if let Some(ref cwd) = options.cwd {
-            let validated = validate_cwd(cwd)?;
-            cmd.cwd(validated);
-        } else if let Some(home) = dirs_home() {
-            cmd.cwd(home);
+        let cwd = match options.cwd {
+            Some(ref cwd) => Some(validate_cwd(cwd)?),
+            None => dirs_home().map(std::path::PathBuf::from),
+        };
+        if let Some(ref cwd) = cwd {
+            cmd.cwd(cwd);
+        }
+
+        // Set the login shell's environment, so sessions started from a
+        // desktop launcher find the user's tools (filtered for safety)
+        for (key, value) in login_env.iter().flat_map(|env| env.iter()) {
+            if is_blocked_env_var(key) {
+                log::debug!("Not passing {key} from the login shell environment");
+                continue;
+            }
+            cmd.env(key, value);
         }
 
         // Set TERM environment variable
         cmd.env("TERM", "xterm-256color");
 
-        // Set additional environment variables (filtered for safety)
-        for (key, value) in &options.env {
+        // Set the profile's and then additional environment variables
+        // (filtered for safety)
+        for (key, value) in profile_env.iter().flatten().chain(&env) {
             if is_blocked_env_var(key) {
                 log::warn!("Blocked dangerous environment variable: {key}");
                 continue;
             }
             cmd.env(key, value);
         }
+        for (key, value) in integration_env {
+            cmd.env(key, value);
+        }
+
+        let start_cwd = cwd.as_deref().map(normalize_path_for_serialization);
+
+        let transcript = if options.transcript {
+            Some(self.create_transcript(&session_id, &shell, start_cwd.as_deref())?)
+        } else {
+            None
+        };
 
         // Spawn the child process on the slave PTY
-        let child = pty_pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| PtyError::SpawnError {
-                message: e.to_string(),
-            })?;
+        let child = match pty_pair.slave.spawn_command(cmd) {
+            Ok(child) => child,
+            Err(e) => {
+                if let Some(transcript) = transcript {
+                    transcript.discard();
+                }
+                return Err(PtyError::SpawnError {
+                    message: e.to_string(),
+                });
+            }
+        };
 
         // Drop the slave immediately after spawn to ensure reader gets EOF when child exits
         drop(pty_pair.slave);
 
         let pid = child.process_id();
+        let mut killer = child.clone_killer();
 
-        // Take writer from master (only once)
-        let writer = pty_pair
-            .master
-            .take_writer()
-            .map_err(|e| PtyError::IoError {
-                message: e.to_string(),
-            })?;
-
-        // Create reader from master
-        let mut reader = pty_pair
-            .master
-            .try_clone_reader()
-            .map_err(|e| PtyError::IoError {
-                message: e.to_string(),
-            })?;
+        // Take writer from master (only once) and create reader from master
+        let io = pty_pair.master.take_writer().and_then(|writer| {
+            let reader = pty_pair.master.try_clone_reader()?;
+            Ok((writer, reader))
+        });
+        let (mut writer, mut reader) = match io {
+            Ok(io) => io,
+            Err(e) => {
+                // No session will own the child, so kill and reap it here
+                let reaped = Mutex::new(false);
+                if let Err(e) = kill_process(pid, killer.as_mut(), &reaped) {
+                    log::warn!("Failed to kill child process of failed session: {e}");
+                }
+                wait_for_exit(child, &reaped);
+                if let Some(transcript) = transcript {
+                    transcript.discard();
+                }
+                return Err(PtyError::IoError {
+                    message: e.to_string(),
+                });
+            }
+        };
 
-        // Spawn reader thread (with panic safety)
-        let event_channel = on_event.clone();
+        let output = Arc::new(Mutex::new(SessionOutput {
+            scrollback: Scrollback::new(scrollback_limit as usize),
+            screen: options
+                .track_screen
+                .then(|| Screen::new(options.rows, options.cols)),
+            osc: OscScanner::default(),
+            commands: CommandTracker::default(),
+            cwd: start_cwd.clone(),
+            terminal_title: None,
+            channel: Some(on_event),
+            final_event: None,
+            reader_done: false,
+            exit: None,
+            transcript: transcript.map(|transcript| Arc::new(Mutex::new(transcript))),
+        }));
+
+        // Spawn reader thread (with panic safety).
+        // The reader only reads and records; batching and delivery happen on
+        // the emitter thread so a slow channel never delays draining the PTY.
+        let recording: Arc<Mutex<Option<Recorder>>> = Arc::new(Mutex::new(None));
+        let reader_recording = Arc::clone(&recording);
+        let (reader_tx, reader_rx) = mpsc::channel();
         let reader_thread = std::thread::spawn(move || {
-            let channel = event_channel;
             let result = catch_unwind(AssertUnwindSafe(|| {
                 let mut buf = [0u8; READ_BUFFER_SIZE];
                 loop {
                     match reader.read(&mut buf) {
                         Ok(0) => {
                             // EOF — child process has exited
-                            if let Err(e) = channel.send(PtyEvent::Exit { code: None }) {
-                                log::error!("PTY channel.send failed for Exit: {e:?}");
-                            }
+                            let _ = reader_tx.send(ReaderMessage::Finished(None));
                             break;
                         }
                         Ok(n) => {
-                            if let Err(e) = channel.send(PtyEvent::Output {
-                                data: buf[..n].to_vec(),
-                            }) {
-                                log::error!("PTY channel.send failed for Output: {e:?}");
+                            if let Some(recorder) = lock(&reader_recording).as_mut() {
+                                recorder.output(&buf[..n]);
+                            }
+                            if reader_tx
+                                .send(ReaderMessage::Data(buf[..n].to_vec()))
+                                .is_err()
+                            {
                                 break;
                             }
                         }
                         Err(e) => {
                             // On macOS/Linux, EIO (errno 5) is expected when the child exits
-                            if e.raw_os_error() == Some(5) {
-                                if let Err(send_err) = channel.send(PtyEvent::Exit { code: None }) {
-                                    log::error!("PTY channel.send failed for Exit: {send_err:?}");
-                                }
-                            } else if let Err(send_err) = channel.send(PtyEvent::Error {
-                                message: e.to_string(),
-                            }) {
-                                log::error!("PTY channel.send failed for Error: {send_err:?}");
-                            }
+                            let error = (e.raw_os_error() != Some(5)).then(|| e.to_string());
+                            let _ = reader_tx.send(ReaderMessage::Finished(error));
                             break;
                         }
                     }
@@ -222,60 +692,168 @@ impl PtyManager {
             if let Err(e) = result {
                 log::error!("Reader thread panicked: {e:?}");
             }
+            if let Some(recorder) = lock(&reader_recording).as_mut() {
+                recorder.flush();
+            }
+        });
+
+        // Spawn writer thread: writes input in the order it was queued, so
+        // writes never block the caller and are never reordered
+        let writer_recording = Arc::clone(&recording);
+        let queued_input = Arc::new(AtomicUsize::new(0));
+        let writer_queued_input = Arc::clone(&queued_input);
+        let (input, input_rx) = mpsc::channel();
+        let writer_thread = std::thread::spawn(move || {
+            write_input(
+                input_rx,
+                writer.as_mut(),
+                &writer_queued_input,
+                &writer_recording,
+            );
+        });
+
+        // Spawn emitter thread: coalesces output into batches and pushes them
+        // to the scrollback and channel. It keeps running even if the channel
+        // goes away, so the shell survives webview reloads and a new channel
+        // can attach later.
+        let emitter_output = Arc::clone(&output);
+        let redactor = redaction::active();
+        let redact = redactor
+            .redacts_live_output()
+            .then(|| RedactStream::new(redactor));
+        let emitter_thread = std::thread::spawn(move || {
+            emit_output(reader_rx, &emitter_output, redact);
+        });
+
+        // Spawn reaper thread: blocks on wait() so the real exit status can be
+        // reported in the Exit event
+        let reaper_output = Arc::clone(&output);
+        let reaper_session_id = session_id.clone();
+        let reaped = Arc::new(Mutex::new(false));
+        let reaper_reaped = Arc::clone(&reaped);
+        let reaper_thread = std::thread::spawn(move || {
+            let exit = wait_for_exit(child, &reaper_reaped);
+            log::info!("PTY session process exited: {reaper_session_id} ({exit:?})");
+            lock(&reaper_output).process_exited(exit);
+        });
+
+        // Spawn foreground watcher thread: reports when a job takes over the
+        // terminal or hands it back to the shell
+        let master = Arc::new(Mutex::new(pty_pair.master));
+        let foreground_master = Arc::downgrade(&master);
+        let foreground_output = Arc::clone(&output);
+        let foreground_thread = std::thread::spawn(move || {
+            watch_foreground(foreground_master, &foreground_output, pid);
         });
 
         let session = PtySession {
-            writer,
-            child,
+            input,
+            queued_input,
+            killer: Mutex::new(killer),
+            reaped,
             _reader_thread: reader_thread,
+            _emitter_thread: emitter_thread,
+            _writer_thread: writer_thread,
+            _reaper_thread: reaper_thread,
+            _foreground_thread: foreground_thread,
             pid,
-            _master: pty_pair.master,
+            shell: shell.clone(),
+            program: options.program.clone(),
+            args,
+            cwd: start_cwd,
+            created_at: SystemTime::now(),
+            title: Mutex::new(None),
+            master,
+            output,
+            recording,
         };
 
-        self.sessions.insert(session_id.clone(), session);
+        {
+            let mut sessions = lock(&self.sessions);
+            if sessions.len() >= MAX_SESSIONS {
+                drop(sessions);
+                force_kill(&session, &[]);
+                return Err(session_limit_error());
+            }
+            sessions.insert(session_id.clone(), Arc::new(session));
+        }
 
-        log::info!("PTY session spawned: {session_id} (shell: {shell}, pid: {pid:?})");
+        match &options.program {
+            Some(program) => {
+                log::info!("PTY session spawned: {session_id} (program: {program}, pid: {pid:?})")
+            }
+            None => log::info!("PTY session spawned: {session_id} (shell: {shell}, pid: {pid:?})"),
+        }
         Ok(session_id)
     }
 
-    /// Writes data to the PTY session's stdin.
-    ///
-    /// NOTE: This holds the PtyManager lock during `write_all` + `flush`.
-    /// If the PTY writer blocks (backpressure), all other sessions are also blocked.
-    /// TODO: For multi-session support, consider per-session locking or async writes.
-    pub fn write(&mut self, session_id: &str, data: &[u8]) -> Result<(), PtyError> {
-        let session =
-            self.sessions
-                .get_mut(session_id)
-                .ok_or_else(|| PtyError::SessionNotFound {
-                    session_id: session_id.to_string(),
-                })?;
-
-        session
-            .writer
-            .write_all(data)
-            .map_err(|e| PtyError::IoError {
-                message: e.to_string(),
+    /// Creates the transcript of a new session, first deleting the
+    /// transcripts of old sessions if there are too many.
+    fn create_transcript(
+        &self,
+        session_id: &str,
+        shell: &str,
+        cwd: Option<&str>,
+    ) -> Result<Transcript, PtyError> {
+        let dir = lock(&self.transcripts_dir)
+            .clone()
+            .ok_or_else(|| PtyError::ValidationError {
+                message: "Transcripts are not available".to_string(),
             })?;
 
-        session.writer.flush().map_err(|e| PtyError::IoError {
-            message: e.to_string(),
-        })?;
+        // Other sessions' transcripts, which must not be pruned
+        let active: Vec<PathBuf> = lock(&self.sessions)
+            .values()
+            .filter_map(|s| {
+                let transcript = lock(&s.output).transcript.clone()?;
+                let dir = lock(&transcript).dir().to_path_buf();
+                Some(dir)
+            })
+            .collect();
+        transcript::prune(&dir, transcript::MAX_TOTAL_BYTES, &active);
 
-        Ok(())
+        let meta = transcript::Meta { shell, cwd };
+        Transcript::create(&dir, session_id, meta, redaction::active()).map_err(|e| {
+            PtyError::IoError {
+                message: format!("Failed to create transcript: {e}"),
+            }
+        })
+    }
+
+    /// Queues data to be written to the PTY session's stdin.
+    ///
+    /// Returns right away; the session's writer thread writes queued data in
+    /// order, so backpressure here blocks neither the caller nor other
+    /// sessions.
+    pub fn write(&self, session_id: &str, data: &[u8]) -> Result<(), PtyError> {
+        let session = self.get(session_id)?;
+
+        let queued = session
+            .queued_input
+            .fetch_add(data.len(), Ordering::Relaxed);
+        if queued + data.len() > MAX_QUEUED_INPUT_BYTES {
+            session
+                .queued_input
+                .fetch_sub(data.len(), Ordering::Relaxed);
+            return Err(PtyError::IoError {
+                message: "Too much input is waiting to be written to the session".to_string(),
+            });
+        }
+        session.input.send(data.to_vec()).map_err(|_| {
+            session
+                .queued_input
+                .fetch_sub(data.len(), Ordering::Relaxed);
+            PtyError::IoError {
+                message: "Session input is closed".to_string(),
+            }
+        })
     }
 
     /// Resizes the PTY session.
     pub fn resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), PtyError> {
-        let session = self
-            .sessions
-            .get(session_id)
-            .ok_or_else(|| PtyError::SessionNotFound {
-                session_id: session_id.to_string(),
-            })?;
+        let session = self.get(session_id)?;
 
-        session
-            ._master
+        lock(&session.master)
             .resize(PtySize {
                 rows,
                 cols,
@@ -286,84 +864,755 @@ impl PtyManager {
                 message: e.to_string(),
             })?;
 
+        if let Some(screen) = &mut lock(&session.output).screen {
+            screen.resize(rows, cols);
+        }
+        if let Some(recorder) = lock(&session.recording).as_mut() {
+            recorder.resize(cols, rows);
+        }
+
         log::debug!("PTY session resized: {session_id} ({cols}x{rows})");
         Ok(())
     }
 
+    /// Starts recording the PTY session to an asciicast v2 file in `dir`.
+    ///
+    /// The oldest recordings in `dir` are deleted if needed to keep it under
+    /// its size limit, counting the new recording at its maximum size.
+    pub fn start_recording(
+        &self,
+        session_id: &str,
+        dir: &Path,
+        options: RecordOptions,
+    ) -> Result<RecordingInfo, PtyError> {
+        let session = self.get(session_id)?;
+        let max_bytes = options
+            .max_bytes
+            .unwrap_or(recording::DEFAULT_MAX_BYTES)
+            .clamp(READ_BUFFER_SIZE as u32, recording::MAX_BYTES);
+
+        // Other sessions' recordings, which must not be pruned
+        let active: Vec<PathBuf> = lock(&self.sessions)
+            .values()
+            .filter_map(|s| Some(lock(&s.recording).as_ref()?.path().to_path_buf()))
+            .collect();
+
+        let mut recording = lock(&session.recording);
+        if recording.is_some() {
+            return Err(PtyError::ValidationError {
+                message: format!("Session is already being recorded: {session_id}"),
+            });
+        }
+
+        recording::prune(
+            dir,
+            recording::MAX_TOTAL_BYTES.saturating_sub(max_bytes.into()),
+            &active,
+        );
+
+        let size = lock(&session.master).get_size().ok();
+        let title = lock(&session.title)
+            .clone()
+            .or_else(|| lock(&session.output).terminal_title.clone());
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        let path = dir.join(format!(
+            "{started_at}-{session_id}.{}",
+            recording::EXTENSION
+        ));
+
+        let header = recording::Header {
+            cols: size.map_or(80, |s| s.cols),
+            rows: size.map_or(24, |s| s.rows),
+            shell: &session.shell,
+            title: title.as_deref(),
+        };
+        let recorder = Recorder::create(
+            path,
+            session_id,
+            header,
+            options.record_input,
+            max_bytes,
+            redaction::active(),
+        )
+        .map_err(|e| PtyError::IoError {
+            message: format!("Failed to create recording: {e}"),
+        })?;
+
+        let info = recorder.info();
+        *recording = Some(recorder);
+        log::info!(
+            "PTY session recording started: {session_id} ({})",
+            info.path
+        );
+        Ok(info)
+    }
+
+    /// Stops recording the PTY session and returns the finished recording.
+    pub fn stop_recording(&self, session_id: &str) -> Result<RecordingInfo, PtyError> {
+        let session = self.get(session_id)?;
+        let recorder =
+            lock(&session.recording)
+                .take()
+                .ok_or_else(|| PtyError::ValidationError {
+                    message: format!("Session is not being recorded: {session_id}"),
+                })?;
+
+        let info = recorder.finish();
+        log::info!(
+            "PTY session recording stopped: {session_id} ({}, {} bytes)",
+            info.path,
+            info.bytes
+        );
+        Ok(info)
+    }
+
+    /// Returns the buffered scrollback of the PTY session.
+    pub fn scrollback(&self, session_id: &str) -> Result<ScrollbackSnapshot, PtyError> {
+        let session = self.get(session_id)?;
+        let snapshot = lock(&session.output).scrollback.snapshot();
+        Ok(snapshot)
+    }
+
+    /// Returns the current screen of the PTY session.
+    /// Requires the session to have been spawned with `track_screen`.
+    pub fn snapshot(
+        &self,
+        session_id: &str,
+        format: SnapshotFormat,
+    ) -> Result<ScreenSnapshot, PtyError> {
+        let session = self.get(session_id)?;
+        let output = lock(&session.output);
+        let screen = output
+            .screen
+            .as_ref()
+            .ok_or_else(|| PtyError::ValidationError {
+                message: format!("Screen tracking is not enabled for session {session_id}"),
+            })?;
+        Ok(screen.snapshot(format))
+    }
+
+    /// Attaches a new channel to an existing session, replacing the previous one.
+    ///
+    /// If `since` is given, buffered output with a greater sequence number is
+    /// replayed to the new channel before live output resumes. If some of it
+    /// was already evicted, the replay starts at the oldest buffered chunk and
+    /// the result is marked as truncated. If the session has already
+    /// finished, its final event is sent after the replay.
+    pub fn attach(
+        &self,
+        session_id: &str,
+        on_event: Channel<PtyEvent>,
+        since: Option<u32>,
+    ) -> Result<AttachInfo, PtyError> {
+        let session = self.get(session_id)?;
+        let mut output = lock(&session.output);
+
+        let truncated = since.is_some_and(|since| output.scrollback.evicted_since(since));
+        if let Some(since) = since {
+            for (seq, data) in output.scrollback.chunks_since(since) {
+                on_event
+                    .send(PtyEvent::Output {
+                        data: data.to_vec(),
+                        seq,
+                    })
+                    .map_err(|e| PtyError::IoError {
+                        message: format!("Failed to replay scrollback: {e}"),
+                    })?;
+            }
+        }
+
+        if let Some(event) = output.final_event.clone() {
+            on_event.send(event).map_err(|e| PtyError::IoError {
+                message: format!("Failed to send final event: {e}"),
+            })?;
+        }
+
+        output.channel = Some(on_event);
+
+        log::info!(
+            "PTY session attached: {session_id} (replay since: {since:?}, truncated: {truncated})"
+        );
+        Ok(AttachInfo { truncated })
+    }
+
+    /// Kills a PTY session with the default options.
+    pub fn kill(&self, session_id: &str) -> Result<(), PtyError> {
+        self.kill_with_options(session_id, KillOptions::default())
+    }
+
     /// Kills a PTY session and cleans up resources.
-    pub fn kill(&mut self, session_id: &str) -> Result<(), PtyError> {
-        let mut session =
-            self.sessions
+    ///
+    /// The session is removed right away and terminated gracefully in the
+    /// background (see `terminate`), so this never blocks on the child.
+    pub fn kill_with_options(
+        &self,
+        session_id: &str,
+        options: KillOptions,
+    ) -> Result<(), PtyError> {
+        let session =
+            lock(&self.sessions)
                 .remove(session_id)
                 .ok_or_else(|| PtyError::SessionNotFound {
                     session_id: session_id.to_string(),
                 })?;
 
-        // Kill the child process (ignore errors if already exited)
-        if let Err(e) = session.child.kill() {
-            log::debug!("Child process already exited or kill failed: {e}");
+        let timeout = options
+            .timeout_ms
+            .map_or(DEFAULT_KILL_TIMEOUT, |ms| Duration::from_millis(ms.into()))
+            .min(MAX_KILL_TIMEOUT);
+        let terminate_session_id = session_id.to_string();
+        std::thread::spawn(move || {
+            terminate(&[session], timeout);
+            log::debug!("PTY session terminated: {terminate_session_id}");
+        });
+
+        // The reaper thread reaps the child and reports its exit status.
+        // Reader thread is intentionally not joined here — it will exit on its
+        // own once the last reference to the session (and with it the master
+        // PTY handle) is dropped, causing the reader to receive EOF or EIO.
+
+        log::info!("PTY session killed: {session_id} (timeout: {timeout:?})");
+        Ok(())
+    }
+
+    /// Returns the process currently in the foreground of the PTY session,
+    /// or None if it cannot be determined.
+    pub fn foreground_process(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<ForegroundProcess>, PtyError> {
+        let session = self.get(session_id)?;
+        let master = lock(&session.master);
+        Ok(foreground::foreground_process(master.as_ref(), session.pid))
+    }
+
+    /// Sends a signal to the foreground process group of the PTY session,
+    /// i.e. the running job, or the shell if it is idle at its prompt.
+    ///
+    /// Unlike writing `^C` to the PTY, this works regardless of what is in
+    /// the input buffer or how the terminal is configured.
+    pub fn signal(&self, session_id: &str, signal: Signal) -> Result<(), PtyError> {
+        let session = self.get(session_id)?;
+        // Held while signalling, so the shell cannot be reaped meanwhile and
+        // its process group ID reused (see `process`)
+        let reaped = lock(&session.reaped);
+        if *reaped {
+            return Err(PtyError::ValidationError {
+                message: format!("Session has already exited: {session_id}"),
+            });
         }
 
-        // Reap the zombie process
-        let _ = session.child.wait();
+        #[cfg(unix)]
+        {
+            // The shell leads its own process group, which is the foreground
+            // group unless a job is running
+            let pgid = lock(&session.master)
+                .process_group_leader()
+                .and_then(|pgid| u32::try_from(pgid).ok())
+                .or(session.pid)
+                .ok_or_else(|| PtyError::SystemError {
+                    message: "Process group of the session is unknown".to_string(),
+                })?;
 
-        // Reader thread is intentionally not joined here — it will exit on its
-        // own once the master PTY handle (_master) is dropped at end of scope,
-        // causing the reader to receive EOF or EIO.
+            let name = process::signal_name(process::signal_number(signal));
+            process::signal_process_group(pgid, signal).map_err(|e| PtyError::SystemError {
+                message: format!("Failed to send {name} to process group {pgid}: {e}"),
+            })?;
+
+            drop(reaped);
+            log::info!("PTY session signalled: {session_id} ({name} to process group {pgid})");
+            Ok(())
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = signal;
+            Err(PtyError::SystemError {
+                message: "Signals are not supported on this platform".to_string(),
+            })
+        }
+    }
+
+    /// Sets or clears the user-assigned title of the PTY session.
+    pub fn set_title(&self, session_id: &str, title: Option<String>) -> Result<(), PtyError> {
+        let title = title
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+
+        if let Some(ref t) = title {
+            if t.chars().count() > MAX_TITLE_LENGTH {
+                return Err(PtyError::ValidationError {
+                    message: format!("Title too long (max {MAX_TITLE_LENGTH} characters)"),
+                });
+            }
+        }
 
-        log::info!("PTY session killed: {session_id}");
+        let session = self.get(session_id)?;
+        *lock(&session.title) = title;
         Ok(())
     }
 
-    /// Returns information about all active sessions.
-    #[allow(dead_code)]
-    pub fn list(&mut self) -> Vec<SessionInfo> {
-        self.sessions
-            .iter_mut()
+    /// Returns information about all active sessions, oldest first.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<(String, Arc<PtySession>)> = lock(&self.sessions)
+            .iter()
+            .map(|(id, session)| (id.clone(), Arc::clone(session)))
+            .collect();
+        sessions.sort_by_key(|(_, session)| session.created_at);
+
+        sessions
+            .into_iter()
             .map(|(id, session)| {
-                let is_alive = session
-                    .child
-                    .try_wait()
-                    .map(|status| status.is_none())
-                    .unwrap_or(false);
+                let (is_alive, current_cwd, terminal_title) = {
+                    let output = lock(&session.output);
+                    (
+                        output.exit.is_none(),
+                        output.cwd.clone(),
+                        output.terminal_title.clone(),
+                    )
+                };
+                let size = lock(&session.master).get_size().ok();
+                let created_at = session
+                    .created_at
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as f64)
+                    .unwrap_or(0.0);
 
                 SessionInfo {
-                    id: id.clone(),
+                    id,
                     pid: session.pid,
                     is_alive,
+                    shell: session.shell.clone(),
+                    program: session.program.clone(),
+                    args: session.args.clone(),
+                    cwd: session.cwd.clone(),
+                    current_cwd,
+                    created_at,
+                    cols: size.map_or(0, |s| s.cols),
+                    rows: size.map_or(0, |s| s.rows),
+                    title: lock(&session.title).clone(),
+                    terminal_title,
                 }
             })
             .collect()
     }
-}
 
-impl Drop for PtyManager {
-    fn drop(&mut self) {
-        let session_ids: Vec<String> = self.sessions.keys().cloned().collect();
-        for id in &session_ids {
-            if let Err(e) = self.kill(id) {
-                log::warn!("Failed to kill session {id} during cleanup: {e}");
+    /// Searches the output of sessions, newest session first.
+    ///
+    /// Sessions with a transcript are searched in their transcript, which
+    /// is synced first if the session is running. Running sessions without
+    /// one are searched in their scrollback.
+    pub fn search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchMatch>, PtyError> {
+        let regex = search::build_regex(query, options)
+            .map_err(|message| PtyError::ValidationError { message })?;
+        let max_results = options
+            .max_results
+            .unwrap_or(search::DEFAULT_MAX_RESULTS)
+            .min(search::MAX_RESULTS) as usize;
+        let selected = |session_id: &str| {
+            options.session_ids.is_empty() || options.session_ids.iter().any(|id| id == session_id)
+        };
+
+        enum Source {
+            Scrollback(u64, Vec<u8>, Vec<(u64, f64)>),
+            Transcript(PathBuf, Vec<transcript::IndexEntry>),
+        }
+        // Sessions to search, with their start time
+        let mut sources: Vec<(String, f64, Source)> = Vec::new();
+
+        let sessions: Vec<(String, Arc<PtySession>)> = lock(&self.sessions)
+            .iter()
+            .filter(|(id, _)| selected(id))
+            .map(|(id, session)| (id.clone(), Arc::clone(session)))
+            .collect();
+        // Live transcripts, synced below so the search sees all output
+        let mut transcripts = Vec::new();
+        for (id, session) in sessions {
+            let output = lock(&session.output);
+            if let Some(transcript) = &output.transcript {
+                transcripts.push(Arc::clone(transcript));
+                continue;
+            }
+            let created_at = session
+                .created_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as f64)
+                .unwrap_or(0.0);
+            if options.until.is_some_and(|until| created_at >= until) {
+                continue;
             }
+            let start = output.scrollback.start_offset();
+            let data = output.scrollback.snapshot().data;
+            let times = output.scrollback.times();
+            sources.push((id, created_at, Source::Scrollback(start, data, times)));
+        }
+        // Without holding the session output locks, which the emitters need
+        for transcript in transcripts {
+            lock(&transcript).flush();
+        }
+
+        let transcripts_dir = lock(&self.transcripts_dir).clone();
+        for (id, dir) in transcripts_dir
+            .iter()
+            .flat_map(|root| transcript::list(root))
+        {
+            if !selected(&id) {
+                continue;
+            }
+            let index = match transcript::read_index(&dir) {
+                Ok(index) => index,
+                Err(e) => {
+                    log::warn!("Failed to read transcript index {}: {e}", dir.display());
+                    continue;
+                }
+            };
+            let (Some(first), Some(last)) = (index.first(), index.last()) else {
+                continue;
+            };
+            let (started_at, ended_at) = (first.time as f64, last.time as f64);
+            if options.since.is_some_and(|since| ended_at < since)
+                || options.until.is_some_and(|until| started_at >= until)
+            {
+                continue;
+            }
+            // A session that stopped keeps its scrollback, but its
+            // transcript is complete
+            sources.retain(|(other, _, _)| *other != id);
+            sources.push((id, started_at, Source::Transcript(dir, index)));
+        }
+        sources.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut matches = Vec::new();
+        for (id, _, source) in sources {
+            let limit = max_results - matches.len();
+            if limit == 0 {
+                break;
+            }
+            let found = match source {
+                Source::Scrollback(start, data, times) => {
+                    let mut search =
+                        SessionSearch::new(&regex, &id, SearchSource::Scrollback, options, limit)
+                            .with_times(times);
+                    search.push(start, &data);
+                    search.finish()
+                }
+                Source::Transcript(dir, index) => {
+                    let times = index
+                        .iter()
+                        .map(|entry| (entry.offset, entry.time as f64))
+                        .collect();
+                    let mut search =
+                        SessionSearch::new(&regex, &id, SearchSource::Transcript, options, limit)
+                            .with_times(times);
+                    if let Err(e) = transcript::read_output(&dir, &index, |offset, chunk| {
+                        search.push(offset, chunk)
+                    }) {
+                        log::warn!("Failed to read transcript {}: {e}", dir.display());
+                    }
+                    search.finish()
+                }
+            };
+            matches.extend(found);
         }
+        Ok(matches)
     }
 }
 
-/// Returns the user's home directory.
-fn dirs_home() -> Option<String> {
-    std::env::var("HOME").ok()
+impl Drop for PtyManager {
+    /// Terminates all sessions, giving them time to exit cleanly (e.g. for
+    /// Claude to flush its transcript on app quit).
+    fn drop(&mut self) {
+        let sessions: Vec<Arc<PtySession>> = lock(&self.sessions)
+            .drain()
+            .map(|(_, session)| session)
+            .collect();
+        if !sessions.is_empty() {
+            log::info!("Terminating {} PTY sessions", sessions.len());
+            terminate(&sessions, DEFAULT_KILL_TIMEOUT);
+        }
+    }
 }
 
-/// Validates that the shell command is in the allowed list.
-fn validate_shell(shell: &str) -> Result<(), PtyError> {
-    if ALLOWED_SHELLS.contains(&shell) {
-        Ok(())
-    } else {
-        Err(PtyError::ValidationError {
-            message: format!("Shell not allowed: {shell}"),
-        })
+/// Terminates sessions gracefully, blocking until they have exited.
+///
+/// The process groups of each session are sent SIGHUP and SIGTERM, plus
+/// SIGCONT so stopped jobs can act on them. Whatever is still running after
+/// `timeout` is killed. On Windows the sessions are killed right away.
+fn terminate(sessions: &[Arc<PtySession>], timeout: Duration) {
+    let groups: Vec<Vec<u32>> = sessions.iter().map(|session| hang_up(session)).collect();
+
+    let deadline = Instant::now() + timeout;
+    let is_done = |(session, groups): (&Arc<PtySession>, &Vec<u32>)| {
+        has_exited(session) && !groups.iter().any(|&pgid| process_group_exists(pgid))
+    };
+    while Instant::now() < deadline && !sessions.iter().zip(&groups).all(is_done) {
+        std::thread::sleep(KILL_POLL_INTERVAL);
+    }
+
+    for (session, groups) in sessions.iter().zip(&groups) {
+        force_kill(session, groups);
+    }
+}
+
+/// Asks the processes of a session to exit. Returns the process groups that
+/// were signalled: the shell's own, and the foreground job's if one is running.
+fn hang_up(session: &PtySession) -> Vec<u32> {
+    #[cfg(unix)]
+    {
+        // Held while signalling, so the shell cannot be reaped meanwhile and
+        // its process group ID reused (see `process`)
+        let reaped = lock(&session.reaped);
+        if *reaped {
+            return Vec::new();
+        }
+
+        let mut groups: Vec<u32> = session.pid.into_iter().collect();
+        let foreground = lock(&session.master)
+            .process_group_leader()
+            .and_then(|pgid| u32::try_from(pgid).ok());
+        if let Some(pgid) = foreground.filter(|pgid| !groups.contains(pgid)) {
+            groups.push(pgid);
+        }
+
+        for &pgid in &groups {
+            for signal in [Signal::Hangup, Signal::Terminate, Signal::Continue] {
+                if let Err(e) = process::signal_process_group(pgid, signal) {
+                    log::debug!("Failed to signal process group {pgid}: {e}");
+                }
+            }
+        }
+        groups
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = session;
+        Vec::new()
+    }
+}
+
+/// Kills whatever is left of a session: the process groups from `hang_up`,
+/// which may outlive the shell, and the child process unless the reaper has
+/// already reaped it.
+fn force_kill(session: &PtySession, groups: &[u32]) {
+    #[cfg(unix)]
+    for &pgid in groups {
+        // Only groups that still have members: the ID of an empty group
+        // may have been reused
+        if process_group_exists(pgid) && process::kill_process_group(pgid).is_ok() {
+            log::debug!("Killed process group {pgid} after the kill timeout");
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = groups;
+
+    let mut killer = lock(&session.killer);
+    if let Err(e) = kill_process(session.pid, killer.as_mut(), &session.reaped) {
+        log::debug!("Child process already exited or kill failed: {e}");
+    }
+}
+
+/// Whether a process group still has members.
+fn process_group_exists(pgid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        process::process_group_exists(pgid)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = pgid;
+        false
+    }
+}
+
+/// ZDOTDIR as a session would get it without shell integration: from its
+/// own environment, its profile, the login shell's environment or the app's.
+fn user_zdotdir(
+    env: &HashMap<String, String>,
+    profile_env: Option<&HashMap<String, String>>,
+    login_env: Option<&HashMap<String, String>>,
+) -> Option<String> {
+    [Some(env), profile_env, login_env]
+        .into_iter()
+        .flatten()
+        .find_map(|env| env.get("ZDOTDIR").cloned())
+        .or_else(|| std::env::var("ZDOTDIR").ok())
+}
+
+/// Whether the session's child process has been reaped.
+fn has_exited(session: &PtySession) -> bool {
+    lock(&session.output).exit.is_some()
+}
+
+/// Runs the writer loop: writes queued input to the PTY, and records it if
+/// the session is being recorded, until the queue is closed or a write fails.
+fn write_input(
+    rx: mpsc::Receiver<Vec<u8>>,
+    writer: &mut dyn Write,
+    queued: &AtomicUsize,
+    recording: &Mutex<Option<Recorder>>,
+) {
+    for data in rx {
+        if let Some(recorder) = lock(recording).as_mut() {
+            recorder.input(&data);
+        }
+        let result = writer.write_all(&data).and_then(|()| writer.flush());
+        queued.fetch_sub(data.len(), Ordering::Relaxed);
+        if let Err(e) = result {
+            log::warn!("Failed to write PTY input: {e}");
+            break;
+        }
+    }
+}
+
+/// Runs the emitter loop: redacts reader output if `redact` is given, frames
+/// it on character and escape sequence boundaries, coalesces it into batches
+/// and pushes them to the session output until the reader stops.
+fn emit_output(
+    rx: mpsc::Receiver<ReaderMessage>,
+    output: &Mutex<SessionOutput>,
+    mut redact: Option<RedactStream>,
+) {
+    let mut framer = Framer::default();
+    let mut coalescer = Coalescer::new(COALESCE_WINDOW, MAX_BATCH_BYTES);
+    // When an incomplete sequence held back by the framer is sent anyway
+    let mut hold_deadline: Option<Instant> = None;
+    // When output written to the transcript is synced to disk
+    let mut flush_deadline: Option<Instant> = None;
+    let has_transcript = lock(output).transcript.is_some();
+
+    let error = loop {
+        let deadline = [coalescer.deadline(), hold_deadline, flush_deadline]
+            .into_iter()
+            .flatten()
+            .min();
+        let received = match deadline {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        let now = Instant::now();
+        if flush_deadline.is_some_and(|flush| flush <= now) {
+            flush_deadline = None;
+            let transcript = lock(output).transcript.clone();
+            if let Some(transcript) = transcript {
+                lock(&transcript).flush();
+            }
+        }
+
+        let batch = match received {
+            Ok(ReaderMessage::Data(data)) => {
+                let data = match &mut redact {
+                    Some(redact) => redact.push(&data),
+                    None => data,
+                };
+                let complete = framer.push(&data);
+                if complete.is_empty() {
+                    // Still waiting for the rest of the same sequence
+                    hold_deadline = hold_deadline.or(Some(now + HOLD_TIMEOUT));
+                    continue;
+                }
+                let pending =
+                    framer.has_pending() || redact.as_ref().is_some_and(RedactStream::has_pending);
+                hold_deadline = pending.then_some(now + HOLD_TIMEOUT);
+                coalescer.push(&complete, now)
+            }
+            Ok(ReaderMessage::Finished(error)) => break error,
+            Err(RecvTimeoutError::Timeout) if hold_deadline.is_some_and(|hold| hold <= now) => {
+                hold_deadline = None;
+                let mut pending = match &mut redact {
+                    Some(redact) => framer.push(&redact.flush()),
+                    None => Vec::new(),
+                };
+                pending.extend_from_slice(&framer.take_pending());
+                coalescer.push(&pending, now)
+            }
+            Err(RecvTimeoutError::Timeout) => coalescer.flush(now),
+            Err(RecvTimeoutError::Disconnected) => {
+                break Some("PTY reader stopped unexpectedly".to_string());
+            }
+        };
+
+        if let Some(batch) = batch {
+            lock(output).push_output(batch);
+            if has_transcript {
+                flush_deadline = flush_deadline.or(Some(now + transcript::FLUSH_INTERVAL));
+            }
+        }
+    };
+
+    let mut batch = coalescer.flush(Instant::now()).unwrap_or_default();
+    if let Some(redact) = &mut redact {
+        batch.extend_from_slice(&framer.push(&redact.flush()));
+    }
+    batch.extend_from_slice(&framer.take_pending());
+
+    let mut output = lock(output);
+    if !batch.is_empty() {
+        output.push_output(batch);
+    }
+    output.reader_finished(error);
+}
+
+/// Polls the foreground process of a session and sends `ForegroundChanged`
+/// whenever it changes, until the child exits or the session is dropped.
+fn watch_foreground(
+    master: Weak<Mutex<Box<dyn MasterPty + Send>>>,
+    output: &Mutex<SessionOutput>,
+    shell_pid: Option<u32>,
+) {
+    let mut last = None;
+    loop {
+        std::thread::sleep(FOREGROUND_POLL_INTERVAL);
+
+        let Some(master) = master.upgrade() else {
+            break;
+        };
+        let process = foreground::foreground_process(lock(&master).as_ref(), shell_pid);
+
+        // Checked under the same lock as the send, so no event follows Exit
+        let mut output = lock(output);
+        if output.exit.is_some() {
+            break;
+        }
+        if process != last {
+            output.send(PtyEvent::ForegroundChanged {
+                process: process.clone(),
+            });
+            last = process;
+        }
     }
 }
 
+/// Error returned when the maximum number of sessions is reached.
+fn session_limit_error() -> PtyError {
+    PtyError::ResourceLimit {
+        message: format!("Maximum number of sessions ({MAX_SESSIONS}) reached"),
+    }
+}
+
+/// Locks a mutex, recovering the inner value if a thread panicked while
+/// holding the lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| {
+        log::warn!("PTY mutex was poisoned, recovering: {err}");
+        err.into_inner()
+    })
+}
+
+/// Returns the user's home directory.
+fn dirs_home() -> Option<String> {
+    std::env::var("HOME").ok()
+}
+
 /// Validates and canonicalizes the working directory path.
 fn validate_cwd(path: &str) -> Result<std::path::PathBuf, PtyError> {
     let path = std::path::Path::new(path);
@@ -389,7 +1638,6 @@ fn is_blocked_env_var(key: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::mpsc;
     use tauri::ipc::InvokeResponseBody;
 
     /// Helper to create a Channel<PtyEvent> for tests.
@@ -411,11 +1659,17 @@ mod tests {
     fn default_spawn_options() -> SpawnOptions {
         SpawnOptions {
             command: Some("/bin/sh".to_string()),
+            program: None,
             args: vec!["-c".to_string(), "echo hello from pty".to_string()],
             cwd: None,
             env: HashMap::new(),
             cols: 80,
             rows: 24,
+            scrollback_limit: None,
+            track_screen: false,
+            shell_integration: false,
+            profile: None,
+            transcript: false,
         }
     }
 
@@ -423,17 +1677,23 @@ mod tests {
     fn interactive_spawn_options() -> SpawnOptions {
         SpawnOptions {
             command: Some("/bin/sh".to_string()),
+            program: None,
             args: vec![],
             cwd: None,
             env: HashMap::new(),
             cols: 80,
             rows: 24,
+            scrollback_limit: None,
+            track_screen: false,
+            shell_integration: false,
+            profile: None,
+            transcript: false,
         }
     }
 
     #[test]
     fn test_spawn_and_read_output() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager.spawn(default_spawn_options(), channel).unwrap();
@@ -445,11 +1705,13 @@ mod tests {
 
         loop {
             match rx.recv_timeout(deadline.saturating_duration_since(std::time::Instant::now())) {
-                Ok(PtyEvent::Output { data }) => {
+                Ok(PtyEvent::Output { data, .. }) => {
                     output.extend_from_slice(&data);
                 }
                 Ok(PtyEvent::Exit { .. }) => break,
                 Ok(PtyEvent::Error { .. }) => break,
+                // Other events are not relevant here
+                Ok(_) => {}
                 Err(_) => break,
             }
         }
@@ -461,24 +1723,24 @@ mod tests {
         );
 
         // Session should still be in manager (echo exited but wasn't killed)
-        assert_eq!(manager.sessions.len(), 1);
+        assert_eq!(lock(&manager.sessions).len(), 1);
     }
 
     #[test]
     fn test_spawn_and_kill() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
-        assert_eq!(manager.sessions.len(), 1);
+        assert_eq!(lock(&manager.sessions).len(), 1);
 
         manager.kill(&session_id).unwrap();
-        assert_eq!(manager.sessions.len(), 0);
+        assert_eq!(lock(&manager.sessions).len(), 0);
     }
 
     #[test]
     fn test_kill_nonexistent_session() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let result = manager.kill("nonexistent");
         assert!(result.is_err());
         if let Err(PtyError::SessionNotFound { session_id }) = result {
@@ -488,9 +1750,75 @@ mod tests {
         }
     }
 
+    /// Spawns `sh` running `trap <action> HUP TERM` and waits until the trap
+    /// is installed.
+    fn spawn_with_trap(manager: &PtyManager, action: &str) -> (String, mpsc::Receiver<PtyEvent>) {
+        let (channel, rx) = test_channel();
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                format!("trap {action} HUP TERM; echo ready; while :; do sleep 0.1; done"),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        let (output, _) = collect_events(&rx, std::time::Duration::from_secs(5), |out, _| {
+            out.contains("ready")
+        });
+        assert!(output.contains("ready"), "Shell did not start: {output:?}");
+        (session_id, rx)
+    }
+
+    #[test]
+    fn test_kill_lets_session_exit_gracefully() {
+        let manager = PtyManager::new();
+        let (session_id, rx) = spawn_with_trap(&manager, "'exit 7'");
+
+        manager.kill(&session_id).unwrap();
+        assert!(manager.list().is_empty());
+
+        let exit = wait_for_exit_event(&rx, std::time::Duration::from_secs(2));
+        assert_eq!(exit, Some((Some(7), None)));
+    }
+
+    #[test]
+    fn test_kill_escalates_after_timeout() {
+        let manager = PtyManager::new();
+        let (session_id, rx) = spawn_with_trap(&manager, "''");
+
+        let start = std::time::Instant::now();
+        manager
+            .kill_with_options(
+                &session_id,
+                KillOptions {
+                    timeout_ms: Some(300),
+                },
+            )
+            .unwrap();
+        assert!(
+            start.elapsed() < std::time::Duration::from_millis(100),
+            "kill must not wait for the session to exit"
+        );
+
+        let exit = wait_for_exit_event(&rx, std::time::Duration::from_secs(3));
+        assert!(start.elapsed() >= std::time::Duration::from_millis(300));
+        assert_eq!(exit, Some((None, Some("SIGKILL".to_string()))));
+    }
+
+    #[test]
+    fn test_drop_terminates_sessions_gracefully() {
+        let manager = PtyManager::new();
+        let (_, rx) = spawn_with_trap(&manager, "'exit 7'");
+
+        drop(manager);
+
+        let exit = wait_for_exit_event(&rx, std::time::Duration::from_secs(2));
+        assert_eq!(exit, Some((Some(7), None)));
+    }
+
     #[test]
     fn test_write_to_session() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
@@ -503,7 +1831,7 @@ mod tests {
 
     #[test]
     fn test_resize_session() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
@@ -514,9 +1842,182 @@ mod tests {
         manager.kill(&session_id).unwrap();
     }
 
+    #[test]
+    fn test_record_session() {
+        let dir = std::env::temp_dir().join(format!("pty-recordings-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+        let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
+
+        let options = RecordOptions {
+            record_input: true,
+            ..RecordOptions::default()
+        };
+        let info = manager.start_recording(&session_id, &dir, options).unwrap();
+        assert!(manager
+            .start_recording(&session_id, &dir, RecordOptions::default())
+            .is_err());
+
+        manager.resize(&session_id, 100, 30).unwrap();
+        manager.write(&session_id, b"echo rec$((1+1))\n").unwrap();
+        collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("rec2")
+        });
+
+        let stopped = manager.stop_recording(&session_id).unwrap();
+        assert_eq!(stopped.path, info.path);
+        assert!(manager.stop_recording(&session_id).is_err());
+        manager.kill(&session_id).unwrap();
+
+        let contents = std::fs::read_to_string(&stopped.path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        let has_event = |kind: &str, text: &str| {
+            lines[1..]
+                .iter()
+                .any(|e| e[1] == kind && e[2].as_str().unwrap().contains(text))
+        };
+        assert!(has_event("r", "100x30"), "No resize event: {contents}");
+        assert!(has_event("i", "echo rec"), "No input event: {contents}");
+        let output: String = lines[1..]
+            .iter()
+            .filter(|e| e[1] == "o")
+            .map(|e| e[2].as_str().unwrap())
+            .collect();
+        assert!(output.contains("rec2"), "Unexpected output: {output:?}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_transcript() {
+        use flate2::read::GzDecoder;
+
+        let manager = PtyManager::new();
+        let options = SpawnOptions {
+            transcript: true,
+            ..default_spawn_options()
+        };
+        let (channel, _rx) = test_channel();
+        let result = manager.spawn(options.clone(), channel);
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
+
+        let dir = std::env::temp_dir().join(format!("pty-transcripts-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        manager.set_transcripts_dir(dir.clone());
+        let (channel, rx) = test_channel();
+        let session_id = manager.spawn(options, channel).unwrap();
+        // Exit is only sent once the transcript is complete
+        collect_output_until(&rx, std::time::Duration::from_secs(5), |_| false);
+
+        let segment = dir.join(&session_id).join("000000.log.gz");
+        let mut output = String::new();
+        GzDecoder::new(std::fs::File::open(segment).unwrap())
+            .read_to_string(&mut output)
+            .unwrap();
+        assert!(
+            output.contains("hello from pty"),
+            "Unexpected transcript: {output:?}"
+        );
+        assert!(dir.join(&session_id).join("index.jsonl").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_search_sessions() {
+        let dir = std::env::temp_dir().join(format!("pty-transcripts-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let manager = PtyManager::new();
+        manager.set_transcripts_dir(dir.clone());
+
+        let spawn = |transcript: bool, text: &str| {
+            let options = SpawnOptions {
+                args: vec![
+                    "-c".to_string(),
+                    format!("printf '\\033[1m%s\\033[0m\\n' {text}"),
+                ],
+                transcript,
+                ..default_spawn_options()
+            };
+            let (channel, rx) = test_channel();
+            let session_id = manager.spawn(options, channel).unwrap();
+            collect_output_until(&rx, std::time::Duration::from_secs(5), |_| false);
+            session_id
+        };
+        let live = spawn(false, "needle-live");
+        let transcribed = spawn(true, "needle-transcript");
+
+        let options = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let matches = manager.search("NEEDLE-[a-z]+", &options).unwrap();
+        let found: Vec<(&str, SearchSource, &str)> = matches
+            .iter()
+            .map(|m| (m.session_id.as_str(), m.source, m.line.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    transcribed.as_str(),
+                    SearchSource::Transcript,
+                    "needle-transcript"
+                ),
+                (live.as_str(), SearchSource::Scrollback, "needle-live"),
+            ]
+        );
+        assert!(matches[0].time.is_some());
+
+        let options = SearchOptions {
+            case_sensitive: true,
+            session_ids: vec![live.clone()],
+            ..SearchOptions::default()
+        };
+        assert!(manager.search("NEEDLE", &options).unwrap().is_empty());
+        assert_eq!(manager.search("needle", &options).unwrap().len(), 1);
+
+        let options = SearchOptions {
+            until: Some(1000.0),
+            ..SearchOptions::default()
+        };
+        assert!(manager.search("needle", &options).unwrap().is_empty());
+
+        // Scrollback lines are filtered by when they were written too
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as f64;
+        let options = SearchOptions {
+            since: Some(now + 60_000.0),
+            ..SearchOptions::default()
+        };
+        assert!(manager.search("needle", &options).unwrap().is_empty());
+        let options = SearchOptions {
+            since: Some(now - 60_000.0),
+            session_ids: vec![live.clone()],
+            ..SearchOptions::default()
+        };
+        let matches = manager.search("needle", &options).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].time.is_some());
+        assert!(matches!(
+            manager.search("", &SearchOptions::default()),
+            Err(PtyError::ValidationError { .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_list_sessions() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel1, _rx1) = test_channel();
         let (channel2, _rx2) = test_channel();
 
@@ -527,28 +2028,592 @@ mod tests {
             .spawn(interactive_spawn_options(), channel2)
             .unwrap();
 
-        let sessions = manager.list();
-        assert_eq!(sessions.len(), 2);
+        let sessions = manager.list();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            sessions[0].id, id1,
+            "Sessions should be listed oldest first"
+        );
+        assert_eq!(sessions[1].id, id2);
+
+        let info = &sessions[0];
+        assert!(info.is_alive);
+        assert!(info.pid.is_some());
+        assert_eq!(info.shell, "/bin/sh");
+        assert_eq!(info.args, vec!["-l".to_string()]);
+        assert_eq!((info.cols, info.rows), (80, 24));
+        assert!(info.created_at > 0.0);
+        assert!(info.title.is_none());
+
+        manager.resize(&id1, 120, 40).unwrap();
+        let sessions = manager.list();
+        assert_eq!((sessions[0].cols, sessions[0].rows), (120, 40));
+
+        manager.kill(&id1).unwrap();
+        let sessions = manager.list();
+        assert_eq!(sessions.len(), 1);
+
+        manager.kill(&id2).unwrap();
+    }
+
+    #[test]
+    fn test_list_reports_cwd() {
+        let manager = PtyManager::new();
+        let (channel, _rx) = test_channel();
+
+        let cwd = std::env::temp_dir().canonicalize().unwrap();
+        let options = SpawnOptions {
+            cwd: Some(cwd.to_string_lossy().into_owned()),
+            ..interactive_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+
+        let sessions = manager.list();
+        assert_eq!(
+            sessions[0].cwd.as_deref(),
+            Some(normalize_path_for_serialization(&cwd).as_str())
+        );
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_set_title() {
+        let manager = PtyManager::new();
+        let (channel, _rx) = test_channel();
+
+        let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
+
+        manager
+            .set_title(&session_id, Some("  claude: refactor  ".to_string()))
+            .unwrap();
+        assert_eq!(manager.list()[0].title.as_deref(), Some("claude: refactor"));
+
+        // Blank titles clear the title
+        manager
+            .set_title(&session_id, Some("   ".to_string()))
+            .unwrap();
+        assert!(manager.list()[0].title.is_none());
+
+        let result = manager.set_title(&session_id, Some("x".repeat(MAX_TITLE_LENGTH + 1)));
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_reflects_screen() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "printf 'first\\nsecond\\033[1;1HFIRST'".to_string(),
+            ],
+            track_screen: true,
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        collect_events(&rx, std::time::Duration::from_secs(3), |_, exited| exited);
+
+        let snapshot = manager.snapshot(&session_id, SnapshotFormat::Text).unwrap();
+        assert_eq!((snapshot.rows, snapshot.cols), (24, 80));
+        assert_eq!(snapshot.lines[0], "FIRST");
+        assert_eq!(snapshot.lines[1], "second");
+        assert_eq!((snapshot.cursor_row, snapshot.cursor_col), (0, 5));
+
+        manager.resize(&session_id, 100, 30).unwrap();
+        let snapshot = manager
+            .snapshot(&session_id, SnapshotFormat::Cells)
+            .unwrap();
+        assert_eq!((snapshot.rows, snapshot.cols), (30, 100));
+        assert_eq!(snapshot.cells.unwrap()[0][0].text, "F");
+
+        let _ = manager.kill(&session_id);
+    }
+
+    #[test]
+    fn test_snapshot_requires_screen_tracking() {
+        let manager = PtyManager::new();
+        let (channel, _rx) = test_channel();
+
+        let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
+        let result = manager.snapshot(&session_id, SnapshotFormat::Text);
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_osc_133_marks_become_command_events() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "printf 'x\\033]133;A\\007$ \\033]133;C;cmdline_url=make%%20test\\007out\\033]133;D;2\\007'"
+                    .to_string(),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+
+        let mut events = Vec::new();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::Exit { .. }) | Err(_) => break,
+                Ok(event) => events.push(event),
+            }
+        }
+
+        // Each event follows the output that ends with its mark
+        let mut output = Vec::new();
+        let mut summary = Vec::new();
+        for event in events {
+            match event {
+                PtyEvent::Output { data, .. } => output.extend_from_slice(&data),
+                PtyEvent::PromptStart => {
+                    assert!(output.ends_with(b"\x1b]133;A\x07"));
+                    summary.push("prompt".to_string());
+                }
+                PtyEvent::CommandStart { command } => {
+                    assert!(output.ends_with(b"make%20test\x07"));
+                    summary.push(format!("start {}", command.unwrap()));
+                }
+                PtyEvent::CommandFinished { exit_code, .. } => {
+                    assert!(output.ends_with(b"\x1b]133;D;2\x07"));
+                    summary.push(format!("finish {}", exit_code.unwrap()));
+                }
+                other => panic!("Unexpected event: {other:?}"),
+            }
+        }
+        assert_eq!(summary, vec!["prompt", "start make test", "finish 2"]);
+
+        let _ = manager.kill(&session_id);
+    }
+
+    #[test]
+    fn test_osc_7_updates_current_cwd() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "printf '\\033]7;file://host/srv/my%%20repo\\007'; \
+                 printf '\\033]7;file://host/srv/my%%20repo\\007'; sleep 5"
+                    .to_string(),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        let start_cwd = manager.list()[0].cwd.clone();
+        assert!(start_cwd.is_some());
+
+        let mut changes = Vec::new();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(1500);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::CwdChanged { cwd }) => changes.push(cwd),
+                Ok(PtyEvent::Exit { .. }) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+
+        // Repeated reports of the same directory are not events
+        assert_eq!(changes, vec!["/srv/my repo"]);
+        let info = &manager.list()[0];
+        assert_eq!(info.current_cwd.as_deref(), Some("/srv/my repo"));
+        assert_eq!(info.cwd, start_cwd);
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_osc_title_updates_terminal_title() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "printf '\\033]0;vim\\007\\033]2;vim\\033\\\\'; \
+                 printf '\\033]1;icon\\007\\033]2;claude\\007'; sleep 5"
+                    .to_string(),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        manager
+            .set_title(&session_id, Some("my session".to_string()))
+            .unwrap();
+
+        let mut titles = Vec::new();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(1500);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::TitleChanged { title }) => titles.push(title),
+                Ok(PtyEvent::Exit { .. }) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+
+        // Repeated titles and the icon name are not events
+        assert_eq!(titles, vec!["vim", "claude"]);
+        let info = &manager.list()[0];
+        assert_eq!(info.terminal_title.as_deref(), Some("claude"));
+        assert_eq!(info.title.as_deref(), Some("my session"));
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    /// Polls until the foreground process of a session matches `predicate`.
+    fn wait_for_foreground(
+        manager: &PtyManager,
+        session_id: &str,
+        predicate: impl Fn(&ForegroundProcess) -> bool,
+    ) -> Option<ForegroundProcess> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::time::Instant::now() < deadline {
+            let process = manager.foreground_process(session_id).unwrap();
+            if let Some(process) = process.filter(&predicate) {
+                return Some(process);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        None
+    }
+
+    #[test]
+    fn test_foreground_process_follows_jobs() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            command: Some("/bin/bash".to_string()),
+            args: vec![
+                "--norc".to_string(),
+                "--noprofile".to_string(),
+                "-i".to_string(),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        let shell_pid = manager.list()[0].pid.unwrap();
+
+        // Wait for the shell to start (before exec, the child still has the
+        // name of the spawning thread)
+        let idle = wait_for_foreground(&manager, &session_id, |p| p.is_shell && p.name == "bash")
+            .expect("Shell never became the foreground process");
+        assert_eq!(idle.pid, shell_pid);
+
+        manager.write(&session_id, b"sleep 5\r").unwrap();
+
+        let mut job = None;
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while job.is_none() {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::ForegroundChanged {
+                    process: Some(process),
+                }) if !process.is_shell => job = Some(process),
+                Ok(PtyEvent::Exit { .. }) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        let job = job.expect("No ForegroundChanged event for the job");
+        assert_eq!(job.name, "sleep");
+        assert_eq!(job.argv, vec!["sleep", "5"]);
+        assert_ne!(job.pid, shell_pid);
+        assert_eq!(manager.foreground_process(&session_id).unwrap(), Some(job));
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_signal_targets_foreground_job() {
+        let manager = PtyManager::new();
+        let (channel, _rx) = test_channel();
+
+        let options = SpawnOptions {
+            command: Some("/bin/bash".to_string()),
+            args: vec![
+                "--norc".to_string(),
+                "--noprofile".to_string(),
+                "-i".to_string(),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        wait_for_foreground(&manager, &session_id, |p| p.is_shell && p.name == "bash")
+            .expect("Shell never started");
+
+        // Leave unsent input in the buffer: the signal must not depend on it
+        manager
+            .write(&session_id, b"sleep 30\rpartial input")
+            .unwrap();
+        let job = wait_for_foreground(&manager, &session_id, |p| p.name == "sleep")
+            .expect("Job never became the foreground process");
+
+        manager.signal(&session_id, Signal::Stop).unwrap();
+        wait_for_foreground(&manager, &session_id, |p| p.is_shell)
+            .expect("Shell did not take back the terminal after SIGTSTP");
+
+        manager.write(&session_id, b"\x15fg\r").unwrap();
+        let resumed = wait_for_foreground(&manager, &session_id, |p| p.name == "sleep")
+            .expect("Job was not resumed");
+        assert_eq!(resumed.pid, job.pid);
+
+        manager.signal(&session_id, Signal::Interrupt).unwrap();
+        wait_for_foreground(&manager, &session_id, |p| p.is_shell)
+            .expect("Job was not interrupted");
+        assert!(manager.list()[0].is_alive, "SIGINT must not kill the shell");
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_signal_unknown_session() {
+        let manager = PtyManager::new();
+        assert!(matches!(
+            manager.signal("missing", Signal::Interrupt),
+            Err(PtyError::SessionNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_signal_reaped_session_is_rejected() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+        let session_id = manager.spawn(default_spawn_options(), channel).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::Exit { .. }) => break,
+                Ok(_) => {}
+                Err(e) => panic!("Session did not exit: {e}"),
+            }
+        }
+
+        assert!(*lock(&manager.get(&session_id).unwrap().reaped));
+        assert!(matches!(
+            manager.signal(&session_id, Signal::Interrupt),
+            Err(PtyError::ValidationError { .. })
+        ));
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_foreground_process_unknown_session() {
+        let manager = PtyManager::new();
+        assert!(matches!(
+            manager.foreground_process("missing"),
+            Err(PtyError::SessionNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bash_shell_integration_reports_commands() {
+        let manager = PtyManager::new();
+        let dir = std::env::temp_dir().join(format!("pty-shell-integration-{}", Uuid::new_v4()));
+        manager.set_shell_integration_dir(dir.clone());
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            command: Some("/bin/bash".to_string()),
+            shell_integration: true,
+            ..interactive_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        manager.write(&session_id, b"cd /\rfalse\r").unwrap();
+
+        let mut command = None;
+        let mut exit_code = None;
+        let mut cwd = None;
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while command.as_deref() != Some("false") || exit_code.is_none() {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::CommandStart { command: c }) => {
+                    command = c;
+                    exit_code = None;
+                }
+                Ok(PtyEvent::CommandFinished {
+                    exit_code: code, ..
+                }) => exit_code = code,
+                Ok(PtyEvent::CwdChanged { cwd: c }) => cwd = Some(c),
+                Ok(PtyEvent::Exit { .. }) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        assert_eq!(command.as_deref(), Some("false"));
+        assert_eq!(exit_code, Some(1));
+        assert_eq!(cwd.as_deref(), Some("/"));
+        assert_eq!(manager.list()[0].current_cwd.as_deref(), Some("/"));
+
+        manager.kill(&session_id).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_user_zdotdir_precedence() {
+        let zdotdir = |dir: &str| HashMap::from([("ZDOTDIR".to_string(), dir.to_string())]);
+        let none = HashMap::new();
+
+        let login = zdotdir("/login");
+        assert_eq!(
+            user_zdotdir(&none, Some(&none), Some(&login)).as_deref(),
+            Some("/login")
+        );
+        let profile = zdotdir("/profile");
+        assert_eq!(
+            user_zdotdir(&none, Some(&profile), Some(&login)).as_deref(),
+            Some("/profile")
+        );
+        assert_eq!(
+            user_zdotdir(&zdotdir("/session"), Some(&profile), Some(&login)).as_deref(),
+            Some("/session")
+        );
+    }
+
+    #[test]
+    fn test_blocked_write_does_not_stall_other_sessions() {
+        let manager = Arc::new(PtyManager::new());
+
+        // A process that never reads stdin, so a large write blocks once the
+        // PTY input queue is full
+        let (blocked_channel, _blocked_rx) = test_channel();
+        let blocked_options = SpawnOptions {
+            args: vec!["-c".to_string(), "sleep 30".to_string()],
+            ..default_spawn_options()
+        };
+        let blocked_id = manager.spawn(blocked_options, blocked_channel).unwrap();
+
+        let (channel, rx) = test_channel();
+        let other_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
+        collect_output_until(&rx, std::time::Duration::from_secs(3), |s| !s.is_empty());
+
+        let writer_manager = Arc::clone(&manager);
+        let writer_id = blocked_id.clone();
+        let writer = std::thread::spawn(move || {
+            let data = vec![b'x'; 1024 * 1024];
+            writer_manager.write(&writer_id, &data)
+        });
+
+        // Give the writer time to fill the input queue and block
+        std::thread::sleep(std::time::Duration::from_millis(300));
+
+        let start = std::time::Instant::now();
+        manager.resize(&other_id, 100, 30).unwrap();
+        let marker = "UNBLOCKED_SESSION_MARKER";
+        manager
+            .write(&other_id, format!("echo {marker}\r").as_bytes())
+            .unwrap();
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(1),
+            "Commands on another session were stalled by a blocked write"
+        );
+
+        let output = collect_output_until(&rx, std::time::Duration::from_secs(3), |s| {
+            s.matches(marker).count() >= 2
+        });
+        assert!(output.matches(marker).count() >= 2);
+
+        manager.kill(&other_id).unwrap();
+        manager.kill(&blocked_id).unwrap();
+        let _ = writer.join();
+    }
+
+    #[test]
+    fn test_concurrent_writes_arrive_in_order() {
+        let manager = Arc::new(PtyManager::new());
+        let (channel, rx) = test_channel();
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "stty raw -echo; echo READY; cat".to_string(),
+            ],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("READY")
+        });
+
+        // Writes from several threads, numbered in the order they are made
+        let next = Arc::new(Mutex::new(0));
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let manager = Arc::clone(&manager);
+                let session_id = session_id.clone();
+                let next = Arc::clone(&next);
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        let mut next = lock(&next);
+                        manager
+                            .write(&session_id, format!("{:03},", *next).as_bytes())
+                            .unwrap();
+                        *next += 1;
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let expected: String = (0..400).map(|i| format!("{i:03},")).collect();
+        let output = collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("399,")
+        });
+        assert!(
+            output.contains(&expected),
+            "Writes out of order: {output:?}"
+        );
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_set_allowed_shells() {
+        let manager = PtyManager::new();
 
-        manager.kill(&id1).unwrap();
-        let sessions = manager.list();
-        assert_eq!(sessions.len(), 1);
+        manager.set_allowed_shells(&[]);
+        let (channel, _rx) = test_channel();
+        let result = manager.spawn(default_spawn_options(), channel);
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
 
-        manager.kill(&id2).unwrap();
+        manager.set_allowed_shells(&["/bin/sh".to_string()]);
+        let (channel, _rx) = test_channel();
+        let session_id = manager.spawn(default_spawn_options(), channel).unwrap();
+        manager.kill(&session_id).unwrap();
     }
 
     #[test]
     fn test_reject_disallowed_shell() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let options = SpawnOptions {
             command: Some("/usr/bin/python3".to_string()),
+            program: None,
             args: vec![],
             cwd: None,
             env: HashMap::new(),
             cols: 80,
             rows: 24,
+            scrollback_limit: None,
+            track_screen: false,
+            shell_integration: false,
+            profile: None,
+            transcript: false,
         };
 
         let result = manager.spawn(options, channel);
@@ -560,18 +2625,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_program_policy_is_enforced() {
+        let manager = PtyManager::new();
+        let program = |program: &str, args: &[&str]| SpawnOptions {
+            command: None,
+            program: Some(program.to_string()),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            ..default_spawn_options()
+        };
+
+        for (options, error) in [
+            // Allowed shells are not allowed programs
+            (program("/bin/sh", &[]), "Program not allowed"),
+            (program("sh", &["-c", "true"]), "Program not allowed"),
+            (
+                program("claude", &["--dangerously-skip-permissions"]),
+                "Option not allowed",
+            ),
+            (
+                SpawnOptions {
+                    command: Some("/bin/sh".to_string()),
+                    ..program("claude", &[])
+                },
+                "both a command and a program",
+            ),
+        ] {
+            let (channel, _rx) = test_channel();
+            match manager.spawn(options, channel) {
+                Err(PtyError::ValidationError { message }) => {
+                    assert!(message.contains(error), "{message:?} !~ {error:?}")
+                }
+                other => panic!("Expected ValidationError with {error:?}, got {other:?}"),
+            }
+        }
+        assert!(manager.list().is_empty());
+    }
+
     #[test]
     fn test_reject_invalid_cwd() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let options = SpawnOptions {
             command: Some("/bin/sh".to_string()),
+            program: None,
             args: vec![],
             cwd: Some("/nonexistent/path/that/does/not/exist".to_string()),
             env: HashMap::new(),
             cols: 80,
             rows: 24,
+            scrollback_limit: None,
+            track_screen: false,
+            shell_integration: false,
+            profile: None,
+            transcript: false,
         };
 
         let result = manager.spawn(options, channel);
@@ -585,7 +2693,7 @@ mod tests {
 
     #[test]
     fn test_blocked_env_vars_are_filtered() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let mut env = HashMap::new();
@@ -594,11 +2702,17 @@ mod tests {
 
         let options = SpawnOptions {
             command: Some("/bin/sh".to_string()),
+            program: None,
             args: vec![],
             cwd: None,
             env,
             cols: 80,
             rows: 24,
+            scrollback_limit: None,
+            track_screen: false,
+            shell_integration: false,
+            profile: None,
+            transcript: false,
         };
 
         // Should succeed — blocked vars are filtered, not rejected
@@ -607,6 +2721,175 @@ mod tests {
         manager.kill(&result.unwrap()).unwrap();
     }
 
+    #[test]
+    fn test_env_profile_is_applied() {
+        use crate::types::EnvVariable;
+
+        let manager = PtyManager::new();
+        let variable = |key: &str, value: &str| EnvVariable {
+            key: key.to_string(),
+            value: Some(value.to_string()),
+            secret: true,
+        };
+        manager.set_env_profiles(&[EnvProfile {
+            name: "work".to_string(),
+            variables: vec![
+                variable("PROFILE_ONLY", "from_profile"),
+                variable("OVERRIDDEN", "from_profile"),
+                variable("LD_PRELOAD", "/tmp/evil.so"),
+            ],
+        }]);
+
+        let (channel, rx) = test_channel();
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "echo \"vars: $PROFILE_ONLY $OVERRIDDEN ${LD_PRELOAD:-unset}\"".to_string(),
+            ],
+            env: HashMap::from([("OVERRIDDEN".to_string(), "from_options".to_string())]),
+            profile: Some("work".to_string()),
+            ..default_spawn_options()
+        };
+        manager.spawn(options, channel).unwrap();
+
+        let output = collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("vars: ")
+        });
+        assert!(
+            output.contains("vars: from_profile from_options unset"),
+            "Unexpected output: {output:?}"
+        );
+
+        let (channel, _rx) = test_channel();
+        let options = SpawnOptions {
+            profile: Some("missing".to_string()),
+            ..default_spawn_options()
+        };
+        let result = manager.spawn(options, channel);
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_secret_references_are_resolved() {
+        let dir = std::env::temp_dir().join(format!("pty-secrets-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let manager = PtyManager::new();
+        manager.secrets().open(&dir).unwrap();
+        manager
+            .secrets()
+            .set("api", "sk-from-vault".to_string())
+            .unwrap();
+
+        let (channel, rx) = test_channel();
+        let options = SpawnOptions {
+            args: vec!["-c".to_string(), "echo \"key: $API_KEY\"".to_string()],
+            env: HashMap::from([("API_KEY".to_string(), "${secret:api}".to_string())]),
+            ..default_spawn_options()
+        };
+        manager.spawn(options, channel).unwrap();
+
+        let output = collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("key: ")
+        });
+        assert!(
+            output.contains("key: sk-from-vault"),
+            "Unexpected output: {output:?}"
+        );
+
+        let (channel, _rx) = test_channel();
+        let options = SpawnOptions {
+            env: HashMap::from([("API_KEY".to_string(), "${secret:missing}".to_string())]),
+            ..default_spawn_options()
+        };
+        let result = manager.spawn(options, channel);
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_secret_references_in_env_profiles_are_resolved() {
+        use crate::types::EnvVariable;
+
+        let dir = std::env::temp_dir().join(format!("pty-secrets-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let manager = PtyManager::new();
+        manager.secrets().open(&dir).unwrap();
+        manager
+            .secrets()
+            .set("ANTHROPIC_API_KEY", "sk-from-vault".to_string())
+            .unwrap();
+        let profile = |name: &str, secret: &str| EnvProfile {
+            name: name.to_string(),
+            variables: vec![EnvVariable {
+                key: "API_KEY".to_string(),
+                value: Some(format!("${{secret:{secret}}}")),
+                secret: false,
+            }],
+        };
+        manager.set_env_profiles(&[
+            profile("work", "ANTHROPIC_API_KEY"),
+            profile("broken", "missing"),
+        ]);
+
+        let (channel, rx) = test_channel();
+        let options = SpawnOptions {
+            args: vec!["-c".to_string(), "echo \"key: $API_KEY\"".to_string()],
+            profile: Some("work".to_string()),
+            ..default_spawn_options()
+        };
+        manager.spawn(options, channel).unwrap();
+
+        let output = collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("key: ")
+        });
+        assert!(
+            output.contains("key: sk-from-vault"),
+            "Unexpected output: {output:?}"
+        );
+
+        let (channel, _rx) = test_channel();
+        let options = SpawnOptions {
+            profile: Some("broken".to_string()),
+            ..default_spawn_options()
+        };
+        let result = manager.spawn(options, channel);
+        assert!(matches!(result, Err(PtyError::ValidationError { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_login_environment_is_merged() {
+        let manager = PtyManager::new();
+        manager.login_environment().set(HashMap::from([
+            ("LOGIN_ONLY".to_string(), "from_login".to_string()),
+            ("OVERRIDDEN".to_string(), "from_login".to_string()),
+            ("LD_PRELOAD".to_string(), "/tmp/evil.so".to_string()),
+        ]));
+
+        let (channel, rx) = test_channel();
+        let options = SpawnOptions {
+            args: vec![
+                "-c".to_string(),
+                "echo \"vars: $LOGIN_ONLY $OVERRIDDEN ${LD_PRELOAD:-unset}\"".to_string(),
+            ],
+            env: HashMap::from([("OVERRIDDEN".to_string(), "from_options".to_string())]),
+            ..default_spawn_options()
+        };
+        manager.spawn(options, channel).unwrap();
+
+        // Options take precedence over the login environment, and blocked
+        // variables are not passed from either
+        let output = collect_output_until(&rx, std::time::Duration::from_secs(5), |s| {
+            s.contains("vars: ")
+        });
+        assert!(
+            output.contains("vars: from_login from_options unset"),
+            "Unexpected output: {output:?}"
+        );
+    }
+
     /// Collects output from the channel until the predicate returns true or timeout.
     /// Returns the accumulated output as a String.
     fn collect_output_until(
@@ -623,7 +2906,7 @@ mod tests {
                 break;
             }
             match rx.recv_timeout(remaining) {
-                Ok(PtyEvent::Output { data }) => {
+                Ok(PtyEvent::Output { data, .. }) => {
                     let chunk = String::from_utf8_lossy(&data);
                     output.push_str(&chunk);
                     if predicate(&output) {
@@ -631,6 +2914,8 @@ mod tests {
                     }
                 }
                 Ok(PtyEvent::Exit { .. }) | Ok(PtyEvent::Error { .. }) => break,
+                // Other events are not relevant here
+                Ok(_) => {}
                 Err(_) => break,
             }
         }
@@ -655,7 +2940,7 @@ mod tests {
                 break;
             }
             match rx.recv_timeout(remaining) {
-                Ok(PtyEvent::Output { data }) => {
+                Ok(PtyEvent::Output { data, .. }) => {
                     let chunk = String::from_utf8_lossy(&data);
                     output.push_str(&chunk);
                     if stop_predicate(&output, exited) {
@@ -671,6 +2956,8 @@ mod tests {
                 Ok(PtyEvent::Error { message }) => {
                     panic!("Unexpected error event from PTY: {message}");
                 }
+                // Other events are not relevant here
+                Ok(_) => {}
                 Err(_) => break,
             }
         }
@@ -684,11 +2971,17 @@ mod tests {
     fn user_scenario_spawn_options() -> SpawnOptions {
         SpawnOptions {
             command: None, // Uses $SHELL (like TerminalPanel sends null)
-            args: vec![],  // Empty args (like TerminalPanel)
+            program: None,
+            args: vec![], // Empty args (like TerminalPanel)
             cwd: None,
             env: HashMap::new(),
             cols: 99, // Match typical terminal dimensions
             rows: 57,
+            scrollback_limit: None,
+            track_screen: false,
+            shell_integration: false,
+            profile: None,
+            transcript: false,
         }
     }
 
@@ -696,7 +2989,7 @@ mod tests {
     fn test_user_scenario_spawn_succeeds() {
         // Test: TerminalPanel calls ptySpawn({ command: null, args: [], ... })
         // Expected: spawn succeeds without error
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, _rx) = test_channel();
 
         let result = manager.spawn(user_scenario_spawn_options(), channel);
@@ -714,7 +3007,7 @@ mod tests {
         // Test: After spawn, shell should NOT exit within 2 seconds.
         // If this fails, the shell is crashing immediately after start.
         // This matches the "연결 끊김" bug — Exit event arrives too soon.
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager
@@ -740,7 +3033,7 @@ mod tests {
     fn test_user_scenario_receives_prompt() {
         // Test: After spawn with command=None, shell should produce output (prompt).
         // This tests whether the user sees anything in the terminal.
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager
@@ -768,7 +3061,7 @@ mod tests {
     fn test_user_scenario_command_execution() {
         // Test: User types "echo hello" and presses Enter in the terminal.
         // xterm.js sends \r for Enter key.
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager
@@ -811,7 +3104,7 @@ mod tests {
     fn test_user_scenario_channel_continues_working() {
         // Test: Channel keeps receiving events after spawn returns.
         // This verifies the Channel doesn't get invalidated.
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager
@@ -855,7 +3148,7 @@ mod tests {
 
     #[test]
     fn test_interactive_shell_produces_output_on_spawn() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
@@ -874,7 +3167,7 @@ mod tests {
 
     #[test]
     fn test_interactive_command_execution() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
@@ -906,7 +3199,7 @@ mod tests {
 
     #[test]
     fn test_enter_key_triggers_execution() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
         let (channel, rx) = test_channel();
 
         let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
@@ -935,26 +3228,36 @@ mod tests {
 
     #[test]
     fn test_pty_event_json_matches_frontend_types() {
-        // Output event — frontend expects: {"event":"Output","data":{"data":[...]}}
+        // Output event — frontend expects: {"event":"Output","data":{"data":"<base64>"}}
         let event = PtyEvent::Output {
             data: vec![72, 101, 108],
+            seq: 1,
         };
         let value: serde_json::Value = serde_json::to_value(&event).unwrap();
         assert_eq!(value["event"], "Output");
-        assert!(value["data"]["data"].is_array());
-        assert_eq!(value["data"]["data"], serde_json::json!([72, 101, 108]));
+        assert!(value["data"]["data"].is_string());
+        assert_eq!(value["data"]["data"], "SGVs");
+        assert_eq!(value["data"]["seq"], 1);
 
         // Exit event with code
-        let event = PtyEvent::Exit { code: Some(0) };
+        let event = PtyEvent::Exit {
+            code: Some(0),
+            signal: None,
+        };
         let value: serde_json::Value = serde_json::to_value(&event).unwrap();
         assert_eq!(value["event"], "Exit");
         assert_eq!(value["data"]["code"], 0);
+        assert!(value["data"]["signal"].is_null());
 
-        // Exit event with null code
-        let event = PtyEvent::Exit { code: None };
+        // Exit event terminated by a signal
+        let event = PtyEvent::Exit {
+            code: None,
+            signal: Some("SIGINT".to_string()),
+        };
         let value: serde_json::Value = serde_json::to_value(&event).unwrap();
         assert_eq!(value["event"], "Exit");
         assert!(value["data"]["code"].is_null());
+        assert_eq!(value["data"]["signal"], "SIGINT");
 
         // Error event
         let event = PtyEvent::Error {
@@ -978,13 +3281,14 @@ mod tests {
         channel
             .send(PtyEvent::Output {
                 data: vec![72, 101],
+                seq: 1,
             })
             .unwrap();
         let json = rx.recv_timeout(std::time::Duration::from_secs(1)).unwrap();
         let value: serde_json::Value = serde_json::from_str(&json).unwrap();
 
         assert_eq!(value["event"], "Output");
-        assert_eq!(value["data"]["data"], serde_json::json!([72, 101]));
+        assert_eq!(value["data"]["data"], "SGU=");
     }
 
     #[test]
@@ -997,11 +3301,335 @@ mod tests {
         assert!(options.env.is_empty());
         assert_eq!(options.cols, 80);
         assert_eq!(options.rows, 38);
+        assert!(options.scrollback_limit.is_none());
+    }
+
+    #[test]
+    fn test_scrollback_contains_output() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let session_id = manager.spawn(default_spawn_options(), channel).unwrap();
+
+        // Wait for the command to finish so all output is buffered
+        let (_, exited) = collect_events(&rx, std::time::Duration::from_secs(5), |_, did_exit| {
+            did_exit
+        });
+        assert!(exited, "Expected echo command to exit");
+
+        let snapshot = manager.scrollback(&session_id).unwrap();
+        let output = String::from_utf8_lossy(&snapshot.data);
+        assert!(
+            output.contains("hello from pty"),
+            "Expected 'hello from pty' in scrollback, got: {output}"
+        );
+        assert!(snapshot.seq > 0);
+        assert!(!snapshot.truncated);
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_attach_replays_and_resumes_output() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let session_id = manager.spawn(interactive_spawn_options(), channel).unwrap();
+        collect_output_until(&rx, std::time::Duration::from_secs(3), |s| !s.is_empty());
+
+        let marker = "BEFORE_ATTACH_MARKER";
+        manager
+            .write(&session_id, format!("echo {marker}\r").as_bytes())
+            .unwrap();
+        collect_output_until(&rx, std::time::Duration::from_secs(3), |s| {
+            s.matches(marker).count() >= 2
+        });
+
+        // Simulate a webview reload: attach a fresh channel with full replay
+        let (new_channel, new_rx) = test_channel();
+        manager.attach(&session_id, new_channel, Some(0)).unwrap();
+
+        let replayed = collect_output_until(&new_rx, std::time::Duration::from_secs(3), |s| {
+            s.matches(marker).count() >= 2
+        });
+        assert!(
+            replayed.matches(marker).count() >= 2,
+            "Expected replayed scrollback to contain '{marker}', got: {replayed}"
+        );
+
+        let live_marker = "AFTER_ATTACH_MARKER";
+        manager
+            .write(&session_id, format!("echo {live_marker}\r").as_bytes())
+            .unwrap();
+        let live = collect_output_until(&new_rx, std::time::Duration::from_secs(3), |s| {
+            s.matches(live_marker).count() >= 2
+        });
+        assert!(
+            live.matches(live_marker).count() >= 2,
+            "Expected live output on attached channel, got: {live}"
+        );
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_reader_survives_dead_channel() {
+        let manager = PtyManager::new();
+        let dead_channel =
+            Channel::new(|_body: InvokeResponseBody| Err(tauri::Error::FailedToReceiveMessage));
+
+        let session_id = manager
+            .spawn(interactive_spawn_options(), dead_channel)
+            .unwrap();
+
+        let marker = "DEAD_CHANNEL_MARKER";
+        manager
+            .write(&session_id, format!("echo {marker}\r").as_bytes())
+            .unwrap();
+
+        // Output keeps being buffered while no channel is attached
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(3);
+        let mut output = String::new();
+        while std::time::Instant::now() < deadline {
+            let snapshot = manager.scrollback(&session_id).unwrap();
+            output = String::from_utf8_lossy(&snapshot.data).into_owned();
+            if output.matches(marker).count() >= 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        assert!(
+            output.matches(marker).count() >= 2,
+            "Expected reader to keep buffering after channel failure, got: {output}"
+        );
+
+        let (channel, rx) = test_channel();
+        manager.attach(&session_id, channel, None).unwrap();
+        manager.write(&session_id, b"exit\r").unwrap();
+
+        let (_, exited) = collect_events(&rx, std::time::Duration::from_secs(3), |_, did_exit| {
+            did_exit
+        });
+        assert!(exited, "Expected Exit event on the newly attached channel");
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_output_is_coalesced_in_order() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let mut options = default_spawn_options();
+        options.args = vec![
+            "-c".to_string(),
+            "i=0; while [ $i -lt 5000 ]; do echo line$i; i=$((i+1)); done".to_string(),
+        ];
+        let session_id = manager.spawn(options, channel).unwrap();
+
+        let mut output = Vec::new();
+        let mut last_seq = 0;
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::Output { data, seq }) => {
+                    assert!(
+                        data.len() < MAX_BATCH_BYTES + READ_BUFFER_SIZE,
+                        "Batch exceeded size cap: {} bytes",
+                        data.len()
+                    );
+                    assert_eq!(seq, last_seq + 1, "Output events must arrive in order");
+                    last_seq = seq;
+                    output.extend_from_slice(&data);
+                }
+                Ok(PtyEvent::Exit { .. }) => break,
+                Ok(PtyEvent::Error { message }) => panic!("Unexpected error: {message}"),
+                // Other events are not relevant here
+                Ok(_) => {}
+                Err(_) => panic!("Timed out waiting for Exit"),
+            }
+        }
+
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("line0\r\n"), "Missing first line");
+        assert!(output.contains("line4999\r\n"), "Missing last line");
+        assert_eq!(output.matches("line").count(), 5000);
+
+        let _ = manager.kill(&session_id);
+    }
+
+    #[test]
+    fn test_output_events_end_on_utf8_boundaries() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        // 3-byte characters, so 4KB reads would otherwise split them
+        let mut options = default_spawn_options();
+        options.args = vec![
+            "-c".to_string(),
+            "i=0; while [ $i -lt 3000 ]; do printf '한글'; i=$((i+1)); done".to_string(),
+        ];
+        let session_id = manager.spawn(options, channel).unwrap();
+
+        let mut total = String::new();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::Output { data, .. }) => {
+                    let chunk = std::str::from_utf8(&data)
+                        .expect("Output event should be valid UTF-8 on its own");
+                    total.push_str(chunk);
+                }
+                Ok(PtyEvent::Exit { .. }) => break,
+                Ok(PtyEvent::Error { message }) => panic!("Unexpected error: {message}"),
+                // Other events are not relevant here
+                Ok(_) => {}
+                Err(_) => panic!("Timed out waiting for Exit"),
+            }
+        }
+        assert_eq!(total.matches("한글").count(), 3000);
+
+        let _ = manager.kill(&session_id);
+    }
+
+    #[test]
+    fn test_attach_after_exit_sends_exit() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let session_id = manager.spawn(default_spawn_options(), channel).unwrap();
+        let (_, exited) = collect_events(&rx, std::time::Duration::from_secs(5), |_, did_exit| {
+            did_exit
+        });
+        assert!(exited);
+
+        let (new_channel, new_rx) = test_channel();
+        manager.attach(&session_id, new_channel, Some(0)).unwrap();
+        let (output, exited) =
+            collect_events(&new_rx, std::time::Duration::from_secs(1), |_, did_exit| {
+                did_exit
+            });
+        assert!(output.contains("hello from pty"));
+        assert!(exited, "Expected Exit event to be replayed after attach");
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_attach_reports_evicted_output() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec!["-c".to_string(), "yes | head -c 100000".to_string()],
+            scrollback_limit: Some(4096),
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+        let (_, exited) = collect_events(&rx, std::time::Duration::from_secs(5), |_, did_exit| {
+            did_exit
+        });
+        assert!(exited);
+
+        let (new_channel, _new_rx) = test_channel();
+        let info = manager.attach(&session_id, new_channel, Some(0)).unwrap();
+        assert!(info.truncated);
+
+        let seq = manager.scrollback(&session_id).unwrap().seq;
+        let (new_channel, _new_rx) = test_channel();
+        let info = manager.attach(&session_id, new_channel, Some(seq)).unwrap();
+        assert!(!info.truncated);
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    /// Waits for the Exit event and returns its code and signal.
+    fn wait_for_exit_event(
+        rx: &mpsc::Receiver<PtyEvent>,
+        timeout: std::time::Duration,
+    ) -> Option<(Option<i32>, Option<String>)> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(PtyEvent::Exit { code, signal }) => return Some((code, signal)),
+                Ok(_) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_exit_reports_exit_code() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec!["-c".to_string(), "exit 3".to_string()],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+
+        let exit = wait_for_exit_event(&rx, std::time::Duration::from_secs(5));
+        assert_eq!(exit, Some((Some(3), None)));
+
+        let sessions = manager.list();
+        assert!(!sessions[0].is_alive);
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_exit_reports_clean_exit() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let session_id = manager.spawn(default_spawn_options(), channel).unwrap();
+
+        let exit = wait_for_exit_event(&rx, std::time::Duration::from_secs(5));
+        assert_eq!(exit, Some((Some(0), None)));
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_reports_terminating_signal() {
+        let manager = PtyManager::new();
+        let (channel, rx) = test_channel();
+
+        let options = SpawnOptions {
+            args: vec!["-c".to_string(), "kill -INT $$".to_string()],
+            ..default_spawn_options()
+        };
+        let session_id = manager.spawn(options, channel).unwrap();
+
+        let exit = wait_for_exit_event(&rx, std::time::Duration::from_secs(5));
+        assert_eq!(exit, Some((None, Some("SIGINT".to_string()))));
+
+        manager.kill(&session_id).unwrap();
+    }
+
+    #[test]
+    fn test_attach_nonexistent_session() {
+        let manager = PtyManager::new();
+        let (channel, _rx) = test_channel();
+        let result = manager.attach("nonexistent", channel, None);
+        assert!(matches!(result, Err(PtyError::SessionNotFound { .. })));
+    }
+
+    #[test]
+    fn test_scrollback_nonexistent_session() {
+        let manager = PtyManager::new();
+        let result = manager.scrollback("nonexistent");
+        assert!(matches!(result, Err(PtyError::SessionNotFound { .. })));
     }
 
     #[test]
     fn test_session_limit() {
-        let mut manager = PtyManager::new();
+        let manager = PtyManager::new();
 
         // Spawn MAX_SESSIONS sessions
         let mut ids = Vec::new();
@@ -0,0 +1,129 @@
+//! Adaptive batching of PTY output.
+//!
+//! A fast-scrolling program produces many small reads; sending each one as
+//! its own IPC message floods the webview. Output is therefore coalesced into
+//! batches, while the first output after an idle period (typically the echo
+//! of a keystroke) is sent immediately to keep interactive latency low.
+
+use std::time::{Duration, Instant};
+
+/// Collects output chunks into batches bounded by time and size.
+pub struct Coalescer {
+    /// Output collected for the current batch
+    pending: Vec<u8>,
+    /// When the current batch must be flushed (None if nothing is pending)
+    deadline: Option<Instant>,
+    /// When the last batch was flushed
+    last_flush: Option<Instant>,
+    /// How long output is collected before a batch is flushed
+    window: Duration,
+    /// Batch size at which output is flushed without waiting for the window
+    max_batch: usize,
+}
+
+impl Coalescer {
+    pub fn new(window: Duration, max_batch: usize) -> Self {
+        Self {
+            pending: Vec::new(),
+            deadline: None,
+            last_flush: None,
+            window,
+            max_batch,
+        }
+    }
+
+    /// Adds a chunk of output. Returns a batch if it should be sent right away.
+    pub fn push(&mut self, data: &[u8], now: Instant) -> Option<Vec<u8>> {
+        let idle = self
+            .last_flush
+            .is_none_or(|last| now.duration_since(last) >= self.window);
+
+        self.pending.extend_from_slice(data);
+
+        if self.deadline.is_none() {
+            // First output after an idle period goes out immediately
+            if idle {
+                return self.flush(now);
+            }
+            self.deadline = Some(now + self.window);
+        }
+
+        if self.pending.len() >= self.max_batch {
+            return self.flush(now);
+        }
+
+        None
+    }
+
+    /// Returns when the pending batch must be flushed, if there is one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Takes the pending batch, if any.
+    pub fn flush(&mut self, now: Instant) -> Option<Vec<u8>> {
+        self.deadline = None;
+        if self.pending.is_empty() {
+            return None;
+        }
+        self.last_flush = Some(now);
+        Some(std::mem::take(&mut self.pending))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(8);
+
+    #[test]
+    fn test_first_output_after_idle_is_sent_immediately() {
+        let mut coalescer = Coalescer::new(WINDOW, 1024);
+        let start = Instant::now();
+
+        assert_eq!(coalescer.push(b"a", start), Some(b"a".to_vec()));
+        assert!(coalescer.deadline().is_none());
+
+        // Keystroke echo well after the window is also immediate
+        let later = start + WINDOW * 10;
+        assert_eq!(coalescer.push(b"b", later), Some(b"b".to_vec()));
+    }
+
+    #[test]
+    fn test_streaming_output_is_batched_within_window() {
+        let mut coalescer = Coalescer::new(WINDOW, 1024);
+        let start = Instant::now();
+
+        assert!(coalescer.push(b"line1\n", start).is_some());
+
+        let t1 = start + Duration::from_millis(1);
+        assert!(coalescer.push(b"line2\n", t1).is_none());
+        assert_eq!(coalescer.deadline(), Some(t1 + WINDOW));
+
+        let t2 = start + Duration::from_millis(2);
+        assert!(coalescer.push(b"line3\n", t2).is_none());
+
+        let batch = coalescer.flush(t1 + WINDOW).unwrap();
+        assert_eq!(batch, b"line2\nline3\n");
+        assert!(coalescer.deadline().is_none());
+    }
+
+    #[test]
+    fn test_batch_is_flushed_at_max_size() {
+        let mut coalescer = Coalescer::new(WINDOW, 8);
+        let start = Instant::now();
+
+        coalescer.push(b"x", start);
+        let t1 = start + Duration::from_millis(1);
+        assert!(coalescer.push(b"1234", t1).is_none());
+        assert_eq!(coalescer.push(b"5678", t1), Some(b"12345678".to_vec()));
+        assert!(coalescer.deadline().is_none());
+    }
+
+    #[test]
+    fn test_flush_without_pending_output() {
+        let mut coalescer = Coalescer::new(WINDOW, 8);
+        assert!(coalescer.flush(Instant::now()).is_none());
+    }
+}
@@ -0,0 +1,105 @@
+//! Detection of the process running in the foreground of a PTY.
+//!
+//! The foreground process group of a terminal is the job the shell handed
+//! the terminal to, or the shell itself while it waits at the prompt. Its
+//! leader is looked up with tcgetpgrp(3) on the master and described from
+//! /proc on Linux, or ps(1) on other Unix systems.
+
+use portable_pty::MasterPty;
+
+use crate::types::ForegroundProcess;
+
+/// Returns the leader of the PTY's foreground process group, or None if it
+/// cannot be determined (e.g. on Windows, or once the session has exited).
+pub fn foreground_process(
+    master: &dyn MasterPty,
+    shell_pid: Option<u32>,
+) -> Option<ForegroundProcess> {
+    #[cfg(unix)]
+    {
+        let pid = u32::try_from(master.process_group_leader()?).ok()?;
+        let (name, argv) = describe(pid)?;
+        Some(ForegroundProcess {
+            pid,
+            name,
+            argv,
+            is_shell: shell_pid == Some(pid),
+        })
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (master, shell_pid);
+        None
+    }
+}
+
+/// Returns the name and arguments of a process.
+#[cfg(target_os = "linux")]
+fn describe(pid: u32) -> Option<(String, Vec<String>)> {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    // Kernel threads and zombies have an empty command line
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).unwrap_or_default();
+    Some((name.trim_end().to_string(), parse_cmdline(&cmdline)))
+}
+
+/// Returns the name and arguments of a process.
+///
+/// ps(1) prints the arguments joined by spaces, so arguments containing
+/// spaces cannot be told apart.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn describe(pid: u32) -> Option<(String, Vec<String>)> {
+    let ps = |field: &str| -> Option<String> {
+        let output = std::process::Command::new("ps")
+            .args(["-o", field, "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !value.is_empty()).then_some(value)
+    };
+
+    let command = ps("comm=")?;
+    let name = std::path::Path::new(&command)
+        .file_name()
+        .map_or(command.clone(), |name| name.to_string_lossy().into_owned());
+    let argv = ps("args=")
+        .map(|args| args.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    Some((name, argv))
+}
+
+/// Splits a NUL-separated /proc command line into arguments.
+#[cfg(any(target_os = "linux", test))]
+fn parse_cmdline(cmdline: &[u8]) -> Vec<String> {
+    let cmdline = cmdline.strip_suffix(&[0]).unwrap_or(cmdline);
+    if cmdline.is_empty() {
+        return Vec::new();
+    }
+    cmdline
+        .split(|&b| b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(
+            parse_cmdline(b"node\0/usr/bin/claude\0--resume\0\0"),
+            vec!["node", "/usr/bin/claude", "--resume", ""]
+        );
+        assert_eq!(parse_cmdline(b"sleep\x005\0"), vec!["sleep", "5"]);
+        assert!(parse_cmdline(b"").is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_describe_current_process() {
+        let (name, argv) = describe(std::process::id()).unwrap();
+        assert!(!name.is_empty());
+        assert!(!argv.is_empty());
+    }
+}
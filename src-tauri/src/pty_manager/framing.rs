@@ -0,0 +1,261 @@
+//! Boundary-aware framing of PTY output.
+//!
+//! Reads from the PTY end wherever the read buffer fills up, which can be in
+//! the middle of a multi-byte UTF-8 character or an escape sequence. The
+//! framer holds back such an incomplete tail until the rest arrives, so every
+//! `Output` event can be decoded on its own.
+
+/// Maximum number of bytes held back for a single incomplete sequence.
+/// Larger sequences (e.g. an unterminated OSC string) are passed through as is.
+const MAX_PENDING_BYTES: usize = 65_536;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+
+/// Splits output into chunks that end on character and escape sequence
+/// boundaries.
+#[derive(Default)]
+pub struct Framer {
+    /// Incomplete trailing bytes held back from the previous push
+    pending: Vec<u8>,
+}
+
+impl Framer {
+    /// Appends output and returns everything up to the last complete
+    /// character or escape sequence. The incomplete tail is held back.
+    pub fn push(&mut self, data: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(data);
+
+        let mut complete = complete_len(&self.pending);
+        if self.pending.len() - complete > MAX_PENDING_BYTES {
+            complete = self.pending.len();
+        }
+
+        let tail = self.pending.split_off(complete);
+        std::mem::replace(&mut self.pending, tail)
+    }
+
+    /// Whether an incomplete sequence is being held back.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Releases the held-back bytes, complete or not.
+    pub fn take_pending(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Escape sequence parser state.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    /// Plain text
+    Ground,
+    /// After ESC
+    Escape,
+    /// After ESC and one or more intermediate bytes (e.g. `ESC (`)
+    EscapeIntermediate,
+    /// Inside a CSI sequence (`ESC [`)
+    Csi,
+    /// Inside a string sequence: OSC, DCS, SOS, PM or APC
+    String,
+    /// After ESC inside a string sequence (possibly the start of ST)
+    StringEscape,
+}
+
+/// Returns the length of the longest prefix of `data` that does not end in
+/// an incomplete escape sequence or UTF-8 character.
+fn complete_len(data: &[u8]) -> usize {
+    let mut state = State::Ground;
+    // Start of the escape sequence currently being parsed
+    let mut sequence_start = 0;
+
+    for (i, &byte) in data.iter().enumerate() {
+        if state == State::StringEscape {
+            if byte == b'\\' {
+                state = State::Ground;
+                continue;
+            }
+            // Not a string terminator: the ESC aborted the string and
+            // starts a new sequence
+            sequence_start = i - 1;
+            state = State::Escape;
+        }
+
+        state = match (state, byte) {
+            (State::String, BEL) => State::Ground,
+            (State::String, ESC) => State::StringEscape,
+            (State::String, CAN | SUB) => State::Ground,
+            (State::String, _) => State::String,
+            // CAN and SUB abort any sequence
+            (_, CAN | SUB) => State::Ground,
+            // ESC starts a new sequence, aborting an unfinished one
+            (_, ESC) => {
+                sequence_start = i;
+                State::Escape
+            }
+            (State::Ground, _) => State::Ground,
+            (State::Escape, b'[') => State::Csi,
+            (State::Escape, b']' | b'P' | b'X' | b'^' | b'_') => State::String,
+            (State::Escape | State::EscapeIntermediate, 0x20..=0x2f) => State::EscapeIntermediate,
+            // Any other byte completes a two-byte (or intermediate) sequence
+            (State::Escape | State::EscapeIntermediate, _) => State::Ground,
+            // CSI parameter and intermediate bytes
+            (State::Csi, 0x20..=0x3f) => State::Csi,
+            // CSI final byte, or a malformed sequence
+            (State::Csi, _) => State::Ground,
+            (State::StringEscape, _) => unreachable!("handled above"),
+        };
+    }
+
+    if state == State::Ground {
+        data.len() - incomplete_utf8_len(data)
+    } else {
+        sequence_start
+    }
+}
+
+/// Returns the number of trailing bytes that form an incomplete UTF-8 character.
+fn incomplete_utf8_len(data: &[u8]) -> usize {
+    // A character is at most 4 bytes, so its lead byte is within the last 3
+    for back in 1..=data.len().min(3) {
+        let byte = data[data.len() - back];
+        if byte & 0xc0 == 0x80 {
+            // Continuation byte
+            continue;
+        }
+        let width = match byte {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        return if width > back { back } else { 0 };
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `data` to a framer split at every possible position and checks
+    /// that each emitted chunk ends on a boundary and nothing is lost.
+    fn assert_split_everywhere(data: &[u8], is_boundary: impl Fn(&[u8]) -> bool) {
+        for split in 0..=data.len() {
+            let mut framer = Framer::default();
+            let first = framer.push(&data[..split]);
+            let second = framer.push(&data[split..]);
+            assert!(
+                is_boundary(&first),
+                "Chunk split at {split} ends mid-sequence: {first:?}"
+            );
+            assert!(!framer.has_pending(), "Bytes left pending at split {split}");
+
+            let mut joined = first;
+            joined.extend_from_slice(&second);
+            assert_eq!(joined, data, "Output lost or reordered at split {split}");
+        }
+    }
+
+    #[test]
+    fn test_ascii_passes_through() {
+        let mut framer = Framer::default();
+        assert_eq!(framer.push(b"hello"), b"hello");
+        assert!(!framer.has_pending());
+    }
+
+    #[test]
+    fn test_korean_text_is_never_split() {
+        let text = "안녕하세요 세계".as_bytes();
+        assert_split_everywhere(text, |chunk| std::str::from_utf8(chunk).is_ok());
+    }
+
+    #[test]
+    fn test_emoji_is_never_split() {
+        let text = "ok 👍🏽 done 🎉".as_bytes();
+        assert_split_everywhere(text, |chunk| std::str::from_utf8(chunk).is_ok());
+    }
+
+    #[test]
+    fn test_split_utf8_is_held_until_complete() {
+        let bytes = "한".as_bytes();
+        let mut framer = Framer::default();
+
+        assert_eq!(framer.push(&bytes[..1]), b"");
+        assert_eq!(framer.push(&bytes[1..2]), b"");
+        assert!(framer.has_pending());
+        assert_eq!(framer.push(&bytes[2..]), bytes);
+        assert!(!framer.has_pending());
+    }
+
+    #[test]
+    fn test_split_csi_is_held_until_final_byte() {
+        let mut framer = Framer::default();
+
+        assert_eq!(framer.push(b"red: \x1b[3"), b"red: ");
+        assert_eq!(framer.push(b"1;1"), b"");
+        assert_eq!(framer.push(b"mtext"), b"\x1b[31;1mtext");
+        assert!(!framer.has_pending());
+    }
+
+    #[test]
+    fn test_csi_is_never_split() {
+        let data = b"a\x1b[38;2;255;128;0mb\x1b[?1049hc\x1b[0m";
+        assert_split_everywhere(data, |chunk| complete_len(chunk) == chunk.len());
+    }
+
+    #[test]
+    fn test_lone_escape_is_held() {
+        let mut framer = Framer::default();
+        assert_eq!(framer.push(b"abc\x1b"), b"abc");
+        assert_eq!(framer.push(b"7"), b"\x1b7");
+    }
+
+    #[test]
+    fn test_osc_is_held_until_terminator() {
+        let mut framer = Framer::default();
+
+        assert_eq!(framer.push(b"\x1b]0;my ti"), b"");
+        assert_eq!(framer.push(b"tle\x07$ "), b"\x1b]0;my title\x07$ ");
+
+        // String terminator (ESC \) split between reads
+        assert_eq!(framer.push(b"\x1b]7;file:///tmp\x1b"), b"");
+        assert_eq!(framer.push(b"\\"), b"\x1b]7;file:///tmp\x1b\\");
+    }
+
+    #[test]
+    fn test_osc_with_utf8_title_is_never_split() {
+        let data = "\x1b]2;제목 ✨\x1b\\prompt".as_bytes();
+        assert_split_everywhere(data, |chunk| {
+            complete_len(chunk) == chunk.len() && std::str::from_utf8(chunk).is_ok()
+        });
+    }
+
+    #[test]
+    fn test_charset_designation_is_held() {
+        let mut framer = Framer::default();
+        assert_eq!(framer.push(b"x\x1b("), b"x");
+        assert_eq!(framer.push(b"B"), b"\x1b(B");
+    }
+
+    #[test]
+    fn test_oversized_sequence_is_released() {
+        let mut framer = Framer::default();
+        let mut data = b"\x1b]52;c;".to_vec();
+        data.resize(MAX_PENDING_BYTES + 16, b'A');
+
+        assert_eq!(framer.push(&data), data);
+        assert!(!framer.has_pending());
+    }
+
+    #[test]
+    fn test_take_pending_releases_incomplete_tail() {
+        let mut framer = Framer::default();
+        assert_eq!(framer.push(b"abc\x1b["), b"abc");
+        assert_eq!(framer.take_pending(), b"\x1b[");
+        assert!(!framer.has_pending());
+    }
+}
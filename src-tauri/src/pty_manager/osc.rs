@@ -0,0 +1,255 @@
+//! Extraction of OSC (Operating System Command) sequences from PTY output.
+//!
+//! Shells and programs report state such as command boundaries, the working
+//! directory or the window title through OSC sequences (`ESC ] ... BEL` or
+//! `ESC ] ... ESC \`). The scanner keeps its state between chunks, so a
+//! sequence split across reads is still recognised.
+
+/// Maximum payload length kept for a single OSC sequence.
+/// Longer sequences (e.g. OSC 52 clipboard data) are skipped.
+const MAX_PAYLOAD_BYTES: usize = 4096;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Ground,
+    /// After ESC
+    Escape,
+    /// Inside an OSC payload
+    Osc,
+    /// After ESC inside an OSC payload (possibly the start of ST)
+    OscEscape,
+}
+
+/// An OSC sequence found in a chunk of output.
+#[derive(Debug, PartialEq)]
+pub struct OscSequence {
+    /// Offset just past the sequence terminator within the chunk
+    pub end: usize,
+    /// Payload between `ESC ]` and the terminator, e.g. `133;A`
+    pub payload: String,
+}
+
+/// Incremental OSC sequence scanner.
+pub struct OscScanner {
+    state: State,
+    payload: Vec<u8>,
+    /// Whether the current payload exceeded `MAX_PAYLOAD_BYTES`
+    overflowed: bool,
+}
+
+impl Default for OscScanner {
+    fn default() -> Self {
+        Self {
+            state: State::Ground,
+            payload: Vec::new(),
+            overflowed: false,
+        }
+    }
+}
+
+impl OscScanner {
+    /// Scans a chunk of output and returns the OSC sequences completed in it.
+    pub fn scan(&mut self, data: &[u8]) -> Vec<OscSequence> {
+        let mut sequences = Vec::new();
+
+        for (i, &byte) in data.iter().enumerate() {
+            if self.state == State::OscEscape {
+                if byte == b'\\' {
+                    self.complete(i + 1, &mut sequences);
+                    continue;
+                }
+                // Not a string terminator: the ESC aborted the sequence
+                self.state = State::Escape;
+            }
+
+            self.state = match (self.state, byte) {
+                (State::Osc, BEL) => {
+                    self.complete(i + 1, &mut sequences);
+                    State::Ground
+                }
+                (State::Osc, ESC) => State::OscEscape,
+                (_, CAN | SUB) => State::Ground,
+                (State::Osc, _) => {
+                    if self.payload.len() < MAX_PAYLOAD_BYTES {
+                        self.payload.push(byte);
+                    } else {
+                        self.overflowed = true;
+                    }
+                    State::Osc
+                }
+                (_, ESC) => State::Escape,
+                (State::Escape, b']') => {
+                    self.payload.clear();
+                    self.overflowed = false;
+                    State::Osc
+                }
+                _ => State::Ground,
+            };
+        }
+
+        sequences
+    }
+
+    fn complete(&mut self, end: usize, sequences: &mut Vec<OscSequence>) {
+        self.state = State::Ground;
+        if !self.overflowed {
+            sequences.push(OscSequence {
+                end,
+                payload: String::from_utf8_lossy(&self.payload).into_owned(),
+            });
+        }
+        self.payload.clear();
+    }
+}
+
+/// Extracts the directory from an OSC 7 working directory report
+/// (`7;file://host/path`). Returns None for other sequences.
+pub fn working_directory(payload: &str) -> Option<String> {
+    let url = payload.strip_prefix("7;")?.strip_prefix("file://")?;
+    // The host is not checked: the path is informational only, and is
+    // validated again if it is used to spawn a session
+    let path = percent_decode(&url[url.find('/')?..]);
+
+    // Windows paths are reported as /C:/Users/...
+    match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => Some(path[1..].to_string()),
+        _ => Some(path),
+    }
+}
+
+/// Extracts the title from an OSC 0 or OSC 2 window title sequence
+/// (`2;title`). Returns None for other sequences, including OSC 1, which
+/// only sets the icon name. Control characters are removed.
+pub fn window_title(payload: &str) -> Option<String> {
+    let title = payload
+        .strip_prefix("0;")
+        .or_else(|| payload.strip_prefix("2;"))?;
+    Some(title.chars().filter(|c| !c.is_control()).collect())
+}
+
+/// Decodes `%XX` escapes. Invalid escapes are kept as is.
+pub fn percent_decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = encoded
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payloads(sequences: &[OscSequence]) -> Vec<&str> {
+        sequences.iter().map(|s| s.payload.as_str()).collect()
+    }
+
+    #[test]
+    fn test_bel_and_st_terminators() {
+        let mut scanner = OscScanner::default();
+        let data = b"a\x1b]133;A\x07$ \x1b]0;title\x1b\\b";
+
+        let sequences = scanner.scan(data);
+        assert_eq!(payloads(&sequences), vec!["133;A", "0;title"]);
+        assert_eq!(sequences[0].end, 9);
+        assert_eq!(sequences[1].end, data.len() - 1);
+    }
+
+    #[test]
+    fn test_sequence_split_across_chunks() {
+        let mut scanner = OscScanner::default();
+
+        assert!(scanner.scan(b"out\x1b]133;D").is_empty());
+        assert!(scanner.scan(b";1\x1b").is_empty());
+        let sequences = scanner.scan(b"\\rest");
+        assert_eq!(payloads(&sequences), vec!["133;D;1"]);
+        assert_eq!(sequences[0].end, 1);
+    }
+
+    #[test]
+    fn test_other_escape_sequences_are_ignored() {
+        let mut scanner = OscScanner::default();
+        let sequences = scanner.scan(b"\x1b[31mred\x1b[0m\x1bP1$r\x1b\\");
+        assert!(sequences.is_empty());
+    }
+
+    #[test]
+    fn test_aborted_sequence_is_dropped() {
+        let mut scanner = OscScanner::default();
+        let sequences = scanner.scan(b"\x1b]0;tit\x18le\x1b]2;ok\x07");
+        assert_eq!(payloads(&sequences), vec!["2;ok"]);
+    }
+
+    #[test]
+    fn test_working_directory() {
+        assert_eq!(
+            working_directory("7;file://host/Users/me/my%20project").as_deref(),
+            Some("/Users/me/my project")
+        );
+        assert_eq!(
+            working_directory("7;file:///tmp/%ED%95%9C").as_deref(),
+            Some("/tmp/한")
+        );
+        assert_eq!(
+            working_directory("7;file://pc/C:/Users/me").as_deref(),
+            Some("C:/Users/me")
+        );
+        assert!(working_directory("7;file://host").is_none());
+        assert!(working_directory("7;http://host/path").is_none());
+        assert!(working_directory("133;A").is_none());
+    }
+
+    #[test]
+    fn test_window_title() {
+        assert_eq!(
+            window_title("0;user@host: ~").as_deref(),
+            Some("user@host: ~")
+        );
+        assert_eq!(
+            window_title("2;vim 제목.txt").as_deref(),
+            Some("vim 제목.txt")
+        );
+        assert_eq!(window_title("2;a\tb\u{9b}c").as_deref(), Some("abc"));
+        assert_eq!(window_title("2;").as_deref(), Some(""));
+        assert!(window_title("1;icon").is_none());
+        assert!(window_title("7;file:///tmp").is_none());
+    }
+
+    #[test]
+    fn test_percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%41"), "%zzA");
+        assert_eq!(percent_decode("%+1"), "%+1");
+    }
+
+    #[test]
+    fn test_oversized_payload_is_skipped() {
+        let mut scanner = OscScanner::default();
+        let mut data = b"\x1b]52;c;".to_vec();
+        data.resize(MAX_PAYLOAD_BYTES + 64, b'A');
+        data.extend_from_slice(b"\x07\x1b]133;B\x07");
+
+        let sequences = scanner.scan(&data);
+        assert_eq!(payloads(&sequences), vec!["133;B"]);
+    }
+}
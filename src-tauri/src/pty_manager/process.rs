@@ -0,0 +1,296 @@
+//! Child process reaping and exit status reporting.
+//!
+//! Once a child is reaped its process ID can be reused, so it must never be
+//! signalled afterwards. The reaper reaps the child while holding the
+//! session's `reaped` lock and sets the flag before releasing it; the child
+//! is only signalled while holding the same lock with the flag unset.
+
+use std::sync::Mutex;
+
+use portable_pty::{Child, ChildKiller};
+
+use super::lock;
+
+#[cfg(unix)]
+use crate::types::Signal;
+
+/// Exit status of a reaped child process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessExit {
+    /// Exit code, if the process exited normally
+    pub code: Option<i32>,
+    /// Name of the signal that terminated the process (e.g. "SIGINT")
+    pub signal: Option<String>,
+}
+
+/// Blocks until the child process exits, reaps it and returns its exit
+/// status. `reaped` is set while the child is reaped.
+///
+/// On Unix the child is a `std::process::Child`, which exposes the raw
+/// signal number; portable-pty's own `ExitStatus` only carries a
+/// human-readable description of the signal.
+pub fn wait_for_exit(mut child: Box<dyn Child + Send + Sync>, reaped: &Mutex<bool>) -> ProcessExit {
+    #[cfg(unix)]
+    {
+        let child: &mut dyn Child = child.as_mut();
+        if let Some(child) = child.downcast_mut::<std::process::Child>() {
+            use std::os::unix::process::ExitStatusExt;
+
+            // Wait without reaping, so the child is not reaped while it may
+            // be signalled
+            if let Err(e) = wait_unreaped(child.id()) {
+                log::warn!("Failed to wait for PTY child process: {e}");
+            }
+            let mut reaped = lock(reaped);
+            let result = child.wait();
+            *reaped = true;
+            drop(reaped);
+
+            return match result {
+                Ok(status) => ProcessExit {
+                    code: status.code(),
+                    signal: status.signal().map(signal_name),
+                },
+                Err(e) => {
+                    log::warn!("Failed to wait for PTY child process: {e}");
+                    ProcessExit {
+                        code: None,
+                        signal: None,
+                    }
+                }
+            };
+        }
+    }
+
+    // Other children cannot be waited for without reaping them. On Unix the
+    // child is marked as reaped first, since it could be signalled by pid.
+    #[cfg(unix)]
+    {
+        *lock(reaped) = true;
+    }
+    let result = child.wait();
+    #[cfg(not(unix))]
+    {
+        *lock(reaped) = true;
+    }
+
+    match result {
+        Ok(status) => ProcessExit {
+            code: Some(status.exit_code() as i32),
+            signal: status.signal().map(str::to_string),
+        },
+        Err(e) => {
+            log::warn!("Failed to wait for PTY child process: {e}");
+            ProcessExit {
+                code: None,
+                signal: None,
+            }
+        }
+    }
+}
+
+/// Blocks until the process exits, leaving it to be reaped.
+#[cfg(unix)]
+fn wait_unreaped(pid: u32) -> std::io::Result<()> {
+    loop {
+        // SAFETY: siginfo_t is plain data, which waitid(2) fills in
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        // SAFETY: `info` is a valid siginfo_t for waitid(2) to write to
+        let result = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if result == 0 {
+            return Ok(());
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// Forcefully terminates the child process, unless it has been reaped.
+///
+/// On Unix this sends SIGKILL directly: portable-pty's killer only sends
+/// SIGHUP, which the process may ignore. Elsewhere the killer terminates
+/// the process outright.
+pub fn kill_process(
+    pid: Option<u32>,
+    killer: &mut (dyn ChildKiller + Send + Sync),
+    reaped: &Mutex<bool>,
+) -> std::io::Result<()> {
+    // Held while signalling, so the child cannot be reaped meanwhile
+    let reaped = lock(reaped);
+    if *reaped {
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        // SAFETY: kill(2) has no memory safety requirements
+        if unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        return Ok(());
+    }
+
+    #[cfg(not(unix))]
+    let _ = pid;
+
+    killer.kill()
+}
+
+/// Sends a signal to every process in a process group.
+#[cfg(unix)]
+pub fn signal_process_group(pgid: u32, signal: Signal) -> std::io::Result<()> {
+    // SAFETY: killpg(2) has no memory safety requirements
+    if unsafe { libc::killpg(pgid as libc::pid_t, signal_number(signal)) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Kills every process in a process group with SIGKILL.
+#[cfg(unix)]
+pub fn kill_process_group(pgid: u32) -> std::io::Result<()> {
+    // SAFETY: killpg(2) has no memory safety requirements
+    if unsafe { libc::killpg(pgid as libc::pid_t, libc::SIGKILL) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether a process group has any members left.
+#[cfg(unix)]
+pub fn process_group_exists(pgid: u32) -> bool {
+    // SAFETY: killpg(2) has no memory safety requirements. Signal 0 only
+    // checks whether the group exists.
+    if unsafe { libc::killpg(pgid as libc::pid_t, 0) } == 0 {
+        return true;
+    }
+    // EPERM: the group exists but belongs to another user
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Returns the Unix signal number of a signal.
+#[cfg(unix)]
+pub fn signal_number(signal: Signal) -> i32 {
+    match signal {
+        Signal::Interrupt => libc::SIGINT,
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Hangup => libc::SIGHUP,
+        Signal::Stop => libc::SIGTSTP,
+        Signal::Continue => libc::SIGCONT,
+        Signal::Quit => libc::SIGQUIT,
+        Signal::WindowChange => libc::SIGWINCH,
+    }
+}
+
+/// Returns the conventional name of a Unix signal number (e.g. 2 -> "SIGINT").
+#[cfg(unix)]
+pub fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGCONT => "SIGCONT",
+        libc::SIGSTOP => "SIGSTOP",
+        libc::SIGTSTP => "SIGTSTP",
+        libc::SIGTTIN => "SIGTTIN",
+        libc::SIGTTOU => "SIGTTOU",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGSYS => "SIGSYS",
+        libc::SIGWINCH => "SIGWINCH",
+        _ => return format!("SIG{signal}"),
+    };
+    name.to_string()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_name_known_signals() {
+        assert_eq!(signal_name(libc::SIGINT), "SIGINT");
+        assert_eq!(signal_name(libc::SIGKILL), "SIGKILL");
+        assert_eq!(signal_name(libc::SIGTERM), "SIGTERM");
+    }
+
+    #[test]
+    fn test_signal_number_matches_serialized_name() {
+        for signal in [
+            Signal::Interrupt,
+            Signal::Terminate,
+            Signal::Hangup,
+            Signal::Stop,
+            Signal::Continue,
+            Signal::Quit,
+            Signal::WindowChange,
+        ] {
+            let serialized = serde_json::to_value(signal).unwrap();
+            assert_eq!(serialized, signal_name(signal_number(signal)));
+        }
+    }
+
+    #[test]
+    fn test_signal_name_unknown_signal() {
+        assert_eq!(signal_name(99), "SIG99");
+    }
+
+    fn spawn(script: &str) -> Box<dyn Child + Send + Sync> {
+        let child = std::process::Command::new("/bin/sh")
+            .args(["-c", script])
+            .spawn()
+            .unwrap();
+        Box::new(child)
+    }
+
+    #[test]
+    fn test_reaped_child_is_not_signalled() {
+        let child = spawn("exit 3");
+        let pid = child.process_id();
+        let mut killer = child.clone_killer();
+        let reaped = Mutex::new(false);
+
+        let exit = wait_for_exit(child, &reaped);
+        assert_eq!(exit.code, Some(3));
+        assert!(*lock(&reaped));
+
+        // Signalling the reused pid would fail with ESRCH (or worse)
+        kill_process(pid, killer.as_mut(), &reaped).unwrap();
+    }
+
+    #[test]
+    fn test_running_child_is_killed() {
+        let child = spawn("sleep 10");
+        let pid = child.process_id();
+        let mut killer = child.clone_killer();
+        let reaped = std::sync::Arc::new(Mutex::new(false));
+
+        let reaper_reaped = std::sync::Arc::clone(&reaped);
+        let reaper = std::thread::spawn(move || wait_for_exit(child, &reaper_reaped));
+        kill_process(pid, killer.as_mut(), &reaped).unwrap();
+
+        let exit = reaper.join().unwrap();
+        assert_eq!(exit.signal.as_deref(), Some("SIGKILL"));
+    }
+}
@@ -0,0 +1,326 @@
+//! Programs that can be run directly in a PTY, without a shell.
+//!
+//! Program sessions have their own policy, separate from the shell
+//! allowlist: only programs listed here can be run, they are resolved by
+//! name on the search path (never from a path given by the frontend), and
+//! their arguments are checked against the options each program accepts.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// An option a program accepts.
+struct ProgramOption {
+    /// Long or short name, e.g. `--model`
+    name: &'static str,
+    /// Whether the option is followed by a value
+    value: OptionValue,
+    /// Accepted values, if restricted
+    values: Option<&'static [&'static str]>,
+}
+
+/// Whether an option is followed by a value.
+#[derive(Clone, Copy, PartialEq)]
+enum OptionValue {
+    None,
+    Required,
+    /// Taken from the next argument unless that is another option
+    Optional,
+}
+
+/// What a program may be run with.
+struct ProgramPolicy {
+    /// Name the program is requested and resolved by
+    name: &'static str,
+    /// Options that may be passed
+    options: &'static [ProgramOption],
+    /// Whether positional arguments (e.g. an initial prompt) are allowed
+    positional: bool,
+}
+
+const fn flag(name: &'static str) -> ProgramOption {
+    ProgramOption {
+        name,
+        value: OptionValue::None,
+        values: None,
+    }
+}
+
+const fn option(name: &'static str) -> ProgramOption {
+    ProgramOption {
+        name,
+        value: OptionValue::Required,
+        values: None,
+    }
+}
+
+const fn optional(name: &'static str) -> ProgramOption {
+    ProgramOption {
+        name,
+        value: OptionValue::Optional,
+        values: None,
+    }
+}
+
+/// Permission modes Claude Code may be started in. Modes that skip
+/// permission prompts are not allowed. Options that load settings or MCP
+/// servers from elsewhere (`--settings`, `--mcp-config`) are not allowed
+/// either, since they could set any permission mode, nor are options that
+/// pre-approve tools or directories or add instructions
+/// (`--allowed-tools`, `--add-dir`, `--append-system-prompt`).
+const CLAUDE_PERMISSION_MODES: &[&str] = &["default", "acceptEdits", "plan"];
+
+const PROGRAMS: &[ProgramPolicy] = &[ProgramPolicy {
+    name: "claude",
+    options: &[
+        flag("--continue"),
+        flag("-c"),
+        flag("--verbose"),
+        // Optionally followed by a category filter
+        optional("--debug"),
+        flag("--ide"),
+        // Without a session ID, the session picker is shown
+        optional("--resume"),
+        optional("-r"),
+        option("--session-id"),
+        option("--model"),
+        option("--fallback-model"),
+        option("--disallowed-tools"),
+        ProgramOption {
+            name: "--permission-mode",
+            value: OptionValue::Required,
+            values: Some(CLAUDE_PERMISSION_MODES),
+        },
+    ],
+    positional: true,
+}];
+
+/// Directories searched after PATH. Apps started from the desktop often get
+/// a minimal PATH that lacks the user's install locations.
+const EXTRA_SEARCH_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"];
+
+/// Directories under the home directory searched after PATH
+const EXTRA_HOME_SEARCH_DIRS: &[&str] = &[".claude/local", ".local/bin", ".npm-global/bin"];
+
+/// Checks that `name` is an allowed program and `args` are valid for it.
+pub fn validate(name: &str, args: &[String]) -> Result<(), String> {
+    let policy = PROGRAMS
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Program not allowed: {name}"))?;
+
+    validate_args(policy, args)
+}
+
+/// Returns the canonical path of the program's executable, looked up on
+/// `path` (a PATH value) and then in the usual install locations.
+pub fn find_program(name: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    find_executable(name, &search_dirs(path))
+}
+
+/// Checks arguments against the options a program accepts.
+fn validate_args(policy: &ProgramPolicy, args: &[String]) -> Result<(), String> {
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            // Everything after `--` is positional
+            if !policy.positional && args.len() > 0 {
+                return Err(format!("{} takes no positional arguments", policy.name));
+            }
+            break;
+        }
+
+        if !arg.starts_with('-') || arg == "-" {
+            if !policy.positional {
+                return Err(format!("{} takes no positional arguments", policy.name));
+            }
+            continue;
+        }
+
+        // `--name=value` or `--name value`
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        let option = policy
+            .options
+            .iter()
+            .find(|o| o.name == name)
+            .ok_or_else(|| format!("Option not allowed for {}: {name}", policy.name))?;
+
+        let value = match option.value {
+            OptionValue::None => {
+                if inline_value.is_some() {
+                    return Err(format!("Option {name} takes no value"));
+                }
+                continue;
+            }
+            OptionValue::Required => inline_value
+                .or_else(|| args.next().map(String::as_str))
+                .ok_or_else(|| format!("Option {name} requires a value"))?,
+            OptionValue::Optional => {
+                let value = match inline_value {
+                    Some(value) => Some(value),
+                    None => args
+                        .next_if(|next| !next.starts_with('-'))
+                        .map(String::as_str),
+                };
+                match value {
+                    Some(value) => value,
+                    None => continue,
+                }
+            }
+        };
+        if let Some(values) = option.values {
+            if !values.contains(&value) {
+                return Err(format!("Invalid value for {name}: {value}"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the directories a program is looked up in, in order.
+fn search_dirs(path: Option<&OsStr>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = path
+        .map(|path| std::env::split_paths(path).collect())
+        .unwrap_or_default();
+
+    if let Some(home) = std::env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.extend(EXTRA_HOME_SEARCH_DIRS.iter().map(|dir| home.join(dir)));
+    }
+    dirs.extend(EXTRA_SEARCH_DIRS.iter().map(PathBuf::from));
+    dirs
+}
+
+/// Returns the canonical path of the first executable called `name` in `dirs`.
+/// Relative directories (e.g. `.` in PATH) are skipped.
+fn find_executable(name: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    dirs.iter()
+        .filter(|dir| dir.is_absolute())
+        .filter_map(|dir| dir.join(name).canonicalize().ok())
+        .find(|path| is_executable(path))
+}
+
+/// Whether `path` is an executable file.
+fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(not(unix))]
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claude() -> &'static ProgramPolicy {
+        &PROGRAMS[0]
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_valid_claude_args() {
+        for valid in [
+            args(&[]),
+            args(&["--continue"]),
+            args(&["--resume", "abc-123", "--model", "opus"]),
+            args(&["--resume"]),
+            args(&["-r", "--model", "opus"]),
+            args(&["--resume=abc-123"]),
+            args(&["--permission-mode=plan", "--disallowed-tools", "Bash"]),
+            args(&["--debug", "api,hooks"]),
+            args(&["--debug", "--verbose"]),
+            args(&["fix the failing test"]),
+            args(&["--", "--not-an-option"]),
+        ] {
+            assert!(
+                validate_args(claude(), &valid).is_ok(),
+                "Expected {valid:?} to be valid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_claude_args() {
+        for (invalid, error) in [
+            (args(&["--dangerously-skip-permissions"]), "not allowed"),
+            (
+                args(&["--permission-mode", "bypassPermissions"]),
+                "Invalid value",
+            ),
+            (args(&["--model"]), "requires a value"),
+            (args(&["--continue=yes"]), "takes no value"),
+            (args(&["-p", "prompt"]), "not allowed"),
+            (
+                args(&[
+                    "--settings",
+                    r#"{"permissions":{"defaultMode":"bypassPermissions"}}"#,
+                ]),
+                "not allowed",
+            ),
+            (args(&["--settings=/tmp/settings.json"]), "not allowed"),
+            (args(&["--mcp-config", "/tmp/mcp.json"]), "not allowed"),
+            (args(&["--allowed-tools", "Bash"]), "not allowed"),
+            (args(&["--allowed-tools=Bash(*)"]), "not allowed"),
+            (args(&["--add-dir", "/"]), "not allowed"),
+            (
+                args(&["--append-system-prompt", "Never ask for permission"]),
+                "not allowed",
+            ),
+        ] {
+            let result = validate_args(claude(), &invalid);
+            assert!(
+                result.as_ref().is_err_and(|e| e.contains(error)),
+                "Expected {invalid:?} to fail with {error:?}, got {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_value_is_optional() {
+        // The next option is not taken as the session ID
+        let result = validate_args(claude(), &args(&["--resume", "--model", "x"]));
+        assert!(result.is_ok());
+        let result = validate_args(claude(), &args(&["--resume", "--model"]));
+        assert!(result.is_err_and(|e| e.contains("requires a value")));
+    }
+
+    #[test]
+    fn test_unknown_program_is_rejected() {
+        let result = validate("bash", &[]);
+        assert_eq!(result.unwrap_err(), "Program not allowed: bash");
+        assert!(validate("claude", &args(&["--continue"])).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_executable_in_search_dirs() {
+        let dirs = vec![
+            PathBuf::from("relative/dir"),
+            PathBuf::from("/nonexistent"),
+            PathBuf::from("/bin"),
+        ];
+        let found = find_executable("sh", &dirs).unwrap();
+        assert_eq!(found, Path::new("/bin/sh").canonicalize().unwrap());
+
+        assert!(find_executable("no-such-program", &dirs).is_none());
+        // Directories are not executables
+        assert!(find_executable("", &dirs).is_none());
+    }
+}
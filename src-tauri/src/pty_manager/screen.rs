@@ -0,0 +1,169 @@
+//! Headless terminal state for PTY sessions.
+//!
+//! Feeds session output through a VT parser so the backend can tell what is
+//! on screen (grid, cursor, attributes, alternate screen) without asking the
+//! frontend.
+
+use crate::types::{CellColor, ScreenCell, ScreenSnapshot, SnapshotFormat};
+
+/// Virtual terminal screen kept in sync with a session's output.
+pub struct Screen {
+    parser: vt100::Parser,
+}
+
+impl Screen {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            // Scrollback is kept separately as raw bytes, so the parser only
+            // needs the visible screen
+            parser: vt100::Parser::new(rows, cols, 0),
+        }
+    }
+
+    /// Applies output from the PTY.
+    pub fn process(&mut self, data: &[u8]) {
+        self.parser.process(data);
+    }
+
+    /// Resizes the screen to match the PTY.
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        self.parser.set_size(rows, cols);
+    }
+
+    /// Renders the current screen.
+    pub fn snapshot(&self, format: SnapshotFormat) -> ScreenSnapshot {
+        let screen = self.parser.screen();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+
+        let cells = match format {
+            SnapshotFormat::Text => None,
+            SnapshotFormat::Cells => Some(
+                (0..rows)
+                    .map(|row| {
+                        (0..cols)
+                            .map(|col| {
+                                screen
+                                    .cell(row, col)
+                                    .map(to_screen_cell)
+                                    .unwrap_or_default()
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        };
+
+        ScreenSnapshot {
+            rows,
+            cols,
+            cursor_row,
+            cursor_col,
+            cursor_visible: !screen.hide_cursor(),
+            alternate_screen: screen.alternate_screen(),
+            lines: screen.rows(0, cols).collect(),
+            cells,
+        }
+    }
+}
+
+fn to_screen_cell(cell: &vt100::Cell) -> ScreenCell {
+    ScreenCell {
+        text: cell.contents(),
+        fg: to_cell_color(cell.fgcolor()),
+        bg: to_cell_color(cell.bgcolor()),
+        bold: cell.bold(),
+        italic: cell.italic(),
+        underline: cell.underline(),
+        inverse: cell.inverse(),
+        wide: cell.is_wide(),
+    }
+}
+
+fn to_cell_color(color: vt100::Color) -> CellColor {
+    match color {
+        vt100::Color::Default => CellColor::Default,
+        vt100::Color::Idx(index) => CellColor::Indexed { index },
+        vt100::Color::Rgb(r, g, b) => CellColor::Rgb { r, g, b },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_snapshot() {
+        let mut screen = Screen::new(4, 20);
+        screen.process(b"hello\r\nworld");
+
+        let snapshot = screen.snapshot(SnapshotFormat::Text);
+        assert_eq!(snapshot.rows, 4);
+        assert_eq!(snapshot.cols, 20);
+        assert_eq!(snapshot.lines, vec!["hello", "world", "", ""]);
+        assert_eq!((snapshot.cursor_row, snapshot.cursor_col), (1, 5));
+        assert!(snapshot.cursor_visible);
+        assert!(snapshot.cells.is_none());
+    }
+
+    #[test]
+    fn test_cursor_movement_overwrites_cells() {
+        let mut screen = Screen::new(2, 10);
+        screen.process(b"abcdef\x1b[1;3HXY\x1b[K");
+
+        let snapshot = screen.snapshot(SnapshotFormat::Text);
+        assert_eq!(snapshot.lines[0], "abXY");
+    }
+
+    #[test]
+    fn test_cell_attributes() {
+        let mut screen = Screen::new(2, 10);
+        screen.process("\x1b[1;31mR\x1b[0m\x1b[38;2;1;2;3;4mG\x1b[0m한".as_bytes());
+
+        let snapshot = screen.snapshot(SnapshotFormat::Cells);
+        let row = &snapshot.cells.as_ref().unwrap()[0];
+        assert_eq!(row.len(), 10);
+
+        assert_eq!(row[0].text, "R");
+        assert!(row[0].bold);
+        assert_eq!(row[0].fg, CellColor::Indexed { index: 1 });
+
+        assert_eq!(row[1].text, "G");
+        assert!(!row[1].bold);
+        assert!(row[1].underline);
+        assert_eq!(row[1].fg, CellColor::Rgb { r: 1, g: 2, b: 3 });
+
+        assert_eq!(row[2].text, "한");
+        assert!(row[2].wide);
+        assert_eq!(row[3].text, "");
+        assert_eq!(row[4], ScreenCell::default());
+    }
+
+    #[test]
+    fn test_alternate_screen() {
+        let mut screen = Screen::new(2, 10);
+        screen.process(b"shell$ ");
+        screen.process(b"\x1b[?1049h\x1b[?25lvim");
+
+        let snapshot = screen.snapshot(SnapshotFormat::Text);
+        assert!(snapshot.alternate_screen);
+        assert!(!snapshot.cursor_visible);
+        assert_eq!(snapshot.lines[0], "vim");
+
+        screen.process(b"\x1b[?1049l\x1b[?25h");
+        let snapshot = screen.snapshot(SnapshotFormat::Text);
+        assert!(!snapshot.alternate_screen);
+        assert_eq!(snapshot.lines[0], "shell$ ");
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut screen = Screen::new(2, 10);
+        screen.resize(5, 40);
+
+        let snapshot = screen.snapshot(SnapshotFormat::Cells);
+        assert_eq!((snapshot.rows, snapshot.cols), (5, 40));
+        assert_eq!(snapshot.lines.len(), 5);
+        assert_eq!(snapshot.cells.unwrap()[4].len(), 40);
+    }
+}
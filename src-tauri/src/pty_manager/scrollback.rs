@@ -0,0 +1,226 @@
+//! Bounded scrollback ring buffer for PTY output.
+//!
+//! Output is stored as the same chunks that were streamed to the frontend,
+//! each tagged with a sequence number. When the buffered size exceeds the
+//! limit, the oldest chunks are evicted.
+
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::types::ScrollbackSnapshot;
+
+/// Ring buffer holding the most recent output of a PTY session.
+pub struct Scrollback {
+    /// Buffered output chunks, oldest first
+    chunks: VecDeque<Vec<u8>>,
+    /// When each buffered chunk was pushed (milliseconds since the Unix epoch)
+    times: VecDeque<f64>,
+    /// Total size of all buffered chunks in bytes
+    len: usize,
+    /// Maximum number of bytes to retain
+    limit: usize,
+    /// Sequence number of the most recently pushed chunk (0 = nothing pushed yet)
+    last_seq: u32,
+    /// Whether any chunk has been evicted
+    truncated: bool,
+    /// Total size of the evicted chunks in bytes
+    evicted: u64,
+}
+
+impl Scrollback {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            times: VecDeque::new(),
+            len: 0,
+            limit,
+            last_seq: 0,
+            truncated: false,
+            evicted: 0,
+        }
+    }
+
+    /// Appends a chunk and returns its sequence number.
+    /// The newest chunk is always retained, even if it alone exceeds the limit.
+    pub fn push(&mut self, data: &[u8]) -> u32 {
+        self.last_seq = self.last_seq.wrapping_add(1);
+        self.len += data.len();
+        self.chunks.push_back(data.to_vec());
+        self.times.push_back(now_ms());
+
+        while self.len > self.limit && self.chunks.len() > 1 {
+            self.times.pop_front();
+            if let Some(evicted) = self.chunks.pop_front() {
+                self.len -= evicted.len();
+                self.evicted += evicted.len() as u64;
+                self.truncated = true;
+            }
+        }
+
+        self.last_seq
+    }
+
+    /// Returns all buffered output as a single contiguous snapshot.
+    pub fn snapshot(&self) -> ScrollbackSnapshot {
+        let mut data = Vec::with_capacity(self.len);
+        for chunk in &self.chunks {
+            data.extend_from_slice(chunk);
+        }
+
+        ScrollbackSnapshot {
+            data,
+            seq: self.last_seq,
+            truncated: self.truncated,
+        }
+    }
+
+    /// Offset of the oldest buffered byte in the session's output.
+    pub fn start_offset(&self) -> u64 {
+        self.evicted
+    }
+
+    /// When the buffered output was written: for each chunk, the offset it
+    /// ends at and when it was pushed.
+    pub fn times(&self) -> Vec<(u64, f64)> {
+        let mut offset = self.evicted;
+        self.chunks
+            .iter()
+            .zip(&self.times)
+            .map(|(chunk, &time)| {
+                offset += chunk.len() as u64;
+                (offset, time)
+            })
+            .collect()
+    }
+
+    /// Returns the buffered chunks with a sequence number greater than `seq`,
+    /// oldest first, together with their sequence numbers. Sequence numbers
+    /// wrap around, so they are compared with wrapping arithmetic: a chunk is
+    /// newer if it is less than 2^31 ahead of `seq`.
+    pub fn chunks_since(&self, seq: u32) -> impl Iterator<Item = (u32, &[u8])> {
+        let first_seq = self.first_seq();
+        self.chunks
+            .iter()
+            .enumerate()
+            .map(move |(i, chunk)| (first_seq.wrapping_add(i as u32), chunk.as_slice()))
+            .filter(move |(chunk_seq, _)| chunk_seq.wrapping_sub(seq) as i32 > 0)
+    }
+
+    /// Whether chunks with a sequence number greater than `seq` were
+    /// evicted, so `chunks_since(seq)` does not return all of them.
+    pub fn evicted_since(&self, seq: u32) -> bool {
+        self.first_seq().wrapping_sub(seq) as i32 > 1
+    }
+
+    /// Sequence number of the oldest buffered chunk, or of the next chunk if
+    /// none are buffered.
+    fn first_seq(&self) -> u32 {
+        self.last_seq
+            .wrapping_sub(self.chunks.len() as u32)
+            .wrapping_add(1)
+    }
+}
+
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_snapshot() {
+        let scrollback = Scrollback::new(1024);
+        let snapshot = scrollback.snapshot();
+        assert!(snapshot.data.is_empty());
+        assert_eq!(snapshot.seq, 0);
+        assert!(!snapshot.truncated);
+    }
+
+    #[test]
+    fn test_push_assigns_increasing_sequence_numbers() {
+        let mut scrollback = Scrollback::new(1024);
+        assert_eq!(scrollback.push(b"one"), 1);
+        assert_eq!(scrollback.push(b"two"), 2);
+
+        let snapshot = scrollback.snapshot();
+        assert_eq!(snapshot.data, b"onetwo");
+        assert_eq!(snapshot.seq, 2);
+    }
+
+    #[test]
+    fn test_oldest_chunks_are_evicted_over_limit() {
+        let mut scrollback = Scrollback::new(8);
+        scrollback.push(b"aaaa");
+        scrollback.push(b"bbbb");
+        scrollback.push(b"cccc");
+
+        let snapshot = scrollback.snapshot();
+        assert_eq!(snapshot.data, b"bbbbcccc");
+        assert_eq!(snapshot.seq, 3);
+        assert!(snapshot.truncated);
+        assert_eq!(scrollback.start_offset(), 4);
+
+        let times = scrollback.times();
+        let offsets: Vec<u64> = times.iter().map(|(offset, _)| *offset).collect();
+        assert_eq!(offsets, vec![8, 12]);
+        assert!(times[0].1 <= times[1].1);
+    }
+
+    #[test]
+    fn test_oversized_chunk_is_kept() {
+        let mut scrollback = Scrollback::new(4);
+        scrollback.push(b"ab");
+        scrollback.push(b"0123456789");
+
+        let snapshot = scrollback.snapshot();
+        assert_eq!(snapshot.data, b"0123456789");
+        assert!(snapshot.truncated);
+    }
+
+    #[test]
+    fn test_chunks_since_skips_already_seen_chunks() {
+        let mut scrollback = Scrollback::new(8);
+        scrollback.push(b"aaaa");
+        scrollback.push(b"bbbb");
+        scrollback.push(b"cccc");
+
+        let chunks: Vec<_> = scrollback.chunks_since(2).collect();
+        assert_eq!(chunks, vec![(3, &b"cccc"[..])]);
+
+        // Evicted chunks are not replayed
+        let chunks: Vec<_> = scrollback.chunks_since(0).collect();
+        assert_eq!(chunks, vec![(2, &b"bbbb"[..]), (3, &b"cccc"[..])]);
+        assert!(scrollback.evicted_since(0));
+        assert!(!scrollback.evicted_since(1));
+        assert!(!scrollback.evicted_since(3));
+    }
+
+    #[test]
+    fn test_chunks_since_across_sequence_wraparound() {
+        let mut scrollback = Scrollback::new(1024);
+        scrollback.last_seq = u32::MAX - 1;
+        assert_eq!(scrollback.push(b"a"), u32::MAX);
+        assert_eq!(scrollback.push(b"b"), 0);
+        assert_eq!(scrollback.push(b"c"), 1);
+
+        let chunks: Vec<_> = scrollback.chunks_since(u32::MAX - 1).collect();
+        assert_eq!(
+            chunks,
+            vec![(u32::MAX, &b"a"[..]), (0, &b"b"[..]), (1, &b"c"[..])]
+        );
+
+        let chunks: Vec<_> = scrollback.chunks_since(u32::MAX).collect();
+        assert_eq!(chunks, vec![(0, &b"b"[..]), (1, &b"c"[..])]);
+
+        let chunks: Vec<_> = scrollback.chunks_since(0).collect();
+        assert_eq!(chunks, vec![(1, &b"c"[..])]);
+
+        assert_eq!(scrollback.chunks_since(1).count(), 0);
+        assert!(!scrollback.evicted_since(u32::MAX - 1));
+        assert!(scrollback.evicted_since(u32::MAX - 2));
+    }
+}
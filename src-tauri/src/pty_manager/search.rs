@@ -0,0 +1,422 @@
+//! Search of session output.
+//!
+//! Output is split into lines with escape sequences and control characters
+//! removed, and each line is matched on its own. Lines are located by their
+//! offset in the session's output, so matches in scrollback and in
+//! transcripts can be compared.
+
+use std::collections::VecDeque;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::types::{SearchMatch, SearchOptions, SearchSource};
+
+/// Default number of context lines before and after a match
+pub const DEFAULT_CONTEXT_LINES: u32 = 2;
+
+/// Maximum number of context lines before and after a match
+pub const MAX_CONTEXT_LINES: u32 = 20;
+
+/// Default maximum number of matches returned by a search
+pub const DEFAULT_MAX_RESULTS: u32 = 200;
+
+/// Largest maximum number of matches a search can be given
+pub const MAX_RESULTS: u32 = 5000;
+
+/// Maximum length of a search query (in characters)
+const MAX_QUERY_LENGTH: usize = 1000;
+
+/// Lines longer than this are split, so output without newlines (progress
+/// bars, binary data) cannot build up arbitrarily long lines
+const MAX_LINE_BYTES: usize = 4096;
+
+/// Compiles the query, escaping it unless it is a regular expression.
+pub fn build_regex(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    if query.is_empty() {
+        return Err("Search query cannot be empty".to_string());
+    }
+    if query.chars().count() > MAX_QUERY_LENGTH {
+        return Err(format!(
+            "Search query too long (max {MAX_QUERY_LENGTH} characters)"
+        ));
+    }
+
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid regular expression: {e}"))
+}
+
+/// Parser state of the line splitter.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Ground,
+    /// After ESC
+    Escape,
+    /// In an escape sequence with intermediate bytes (e.g. `ESC ( B`)
+    Intermediate,
+    /// In a control sequence (`ESC [`)
+    Csi,
+    /// In a control string (OSC, DCS, SOS, PM or APC), ended by BEL or ST
+    String,
+    /// After ESC in a control string, which is usually the start of ST
+    StringEscape,
+}
+
+/// Splits output into lines of text, removing escape sequences and control
+/// characters. Sequences and lines may be split across chunks.
+struct LineSplitter {
+    state: State,
+    /// Text of the current line
+    line: Vec<u8>,
+    /// Offset of the start of the current line in the output
+    line_start: u64,
+    /// Offset of the next byte
+    offset: u64,
+}
+
+impl LineSplitter {
+    fn new() -> Self {
+        Self {
+            state: State::Ground,
+            line: Vec::new(),
+            line_start: 0,
+            offset: 0,
+        }
+    }
+
+    /// Splits a chunk of output starting at `offset`, returning the lines it
+    /// completes with their offsets. A chunk that does not follow on from
+    /// the previous one ends the current line.
+    fn push(&mut self, offset: u64, data: &[u8]) -> Vec<(u64, String)> {
+        let mut lines = Vec::new();
+        if offset != self.offset {
+            lines.extend(self.take_line());
+            self.state = State::Ground;
+            self.offset = offset;
+            self.line_start = offset;
+        }
+
+        for &byte in data {
+            self.offset += 1;
+            self.state = match (self.state, byte) {
+                (State::Ground, 0x1b) => State::Escape,
+                (State::Ground, b'\n') => {
+                    lines.extend(self.take_line());
+                    self.line_start = self.offset;
+                    State::Ground
+                }
+                (State::Ground, b'\t') => {
+                    self.push_byte(byte, &mut lines);
+                    State::Ground
+                }
+                // Other control characters, including CR and BS, are dropped
+                (State::Ground, 0x00..=0x1f | 0x7f) => State::Ground,
+                (State::Ground, _) => {
+                    self.push_byte(byte, &mut lines);
+                    State::Ground
+                }
+
+                (State::Escape, b'[') => State::Csi,
+                (State::Escape, b']' | b'P' | b'X' | b'^' | b'_') => State::String,
+                (State::Escape, 0x20..=0x2f) => State::Intermediate,
+                (State::Escape, _) => State::Ground,
+
+                (State::Intermediate, 0x20..=0x2f) => State::Intermediate,
+                (State::Intermediate, _) => State::Ground,
+
+                (State::Csi, 0x1b) => State::Escape,
+                (State::Csi, 0x40..=0x7e) => State::Ground,
+                (State::Csi, _) => State::Csi,
+
+                (State::String, 0x07) => State::Ground,
+                (State::String, 0x1b) => State::StringEscape,
+                (State::String, _) => State::String,
+
+                (State::StringEscape, _) => State::Ground,
+            };
+        }
+        lines
+    }
+
+    /// Returns the line in progress, if any.
+    fn finish(&mut self) -> Option<(u64, String)> {
+        self.take_line()
+    }
+
+    fn push_byte(&mut self, byte: u8, lines: &mut Vec<(u64, String)>) {
+        self.line.push(byte);
+        if self.line.len() >= MAX_LINE_BYTES {
+            lines.extend(self.take_line());
+            self.line_start = self.offset;
+        }
+    }
+
+    /// Takes the text of the current line. Lines without any text are
+    /// skipped.
+    fn take_line(&mut self) -> Option<(u64, String)> {
+        if self.line.is_empty() {
+            return None;
+        }
+        let line = std::mem::take(&mut self.line);
+        Some((self.line_start, String::from_utf8_lossy(&line).into_owned()))
+    }
+}
+
+/// Searches the output of one session.
+pub struct SessionSearch<'a> {
+    regex: &'a Regex,
+    session_id: String,
+    source: SearchSource,
+    context: usize,
+    limit: usize,
+    /// Times of offsets in the output, from a transcript index
+    times: Vec<(u64, f64)>,
+    since: Option<f64>,
+    until: Option<f64>,
+    lines: LineSplitter,
+    /// The last lines, for the context before a match
+    before: VecDeque<String>,
+    /// Matches still collecting their context after
+    pending: Vec<SearchMatch>,
+    matches: Vec<SearchMatch>,
+}
+
+impl<'a> SessionSearch<'a> {
+    /// Starts a search for at most `limit` matches.
+    pub fn new(
+        regex: &'a Regex,
+        session_id: &str,
+        source: SearchSource,
+        options: &SearchOptions,
+        limit: usize,
+    ) -> Self {
+        let context = options
+            .context_lines
+            .unwrap_or(DEFAULT_CONTEXT_LINES)
+            .min(MAX_CONTEXT_LINES);
+        Self {
+            regex,
+            session_id: session_id.to_string(),
+            source,
+            context: context as usize,
+            limit,
+            times: Vec::new(),
+            since: options.since,
+            until: options.until,
+            lines: LineSplitter::new(),
+            before: VecDeque::new(),
+            pending: Vec::new(),
+            matches: Vec::new(),
+        }
+    }
+
+    /// Sets the times output was written at, as `(offset, time)` pairs in
+    /// order. Lines are then only matched if they were output in the time
+    /// range of the search.
+    pub fn with_times(mut self, times: Vec<(u64, f64)>) -> Self {
+        self.times = times;
+        self
+    }
+
+    /// Searches the next chunk of output, starting at `offset`. Returns
+    /// false once the search is complete.
+    pub fn push(&mut self, offset: u64, data: &[u8]) -> bool {
+        for (offset, line) in self.lines.push(offset, data) {
+            self.line(offset, line);
+        }
+        !self.is_complete()
+    }
+
+    /// Ends the search, returning the matches in output order.
+    pub fn finish(mut self) -> Vec<SearchMatch> {
+        if let Some((offset, line)) = self.lines.finish() {
+            self.line(offset, line);
+        }
+        self.matches.append(&mut self.pending);
+        self.matches
+    }
+
+    fn is_complete(&self) -> bool {
+        self.pending.is_empty() && self.matches.len() >= self.limit
+    }
+
+    fn line(&mut self, offset: u64, line: String) {
+        for pending in &mut self.pending {
+            pending.context_after.push(line.clone());
+        }
+        let context = self.context;
+        let (complete, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|m| m.context_after.len() >= context);
+        self.matches.extend(complete);
+        self.pending = pending;
+
+        if self.matches.len() + self.pending.len() < self.limit && self.regex.is_match(&line) {
+            let time = self.time_at(offset);
+            let in_range = time.is_none_or(|time| {
+                self.since.is_none_or(|since| time >= since)
+                    && self.until.is_none_or(|until| time < until)
+            });
+            if in_range {
+                let found = SearchMatch {
+                    session_id: self.session_id.clone(),
+                    source: self.source,
+                    offset: offset as f64,
+                    time,
+                    line: line.clone(),
+                    context_before: self.before.iter().cloned().collect(),
+                    context_after: Vec::new(),
+                };
+                if context == 0 {
+                    self.matches.push(found);
+                } else {
+                    self.pending.push(found);
+                }
+            }
+        }
+
+        self.before.push_back(line);
+        if self.before.len() > context {
+            self.before.pop_front();
+        }
+    }
+
+    /// Time a line at `offset` was output: the time of the first index
+    /// entry after it, which is written at most a sync interval later.
+    fn time_at(&self, offset: u64) -> Option<f64> {
+        let after = self.times.partition_point(|(o, _)| *o <= offset);
+        self.times
+            .get(after)
+            .or_else(|| self.times.last())
+            .map(|(_, time)| *time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(chunks: &[&[u8]]) -> Vec<(u64, String)> {
+        let mut splitter = LineSplitter::new();
+        let mut offset = 0;
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            lines.extend(splitter.push(offset, chunk));
+            offset += chunk.len() as u64;
+        }
+        lines.extend(splitter.finish());
+        lines
+    }
+
+    fn search(query: &str, options: &SearchOptions, output: &[u8]) -> Vec<SearchMatch> {
+        let regex = build_regex(query, options).unwrap();
+        let mut search = SessionSearch::new(&regex, "id", SearchSource::Scrollback, options, 10);
+        search.push(0, output);
+        search.finish()
+    }
+
+    #[test]
+    fn test_escape_sequences_are_removed() {
+        let output = b"\x1b[1;32mgreen\x1b[0m text\r\n\x1b]0;title\x07\x1b(Bnext\x1b]8;;http://x\x1b\\link\r\n";
+        assert_eq!(
+            split(&[output]),
+            vec![(0, "green text".to_string()), (23, "nextlink".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_sequences_split_across_chunks() {
+        let lines = split(&[b"one\x1b[3", b"1mred\x1b", b"[0m\r", b"\ntwo"]);
+        assert_eq!(
+            lines,
+            vec![(0, "onered".to_string()), (17, "two".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_long_lines_are_split() {
+        let output = vec![b'x'; MAX_LINE_BYTES + 10];
+        let lines = split(&[&output]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].0, MAX_LINE_BYTES as u64);
+        assert_eq!(lines[1].1.len(), 10);
+    }
+
+    #[test]
+    fn test_build_regex() {
+        let literal = SearchOptions::default();
+        assert!(build_regex("a.b", &literal).unwrap().is_match("A.B"));
+        assert!(!build_regex("a.b", &literal).unwrap().is_match("axb"));
+
+        let regex = SearchOptions {
+            regex: true,
+            case_sensitive: true,
+            ..SearchOptions::default()
+        };
+        assert!(build_regex("a.b", &regex).unwrap().is_match("axb"));
+        assert!(!build_regex("a.b", &regex).unwrap().is_match("AXB"));
+        assert!(build_regex("(", &regex).is_err());
+        assert!(build_regex("", &literal).is_err());
+    }
+
+    #[test]
+    fn test_matches_have_context() {
+        let options = SearchOptions {
+            context_lines: Some(1),
+            ..SearchOptions::default()
+        };
+        let matches = search(
+            "ERROR",
+            &options,
+            b"one\r\nerror: two\r\nthree\r\nfour\r\nerror",
+        );
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line, "error: two");
+        assert_eq!(matches[0].offset, 5.0);
+        assert_eq!(matches[0].context_before, vec!["one"]);
+        assert_eq!(matches[0].context_after, vec!["three"]);
+        assert_eq!(matches[1].context_before, vec!["four"]);
+        assert!(matches[1].context_after.is_empty());
+    }
+
+    #[test]
+    fn test_search_stops_at_limit() {
+        let regex = build_regex("x", &SearchOptions::default()).unwrap();
+        let options = SearchOptions {
+            context_lines: Some(1),
+            ..SearchOptions::default()
+        };
+        let mut search = SessionSearch::new(&regex, "id", SearchSource::Scrollback, &options, 2);
+        assert!(search.push(0, b"x1\nx2\n"));
+        assert!(!search.push(6, b"x3\n"));
+        let matches = search.finish();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].context_after, vec!["x3"]);
+    }
+
+    #[test]
+    fn test_matches_are_filtered_by_time() {
+        let regex = build_regex("x", &SearchOptions::default()).unwrap();
+        let options = SearchOptions {
+            since: Some(2000.0),
+            until: Some(3000.0),
+            ..SearchOptions::default()
+        };
+        let search = SessionSearch::new(&regex, "id", SearchSource::Transcript, &options, 10);
+        // Output up to offset 3 was written by 1000, up to 6 by 2000, ...
+        let mut search = search.with_times(vec![(3, 1000.0), (6, 2000.0), (9, 3000.0)]);
+        search.push(0, b"x1\nx2\nx3\n");
+        let matches = search.finish();
+
+        let lines: Vec<(&str, Option<f64>)> =
+            matches.iter().map(|m| (m.line.as_str(), m.time)).collect();
+        assert_eq!(lines, vec![("x2", Some(2000.0))]);
+    }
+}
@@ -0,0 +1,270 @@
+//! Shell integration: command boundaries reported through OSC 133 marks.
+//!
+//! Shells following the FinalTerm convention mark the prompt and each
+//! command: `A` before the prompt, `B` after it, `C` when a command starts
+//! running and `D;<exit code>` when it finishes. The marks are turned into
+//! `PtyEvent`s. For bash, zsh and fish, opt-in scripts that emit the marks
+//! can be injected at spawn. The scripts are written to a directory under
+//! the app data once per run and shared by all sessions.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::osc::percent_decode;
+use crate::types::PtyEvent;
+
+const BASH_SCRIPT: &str = include_str!("shell_integration/bash.sh");
+const ZSHENV_SCRIPT: &str = include_str!("shell_integration/zshenv.zsh");
+const ZPROFILE_SCRIPT: &str = include_str!("shell_integration/zprofile.zsh");
+const ZSHRC_SCRIPT: &str = include_str!("shell_integration/zshrc.zsh");
+const ZSH_SCRIPT: &str = include_str!("shell_integration/integration.zsh");
+const FISH_SCRIPT: &str = include_str!("shell_integration/integration.fish");
+
+/// Environment variable passing the user's own ZDOTDIR to the zsh scripts
+const USER_ZDOTDIR_VAR: &str = "CLAUDE_CODE_GUI_ZDOTDIR";
+
+/// Directory the integration scripts were written to in this run
+static WRITTEN_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Turns OSC 133 marks into command lifecycle events.
+#[derive(Default)]
+pub struct CommandTracker {
+    /// When the running command started (mark `C`)
+    started: Option<Instant>,
+}
+
+impl CommandTracker {
+    /// Interprets an OSC payload. Returns an event if it is an OSC 133 mark.
+    pub fn handle(&mut self, payload: &str, now: Instant) -> Option<PtyEvent> {
+        let mark = payload.strip_prefix("133;")?;
+        let mut parts = mark.split(';');
+
+        match parts.next()? {
+            "A" => Some(PtyEvent::PromptStart),
+            "C" => {
+                self.started = Some(now);
+                let command = parts.find_map(|param| {
+                    if let Some(encoded) = param.strip_prefix("cmdline_url=") {
+                        Some(percent_decode(encoded))
+                    } else {
+                        param.strip_prefix("cmdline=").map(str::to_string)
+                    }
+                });
+                Some(PtyEvent::CommandStart { command })
+            }
+            "D" => {
+                let exit_code = parts.next().and_then(|code| code.parse().ok());
+                let duration_ms = self.started.take().map(|started| {
+                    now.duration_since(started)
+                        .as_millis()
+                        .try_into()
+                        .unwrap_or(u32::MAX)
+                });
+                Some(PtyEvent::CommandFinished {
+                    exit_code,
+                    duration_ms,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Arguments and environment that start a shell with integration loaded.
+pub struct Injection {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+/// Returns how to start `shell` as a login shell with the integration
+/// script loaded from `dir`, or None if the shell is not supported or the
+/// scripts could not be written. `user_zdotdir` is the ZDOTDIR the session
+/// would have had, which the zsh scripts restore.
+pub fn injection(shell: &str, dir: &Path, user_zdotdir: Option<&str>) -> Option<Injection> {
+    let name = Path::new(shell).file_name()?.to_str()?;
+    if !matches!(name, "bash" | "zsh" | "fish") {
+        log::warn!("Shell integration is not available for {shell}");
+        return None;
+    }
+
+    if let Err(e) = write_scripts(dir) {
+        log::warn!("Failed to write shell integration scripts: {e}");
+        return None;
+    }
+
+    let injection = match name {
+        "bash" => Injection {
+            args: vec![
+                "--init-file".to_string(),
+                dir.join("bash.sh").to_string_lossy().into_owned(),
+            ],
+            env: Vec::new(),
+        },
+        "zsh" => {
+            let mut env = vec![("ZDOTDIR".to_string(), dir.to_string_lossy().into_owned())];
+            if let Some(user_zdotdir) = user_zdotdir {
+                env.push((USER_ZDOTDIR_VAR.to_string(), user_zdotdir.to_string()));
+            }
+            Injection {
+                args: vec!["-l".to_string()],
+                env,
+            }
+        }
+        _ => Injection {
+            args: vec![
+                "-l".to_string(),
+                "--init-command".to_string(),
+                format!("source {}", fish_quote(&dir.join("integration.fish"))),
+            ],
+            env: Vec::new(),
+        },
+    };
+
+    Some(injection)
+}
+
+/// Writes the integration scripts to `dir` on first use in this run,
+/// replacing those of earlier versions. The directory is created private
+/// to the current user.
+fn write_scripts(dir: &Path) -> std::io::Result<()> {
+    let mut written_dir = super::lock(&WRITTEN_DIR);
+    if written_dir.as_deref() == Some(dir) {
+        return Ok(());
+    }
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+
+    for (file, contents) in [
+        ("bash.sh", BASH_SCRIPT),
+        (".zshenv", ZSHENV_SCRIPT),
+        (".zprofile", ZPROFILE_SCRIPT),
+        (".zshrc", ZSHRC_SCRIPT),
+        ("integration.zsh", ZSH_SCRIPT),
+        ("integration.fish", FISH_SCRIPT),
+    ] {
+        std::fs::write(dir.join(file), contents)?;
+    }
+
+    *written_dir = Some(dir.to_path_buf());
+    Ok(())
+}
+
+/// Quotes a path for use in a fish command line.
+fn fish_quote(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    format!("'{}'", path.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_prompt_start() {
+        let mut tracker = CommandTracker::default();
+        let event = tracker.handle("133;A", Instant::now());
+        assert!(matches!(event, Some(PtyEvent::PromptStart)));
+    }
+
+    #[test]
+    fn test_prompt_end_and_other_sequences_are_ignored() {
+        let mut tracker = CommandTracker::default();
+        assert!(tracker.handle("133;B", Instant::now()).is_none());
+        assert!(tracker.handle("0;title", Instant::now()).is_none());
+        assert!(tracker.handle("1337;SetMark", Instant::now()).is_none());
+    }
+
+    #[test]
+    fn test_command_lifecycle() {
+        let mut tracker = CommandTracker::default();
+        let start = Instant::now();
+
+        let event = tracker.handle("133;C;cmdline_url=ls%20-la%20%ED%95%9C", start);
+        match event {
+            Some(PtyEvent::CommandStart { command }) => {
+                assert_eq!(command.as_deref(), Some("ls -la 한"));
+            }
+            other => panic!("Expected CommandStart, got {other:?}"),
+        }
+
+        let event = tracker.handle("133;D;2", start + Duration::from_millis(1500));
+        match event {
+            Some(PtyEvent::CommandFinished {
+                exit_code,
+                duration_ms,
+            }) => {
+                assert_eq!(exit_code, Some(2));
+                assert_eq!(duration_ms, Some(1500));
+            }
+            other => panic!("Expected CommandFinished, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_command_without_details() {
+        let mut tracker = CommandTracker::default();
+
+        let event = tracker.handle("133;C", Instant::now());
+        assert!(matches!(
+            event,
+            Some(PtyEvent::CommandStart { command: None })
+        ));
+
+        // Finished without a start mark or exit code
+        let mut tracker = CommandTracker::default();
+        let event = tracker.handle("133;D", Instant::now());
+        assert!(matches!(
+            event,
+            Some(PtyEvent::CommandFinished {
+                exit_code: None,
+                duration_ms: None
+            })
+        ));
+    }
+
+    #[test]
+    fn test_plain_cmdline_param() {
+        let mut tracker = CommandTracker::default();
+        let event = tracker.handle("133;C;aid=1;cmdline=make test", Instant::now());
+        assert!(matches!(
+            event,
+            Some(PtyEvent::CommandStart { command: Some(ref c) }) if c == "make test"
+        ));
+    }
+
+    #[test]
+    fn test_injection_for_supported_shells() {
+        let dir = std::env::temp_dir()
+            .join(format!("shell-integration-{}", uuid::Uuid::new_v4()))
+            .join("scripts");
+        let user_zdotdir = Some("/home/me/.zsh");
+
+        let bash = injection("/bin/bash", &dir, user_zdotdir).unwrap();
+        assert_eq!(bash.args[0], "--init-file");
+        assert_eq!(Path::new(&bash.args[1]), dir.join("bash.sh"));
+        assert!(dir.join("bash.sh").is_file());
+
+        let zsh = injection("/bin/zsh", &dir, user_zdotdir).unwrap();
+        let zdotdir = &zsh.env.iter().find(|(k, _)| k == "ZDOTDIR").unwrap().1;
+        assert_eq!(Path::new(zdotdir), dir);
+        assert!(dir.join(".zshrc").is_file());
+        assert!(zsh
+            .env
+            .contains(&(USER_ZDOTDIR_VAR.to_string(), "/home/me/.zsh".to_string())));
+
+        let fish = injection("/usr/bin/fish", &dir, None).unwrap();
+        assert_eq!(fish.args[1], "--init-command");
+
+        assert!(injection("/bin/sh", &dir, None).is_none());
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
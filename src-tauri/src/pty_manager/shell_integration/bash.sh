@@ -0,0 +1,75 @@
+# Shell integration for bash: reports prompt and command boundaries with
+# OSC 133 marks and the working directory with OSC 7. Loaded with
+# --init-file, so the login startup files are sourced here first.
+
+if [ -n "$__ccg_integration_loaded" ]; then
+    return
+fi
+__ccg_integration_loaded=1
+
+if [ -r /etc/profile ]; then
+    . /etc/profile
+fi
+if [ -r ~/.bash_profile ]; then
+    . ~/.bash_profile
+elif [ -r ~/.bash_login ]; then
+    . ~/.bash_login
+elif [ -r ~/.profile ]; then
+    . ~/.profile
+fi
+
+__ccg_in_command=0
+__ccg_prompt_ready=0
+
+__ccg_urlencode() {
+    local LC_ALL=C
+    local s="$1" out="" c i
+    for ((i = 0; i < ${#s}; i++)); do
+        c="${s:i:1}"
+        case "$c" in
+            [a-zA-Z0-9/.~_-]) out+="$c" ;;
+            *) printf -v c '%%%02X' "'$c"; out+="$c" ;;
+        esac
+    done
+    printf '%s' "$out"
+}
+
+# Runs first in PROMPT_COMMAND, so $? is still the status of the command
+__ccg_precmd() {
+    local status=$?
+    __ccg_prompt_ready=0
+    if [ "$__ccg_in_command" = 1 ]; then
+        printf '\e]133;D;%s\a' "$status"
+        __ccg_in_command=0
+    fi
+    printf '\e]7;file://%s%s\a' "$HOSTNAME" "$(__ccg_urlencode "$PWD")"
+    # Prompt frameworks may rebuild PS1 before every prompt
+    case "$PS1" in
+        *'133;B'*) ;;
+        *) PS1='\[\e]133;A\a\]'"$PS1"'\[\e]133;B\a\]' ;;
+    esac
+}
+
+# Runs last in PROMPT_COMMAND: the next DEBUG trap is the user's command
+__ccg_prompt_done() {
+    __ccg_prompt_ready=1
+}
+
+__ccg_preexec() {
+    if [ "$__ccg_prompt_ready" != 1 ] || [ -n "$COMP_LINE" ]; then
+        return
+    fi
+    # An empty command line runs PROMPT_COMMAND directly
+    case "$BASH_COMMAND" in
+        __ccg_precmd*) return ;;
+    esac
+    __ccg_prompt_ready=0
+    __ccg_in_command=1
+    local command
+    command=$(HISTTIMEFORMAT= builtin history 1 | sed 's/^ *[0-9]*[* ] *//')
+    printf '\e]133;C;cmdline_url=%s\a' "$(__ccg_urlencode "$command")"
+}
+
+# Newline-separated, since the user's PROMPT_COMMAND may end with ';'
+PROMPT_COMMAND=$'__ccg_precmd\n'"$PROMPT_COMMAND"$'\n__ccg_prompt_done'
+trap '__ccg_preexec' DEBUG
@@ -0,0 +1,29 @@
+# Shell integration for fish: reports prompt and command boundaries with
+# OSC 133 marks and the working directory with OSC 7. Loaded with
+# --init-command, after the user's config.
+
+if not set -q __ccg_integration_loaded
+    set -g __ccg_integration_loaded 1
+
+    function __ccg_prompt_start --on-event fish_prompt
+        printf '\e]7;file://%s%s\a' $hostname (string escape --style=url -- $PWD)
+        printf '\e]133;A\a'
+    end
+
+    function __ccg_preexec --on-event fish_preexec
+        printf '\e]133;C;cmdline_url=%s\a' (string escape --style=url -- $argv[1])
+    end
+
+    function __ccg_postexec --on-event fish_postexec
+        printf '\e]133;D;%s\a' $status
+    end
+
+    # Mark the end of the prompt, where command input starts
+    if functions -q fish_prompt
+        functions -c fish_prompt __ccg_original_fish_prompt
+        function fish_prompt
+            __ccg_original_fish_prompt
+            printf '\e]133;B\a'
+        end
+    end
+end
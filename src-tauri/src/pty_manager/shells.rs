@@ -0,0 +1,166 @@
+//! Allowlist of shells that sessions may be spawned with.
+//!
+//! The allowlist is part of the user's preferences and is seeded from
+//! `/etc/shells`. Entries are compared by canonical path, so a shell reached
+//! through a symlink (e.g. `/run/current-system/sw/bin/zsh` on NixOS, or a
+//! Nix profile) matches the entry for the same binary.
+
+use std::path::{Path, PathBuf};
+
+/// Shells allowed when `/etc/shells` is not available
+const FALLBACK_SHELLS: &[&str] = &[
+    "/bin/bash",
+    "/bin/zsh",
+    "/bin/sh",
+    "/bin/fish",
+    "/usr/bin/bash",
+    "/usr/bin/zsh",
+    "/usr/bin/fish",
+    "/usr/local/bin/bash",
+    "/usr/local/bin/zsh",
+    "/usr/local/bin/fish",
+    "/opt/homebrew/bin/bash",
+    "/opt/homebrew/bin/zsh",
+    "/opt/homebrew/bin/fish",
+];
+
+/// Returns the default allowlist: the installed shells listed in
+/// `/etc/shells`, or in `FALLBACK_SHELLS` if it cannot be read.
+pub fn default_allowed_shells() -> Vec<String> {
+    let listed = match std::fs::read_to_string("/etc/shells") {
+        Ok(contents) => parse_etc_shells(&contents),
+        Err(e) => {
+            log::debug!("Failed to read /etc/shells, using built-in list: {e}");
+            FALLBACK_SHELLS.iter().map(|s| s.to_string()).collect()
+        }
+    };
+
+    let mut shells: Vec<String> = Vec::new();
+    for shell in listed {
+        if verify_shell(&shell).is_ok() && !shells.contains(&shell) {
+            shells.push(shell);
+        }
+    }
+    shells
+}
+
+/// Checks that `shell` is an absolute path to an executable file and
+/// returns its canonical path.
+pub fn verify_shell(shell: &str) -> Result<PathBuf, String> {
+    let path = Path::new(shell);
+    if !path.is_absolute() {
+        return Err(format!("Shell path must be absolute: {shell}"));
+    }
+
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("Shell not found: {shell} ({e})"))?;
+    let metadata =
+        std::fs::metadata(&canonical).map_err(|e| format!("Shell not found: {shell} ({e})"))?;
+    if !metadata.is_file() {
+        return Err(format!("Shell is not a file: {shell}"));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(format!("Shell is not executable: {shell}"));
+        }
+    }
+
+    Ok(canonical)
+}
+
+/// Canonical paths of the allowed shells.
+pub struct ShellAllowlist {
+    shells: Vec<PathBuf>,
+}
+
+impl ShellAllowlist {
+    /// Builds the allowlist from the configured entries. Entries that no
+    /// longer resolve to an executable are skipped.
+    pub fn new(shells: &[String]) -> Self {
+        let shells = shells
+            .iter()
+            .filter_map(|shell| {
+                verify_shell(shell)
+                    .inspect_err(|e| log::warn!("Ignoring allowed shell entry: {e}"))
+                    .ok()
+            })
+            .collect();
+        Self { shells }
+    }
+
+    /// Whether `shell` resolves to one of the allowed shells.
+    pub fn allows(&self, shell: &str) -> bool {
+        verify_shell(shell).is_ok_and(|canonical| self.shells.contains(&canonical))
+    }
+}
+
+/// Returns the shell paths listed in the contents of `/etc/shells`.
+fn parse_etc_shells(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_etc_shells() {
+        let contents = "# /etc/shells: valid login shells\n/bin/sh\n\n  /usr/bin/zsh  \nnologin\n";
+        assert_eq!(parse_etc_shells(contents), vec!["/bin/sh", "/usr/bin/zsh"]);
+    }
+
+    #[test]
+    fn test_verify_shell() {
+        assert!(verify_shell("/bin/sh").is_ok());
+        assert!(verify_shell("sh").unwrap_err().contains("absolute"));
+        assert!(verify_shell("/nonexistent/shell")
+            .unwrap_err()
+            .contains("not found"));
+        assert!(verify_shell("/tmp").unwrap_err().contains("not a file"));
+    }
+
+    #[test]
+    fn test_verify_shell_rejects_non_executable_file() {
+        let path = std::env::temp_dir().join(format!("not-a-shell-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+
+        let result = verify_shell(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.unwrap_err().contains("not executable"));
+    }
+
+    #[test]
+    fn test_allowlist_matches_by_canonical_path() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("shells-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let link = dir.join("sh");
+        symlink("/bin/sh", &link).unwrap();
+        let link = link.to_str().unwrap().to_string();
+
+        let allowlist = ShellAllowlist::new(&[link.clone(), "/nonexistent/zsh".to_string()]);
+        assert!(allowlist.allows("/bin/sh"));
+        assert!(allowlist.allows(&link));
+        assert!(!allowlist.allows("/nonexistent/zsh"));
+        assert!(!allowlist.allows("sh"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_default_allowed_shells_are_executable() {
+        let shells = default_allowed_shells();
+        assert!(!shells.is_empty());
+        assert!(shells.iter().all(|shell| verify_shell(shell).is_ok()));
+    }
+}
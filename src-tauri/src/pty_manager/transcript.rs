@@ -0,0 +1,606 @@
+//! Persistent transcripts of PTY session output.
+//!
+//! Each session with a transcript gets a directory, named after the session
+//! ID, containing:
+//!
+//! - `meta.json`: the executable, working directory and start time
+//! - `NNNNNN.log.gz`: the raw output, in gzip segments of about
+//!   `SEGMENT_BYTES` uncompressed bytes. Only the newest `MAX_SEGMENTS` are
+//!   kept.
+//! - `index.jsonl`: one entry per line with a time (milliseconds since the
+//!   Unix epoch) and the offset in the output it applies to. Entries mark
+//!   where each segment starts, shell integration marks (prompts, commands
+//!   and their exit codes) and a checkpoint each time the transcript is
+//!   synced.
+//!
+//! The index is only ever appended to. While output arrives, the transcript
+//! is synced to disk every `FLUSH_INTERVAL`. Segments are flushed with a
+//! gzip sync flush, so a segment cut short by a crash still decompresses up
+//! to the last sync.
+//!
+//! Secrets are redacted before output is written, so offsets are in the
+//! redacted output. Output that may be the start of a secret is held back
+//! until the rest arrives, a mark is added or the transcript is finished.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::redaction::{RedactStream, Redactor};
+use crate::types::PtyEvent;
+use crate::utils::fs::{create_private, write_private};
+
+/// Uncompressed size after which a new segment is started (8MB)
+pub const SEGMENT_BYTES: u64 = 8_388_608;
+
+/// Number of segments kept per session. Older segments are deleted.
+pub const MAX_SEGMENTS: u32 = 8;
+
+/// Maximum total size of the transcripts directory (1GB). The transcripts
+/// of the oldest sessions are deleted when a new session starts.
+pub const MAX_TOTAL_BYTES: u64 = 1_073_741_824;
+
+/// How long output may stay in memory before it is synced to disk
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Name of the index file in a transcript directory
+const INDEX_FILE: &str = "index.jsonl";
+
+/// Size of the chunks output is read in
+const READ_CHUNK_SIZE: usize = 65_536;
+
+/// An entry of the transcript index.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Milliseconds since the Unix epoch
+    pub time: u64,
+    /// Offset in the session's output, counted from the start of the session
+    pub offset: u64,
+    #[serde(flatten)]
+    pub mark: Mark,
+}
+
+/// What an index entry marks.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mark {
+    /// All output up to the offset was synced to disk
+    Checkpoint,
+    /// A segment starts at the offset
+    Segment { segment: u32 },
+    /// The shell is about to draw its prompt
+    Prompt,
+    /// A command started running
+    Command { command: Option<String> },
+    /// A command finished
+    CommandFinished { exit_code: Option<i32> },
+    /// The shell's working directory changed
+    Cwd { cwd: String },
+}
+
+/// Session details written to `meta.json`.
+pub struct Meta<'a> {
+    pub shell: &'a str,
+    pub cwd: Option<&'a str>,
+}
+
+/// A transcript being written.
+pub struct Transcript {
+    dir: PathBuf,
+    session_id: String,
+    /// Current segment
+    encoder: GzEncoder<File>,
+    segment: u32,
+    /// Uncompressed bytes written to the current segment
+    segment_bytes: u64,
+    /// Uncompressed bytes written since the session started
+    offset: u64,
+    index: BufWriter<File>,
+    /// Whether anything was written since the last sync
+    dirty: bool,
+    /// Whether writing failed, which stops the transcript
+    failed: bool,
+    redact: RedactStream,
+}
+
+impl Transcript {
+    /// Creates the transcript directory of a session in `root`.
+    pub fn create(
+        root: &Path,
+        session_id: &str,
+        meta: Meta<'_>,
+        redactor: Arc<Redactor>,
+    ) -> std::io::Result<Self> {
+        let dir = root.join(session_id);
+        std::fs::create_dir_all(&dir)?;
+
+        let meta_json = json!({
+            "session_id": session_id,
+            "shell": meta.shell,
+            "cwd": meta.cwd,
+            "started_at": now_ms(),
+        });
+        write_private(&dir.join("meta.json"), meta_json.to_string().as_bytes())?;
+
+        let mut transcript = Self {
+            encoder: create_segment(&dir, 0)?,
+            index: BufWriter::new(create_private(&dir.join(INDEX_FILE))?),
+            dir,
+            session_id: session_id.to_string(),
+            segment: 0,
+            segment_bytes: 0,
+            offset: 0,
+            dirty: false,
+            failed: false,
+            redact: RedactStream::new(redactor),
+        };
+        transcript.append(Mark::Segment { segment: 0 })?;
+        transcript.index.flush()?;
+        Ok(transcript)
+    }
+
+    /// Directory of the transcript.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Appends output, starting a new segment if the current one is full.
+    pub fn write(&mut self, data: &[u8]) {
+        if data.is_empty() || self.failed {
+            return;
+        }
+        let data = self.redact.push(data);
+        self.write_redacted(&data);
+    }
+
+    /// Adds a shell integration mark to the index, at the current offset.
+    pub fn mark(&mut self, event: &PtyEvent) {
+        let mark = match event {
+            PtyEvent::PromptStart => Mark::Prompt,
+            PtyEvent::CommandStart { command } => Mark::Command {
+                command: command.clone(),
+            },
+            PtyEvent::CommandFinished { exit_code, .. } => Mark::CommandFinished {
+                exit_code: *exit_code,
+            },
+            PtyEvent::CwdChanged { cwd } => Mark::Cwd { cwd: cwd.clone() },
+            _ => return,
+        };
+        if !self.failed {
+            // The mark applies after the output held back for redaction
+            let data = self.redact.flush();
+            self.write_redacted(&data);
+            let result = self.append(mark);
+            self.check(result);
+        }
+    }
+
+    /// Syncs the output written so far and the index to disk.
+    pub fn flush(&mut self) {
+        if !self.dirty || self.failed {
+            return;
+        }
+        let result = self.try_flush();
+        self.check(result);
+    }
+
+    /// Syncs the transcript and completes the current segment. Nothing may
+    /// be written afterwards.
+    pub fn finish(&mut self) {
+        let data = self.redact.flush();
+        self.write_redacted(&data);
+        self.flush();
+        if self.failed {
+            return;
+        }
+        let result = self
+            .encoder
+            .try_finish()
+            .and_then(|()| self.encoder.get_ref().sync_data());
+        self.check(result);
+    }
+
+    /// Deletes the transcript, for a session that failed to start.
+    pub fn discard(self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            log::warn!("Failed to delete transcript {}: {e}", self.dir.display());
+        }
+    }
+
+    fn write_redacted(&mut self, data: &[u8]) {
+        if !data.is_empty() && !self.failed {
+            let result = self.try_write(data);
+            self.check(result);
+        }
+    }
+
+    fn try_write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if self.segment_bytes >= SEGMENT_BYTES {
+            self.rotate()?;
+        }
+        self.encoder.write_all(data)?;
+        self.segment_bytes += data.len() as u64;
+        self.offset += data.len() as u64;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn try_flush(&mut self) -> std::io::Result<()> {
+        // Output first, so the index never points past what is on disk
+        self.encoder.flush()?;
+        self.encoder.get_ref().sync_data()?;
+        self.append(Mark::Checkpoint)?;
+        self.index.flush()?;
+        self.index.get_ref().sync_data()?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Completes the current segment and starts the next one, deleting the
+    /// oldest segment if there are too many.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let segment = self.segment + 1;
+        let previous = std::mem::replace(&mut self.encoder, create_segment(&self.dir, segment)?);
+        previous.finish()?.sync_data()?;
+        self.segment = segment;
+        self.segment_bytes = 0;
+        self.append(Mark::Segment { segment })?;
+
+        if let Some(oldest) = segment.checked_sub(MAX_SEGMENTS) {
+            let path = segment_path(&self.dir, oldest);
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!(
+                    "Failed to delete transcript segment {}: {e}",
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, mark: Mark) -> std::io::Result<()> {
+        let entry = IndexEntry {
+            time: now_ms(),
+            offset: self.offset,
+            mark,
+        };
+        serde_json::to_writer(&mut self.index, &entry)?;
+        self.index.write_all(b"\n")
+    }
+
+    fn check(&mut self, result: std::io::Result<()>) {
+        if let Err(e) = result {
+            log::error!(
+                "Failed to write transcript of session {}, transcript stopped: {e}",
+                self.session_id
+            );
+            self.failed = true;
+        }
+    }
+}
+
+/// Path of a segment in a transcript directory.
+fn segment_path(dir: &Path, segment: u32) -> PathBuf {
+    dir.join(format!("{segment:06}.log.gz"))
+}
+
+fn create_segment(dir: &Path, segment: u32) -> std::io::Result<GzEncoder<File>> {
+    let file = create_private(&segment_path(dir, segment))?;
+    Ok(GzEncoder::new(file, Compression::default()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Lists the transcripts in `root` as session IDs and directories.
+pub fn list(root: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.path())))
+        .collect()
+}
+
+/// Reads the index of a transcript. A partly written last entry, left by a
+/// crash, is skipped.
+pub fn read_index(dir: &Path) -> std::io::Result<Vec<IndexEntry>> {
+    let contents = std::fs::read_to_string(dir.join(INDEX_FILE))?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Reads the output of a transcript from its oldest kept segment, passing
+/// each chunk and its offset to `f` until it returns false. Only output
+/// that was synced to disk is read.
+pub fn read_output(
+    dir: &Path,
+    index: &[IndexEntry],
+    mut f: impl FnMut(u64, &[u8]) -> bool,
+) -> std::io::Result<()> {
+    let segments = index.iter().filter_map(|entry| match entry.mark {
+        Mark::Segment { segment } => Some((segment, entry.offset)),
+        _ => None,
+    });
+
+    let mut buf = vec![0u8; READ_CHUNK_SIZE];
+    for (segment, mut offset) in segments {
+        let file = match File::open(segment_path(dir, segment)) {
+            Ok(file) => file,
+            // Deleted when the transcript was rotated
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut decoder = GzDecoder::new(BufReader::new(file));
+        loop {
+            match decoder.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if !f(offset, &buf[..n]) {
+                        return Ok(());
+                    }
+                    offset += n as u64;
+                }
+                // The segment is still being written, or was cut short
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the transcripts of the oldest sessions in `root` until at most
+/// `keep_bytes` are left. Transcripts in `active` are never deleted.
+pub fn prune(root: &Path, keep_bytes: u64, active: &[PathBuf]) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+
+    // Sessions ordered by when their index was last written to
+    let mut transcripts: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            let dir = entry.path();
+            let modified = std::fs::metadata(dir.join(INDEX_FILE))
+                .and_then(|m| m.modified())
+                .unwrap_or(UNIX_EPOCH);
+            (modified, dir_size(&dir), dir)
+        })
+        .collect();
+    transcripts.sort();
+
+    let mut total: u64 = transcripts.iter().map(|(_, len, _)| len).sum();
+    for (_, len, dir) in transcripts {
+        if total <= keep_bytes {
+            break;
+        }
+        if active.contains(&dir) {
+            continue;
+        }
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => {
+                log::info!("Deleted old transcript: {}", dir.display());
+                total -= len;
+            }
+            Err(e) => log::warn!("Failed to delete old transcript {}: {e}", dir.display()),
+        }
+    }
+}
+
+/// Total size of the files in a directory.
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("transcripts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    fn meta() -> Meta<'static> {
+        Meta {
+            shell: "/bin/sh",
+            cwd: Some("/tmp"),
+        }
+    }
+
+    /// Reads the output of a transcript, with the offset it starts at.
+    fn read_all(dir: &Path) -> (u64, Vec<u8>) {
+        let index = read_index(dir).unwrap();
+        let mut start = None;
+        let mut data = Vec::new();
+        read_output(dir, &index, |offset, chunk| {
+            start.get_or_insert(offset);
+            data.extend_from_slice(chunk);
+            true
+        })
+        .unwrap();
+        (start.unwrap_or(0), data)
+    }
+
+    #[test]
+    fn test_transcript_indexes_marks() {
+        let root = temp_dir();
+        let mut transcript =
+            Transcript::create(&root, "session", meta(), Arc::new(Redactor::default())).unwrap();
+        transcript.mark(&PtyEvent::PromptStart);
+        transcript.write(b"$ ls\r\n");
+        transcript.mark(&PtyEvent::CommandStart {
+            command: Some("ls".to_string()),
+        });
+        transcript.write(b"file\r\n");
+        transcript.mark(&PtyEvent::CommandFinished {
+            exit_code: Some(0),
+            duration_ms: Some(5),
+        });
+        transcript.mark(&PtyEvent::TitleChanged {
+            title: "not indexed".to_string(),
+        });
+        transcript.finish();
+
+        let dir = root.join("session");
+        assert_eq!(read_all(&dir), (0, b"$ ls\r\nfile\r\n".to_vec()));
+
+        let marks: Vec<(u64, Mark)> = read_index(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.offset, entry.mark))
+            .collect();
+        assert_eq!(
+            marks,
+            vec![
+                (0, Mark::Segment { segment: 0 }),
+                (0, Mark::Prompt),
+                (
+                    6,
+                    Mark::Command {
+                        command: Some("ls".to_string())
+                    }
+                ),
+                (12, Mark::CommandFinished { exit_code: Some(0) }),
+                (12, Mark::Checkpoint),
+            ]
+        );
+
+        let meta: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.join("meta.json")).unwrap()).unwrap();
+        assert_eq!(meta["session_id"], "session");
+        assert_eq!(meta["shell"], "/bin/sh");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_flushed_output_survives_a_crash() {
+        let root = temp_dir();
+        let redactor = Arc::new(Redactor::passthrough());
+        let mut transcript = Transcript::create(&root, "session", meta(), redactor).unwrap();
+        transcript.write(b"synced");
+        transcript.flush();
+        transcript.write(b" lost");
+
+        // Simulate a crash: the segment is never completed
+        let dir = transcript.dir().to_path_buf();
+        std::mem::forget(transcript);
+
+        assert_eq!(read_all(&dir), (0, b"synced".to_vec()));
+        let last = read_index(&dir).unwrap().pop().unwrap();
+        assert_eq!((last.offset, last.mark), (6, Mark::Checkpoint));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_transcript_redacts_secrets() {
+        let root = temp_dir();
+        let redactor = Arc::new(Redactor::default());
+        let mut transcript = Transcript::create(&root, "session", meta(), redactor).unwrap();
+        transcript.write(b"KEY=sk-ant-api03-abcdef");
+        transcript.write(b"ghijklmnopqrstuvwxyz");
+        transcript.mark(&PtyEvent::PromptStart);
+        transcript.write(b"$ ");
+        transcript.finish();
+
+        let dir = root.join("session");
+        assert_eq!(read_all(&dir), (0, b"KEY=[REDACTED]$ ".to_vec()));
+        let prompt = read_index(&dir)
+            .unwrap()
+            .into_iter()
+            .find(|entry| entry.mark == Mark::Prompt)
+            .unwrap();
+        assert_eq!(prompt.offset, 14);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_transcript_rotates_segments() {
+        let root = temp_dir();
+        let mut transcript =
+            Transcript::create(&root, "session", meta(), Arc::new(Redactor::default())).unwrap();
+        let chunk = vec![b'x'; SEGMENT_BYTES as usize];
+        for _ in 0..MAX_SEGMENTS + 2 {
+            transcript.write(&chunk);
+        }
+        transcript.write(b"end");
+        transcript.finish();
+
+        let dir = root.join("session");
+        let last = MAX_SEGMENTS + 2;
+        assert!(!segment_path(&dir, 0).exists());
+        assert!(!segment_path(&dir, 2).exists());
+        assert!(segment_path(&dir, 3).exists());
+
+        // Output is read from the oldest kept segment
+        let (start, data) = read_all(&dir);
+        assert_eq!(start, 3 * SEGMENT_BYTES);
+        assert_eq!(data.len() as u64, 7 * SEGMENT_BYTES + 3);
+        assert!(data.ends_with(b"xend"));
+
+        let starts: Vec<(u32, u64)> = read_index(&dir)
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry.mark {
+                Mark::Segment { segment } => Some((segment, entry.offset)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(starts.len() as u32, last + 1);
+        assert_eq!(starts[3], (3, 3 * SEGMENT_BYTES));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_prune_deletes_oldest_transcripts() {
+        let root = temp_dir();
+        let dirs: Vec<PathBuf> = (0..4)
+            .map(|i| {
+                let dir = root.join(format!("session-{i}"));
+                std::fs::create_dir(&dir).unwrap();
+                let index = dir.join(INDEX_FILE);
+                std::fs::write(&index, [0u8; 100]).unwrap();
+                let modified = UNIX_EPOCH + Duration::from_secs(1000 + i);
+                File::options()
+                    .write(true)
+                    .open(&index)
+                    .unwrap()
+                    .set_modified(modified)
+                    .unwrap();
+                dir
+            })
+            .collect();
+
+        // The oldest transcript is active, so the next two are deleted
+        prune(&root, 200, &dirs[..1]);
+        let exists: Vec<bool> = dirs.iter().map(|d| d.exists()).collect();
+        assert_eq!(exists, vec![true, false, false, true]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
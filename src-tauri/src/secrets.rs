@@ -0,0 +1,491 @@
+//! Encrypted vault for secrets injected into sessions, such as API keys.
+//!
+//! Secrets are saved to `secrets.vault` in the app data directory, encrypted
+//! with ChaCha20-Poly1305 under a random key kept in `secrets.key` next to
+//! it. Both files are only readable by the user. With a passphrase, the
+//! encryption key is derived with Argon2id from the passphrase and the
+//! keyfile, so both are needed, and the vault stays locked until it is
+//! unlocked.
+//!
+//! Secret values never leave the backend: the frontend only sees their
+//! names, and `${secret:NAME}` references in a session's environment are
+//! resolved by `PtyManager::spawn`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use crate::types::SecretsStatus;
+use crate::utils::fs::{replace_private, write_private};
+
+const KEY_FILE: &str = "secrets.key";
+const VAULT_FILE: &str = "secrets.vault";
+
+/// Format version of the vault file
+const VAULT_VERSION: u32 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// How a secret is referenced in an environment value
+const REFERENCE_PREFIX: &str = "${secret:";
+const REFERENCE_SUFFIX: &str = "}";
+
+type EncryptionKey = [u8; KEY_LEN];
+
+/// The vault file. Secrets are stored as an encrypted JSON object.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    /// Salt of the passphrase key, if the vault has a passphrase
+    #[serde(default, with = "optional_base64")]
+    salt: Option<Vec<u8>>,
+    #[serde(with = "crate::types::base64_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "crate::types::base64_bytes")]
+    ciphertext: Vec<u8>,
+}
+
+/// Store of named secrets. It is unavailable until `open` succeeds.
+#[derive(Default)]
+pub struct SecretsVault {
+    vault: Mutex<Option<Vault>>,
+}
+
+/// An opened vault
+struct Vault {
+    dir: PathBuf,
+    keyfile: EncryptionKey,
+    /// Salt of the passphrase key, if the vault has a passphrase
+    salt: Option<Vec<u8>>,
+    /// Key and secrets, once decrypted
+    unlocked: Option<Unlocked>,
+}
+
+struct Unlocked {
+    key: EncryptionKey,
+    secrets: BTreeMap<String, String>,
+}
+
+impl SecretsVault {
+    /// Opens the vault in `dir`, creating the keyfile if there is no vault
+    /// yet. A vault without a passphrase is unlocked right away.
+    pub fn open(&self, dir: &Path) -> Result<(), String> {
+        let vault_path = dir.join(VAULT_FILE);
+        let keyfile = match read_keyfile(&dir.join(KEY_FILE))? {
+            Some(key) => key,
+            None if vault_path.exists() => return Err(missing_keyfile()),
+            None => create_keyfile(&dir.join(KEY_FILE))?,
+        };
+
+        let mut vault = Vault {
+            dir: dir.to_path_buf(),
+            keyfile,
+            salt: None,
+            unlocked: None,
+        };
+        match read_vault_file(&vault_path)? {
+            None => {
+                vault.unlocked = Some(Unlocked {
+                    key: keyfile,
+                    secrets: BTreeMap::new(),
+                })
+            }
+            Some(file) if file.salt.is_some() => vault.salt = file.salt,
+            Some(file) => {
+                vault.unlocked = Some(Unlocked {
+                    key: keyfile,
+                    secrets: decrypt(&keyfile, &file)?,
+                })
+            }
+        }
+
+        *self.lock() = Some(vault);
+        Ok(())
+    }
+
+    /// Names of the stored secrets and whether the vault is locked.
+    pub fn status(&self) -> Result<SecretsStatus, String> {
+        let guard = self.lock();
+        let vault = guard.as_ref().ok_or_else(unavailable)?;
+        Ok(SecretsStatus {
+            names: vault
+                .unlocked
+                .iter()
+                .flat_map(|u| u.secrets.keys().cloned())
+                .collect(),
+            has_passphrase: vault.salt.is_some(),
+            locked: vault.unlocked.is_none(),
+        })
+    }
+
+    /// Unlocks a vault protected by a passphrase.
+    pub fn unlock(&self, passphrase: &str) -> Result<(), String> {
+        let mut guard = self.lock();
+        let vault = guard.as_mut().ok_or_else(unavailable)?;
+        let Some(salt) = &vault.salt else {
+            return Ok(());
+        };
+
+        let key = derive_key(&vault.keyfile, passphrase, salt)?;
+        let file = read_vault_file(&vault.dir.join(VAULT_FILE))?
+            .ok_or_else(|| "The secrets vault file is missing".to_string())?;
+        let secrets = decrypt(&key, &file).map_err(|_| "Wrong passphrase".to_string())?;
+        vault.unlocked = Some(Unlocked { key, secrets });
+        Ok(())
+    }
+
+    /// Stores a secret, replacing the secret with the same name.
+    pub fn set(&self, name: &str, value: String) -> Result<(), String> {
+        self.update(|unlocked| {
+            unlocked.secrets.insert(name.to_string(), value);
+            Ok(())
+        })
+    }
+
+    /// Deletes a secret.
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        self.update(|unlocked| {
+            unlocked
+                .secrets
+                .remove(name)
+                .map(drop)
+                .ok_or_else(|| format!("Secret not found: {name}"))
+        })
+    }
+
+    /// Sets or removes (with None) the passphrase. The vault must be unlocked.
+    pub fn set_passphrase(&self, passphrase: Option<&str>) -> Result<(), String> {
+        let mut guard = self.lock();
+        let vault = guard.as_mut().ok_or_else(unavailable)?;
+        let unlocked = vault.unlocked.as_ref().ok_or_else(locked)?;
+
+        let (key, salt) = match passphrase {
+            Some("") => return Err("Passphrase cannot be empty".to_string()),
+            Some(passphrase) => {
+                let mut salt = vec![0; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                (derive_key(&vault.keyfile, passphrase, &salt)?, Some(salt))
+            }
+            None => (vault.keyfile, None),
+        };
+        let unlocked = Unlocked {
+            key,
+            secrets: unlocked.secrets.clone(),
+        };
+
+        write_vault_file(&vault.dir, salt.clone(), &unlocked)?;
+        vault.salt = salt;
+        vault.unlocked = Some(unlocked);
+        Ok(())
+    }
+
+    /// Returns `env` with `${secret:NAME}` values replaced by the secrets
+    /// they name. Other values are kept as they are.
+    pub fn resolve_env(
+        &self,
+        env: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, String> {
+        let guard = self.lock();
+        env.iter()
+            .map(|(key, value)| {
+                let Some(name) = parse_reference(value) else {
+                    return Ok((key.clone(), value.clone()));
+                };
+                let vault = guard.as_ref().ok_or_else(unavailable)?;
+                let unlocked = vault.unlocked.as_ref().ok_or_else(locked)?;
+                let secret = unlocked
+                    .secrets
+                    .get(name)
+                    .ok_or_else(|| format!("Unknown secret: {name}"))?;
+                Ok((key.clone(), secret.clone()))
+            })
+            .collect()
+    }
+
+    /// Changes the secrets of an unlocked vault and saves them.
+    fn update(
+        &self,
+        change: impl FnOnce(&mut Unlocked) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut guard = self.lock();
+        let vault = guard.as_mut().ok_or_else(unavailable)?;
+        let unlocked = vault.unlocked.as_mut().ok_or_else(locked)?;
+
+        let previous = unlocked.secrets.clone();
+        change(unlocked)?;
+        if let Err(e) = write_vault_file(&vault.dir, vault.salt.clone(), unlocked) {
+            // Keep the secrets in sync with the file
+            unlocked.secrets = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Vault>> {
+        self.vault.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn unavailable() -> String {
+    "The secrets vault is not available".to_string()
+}
+
+fn missing_keyfile() -> String {
+    "The secrets key file is missing, so the stored secrets cannot be decrypted".to_string()
+}
+
+fn locked() -> String {
+    "The secrets vault is locked".to_string()
+}
+
+/// Returns the secret name of a `${secret:NAME}` reference.
+fn parse_reference(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(REFERENCE_PREFIX)?
+        .strip_suffix(REFERENCE_SUFFIX)
+}
+
+/// Derives the encryption key of a vault with a passphrase. The keyfile is
+/// used as the Argon2 secret, so the passphrase alone is not enough.
+fn derive_key(
+    keyfile: &EncryptionKey,
+    passphrase: &str,
+    salt: &[u8],
+) -> Result<EncryptionKey, String> {
+    let argon2 = Argon2::new_with_secret(
+        keyfile,
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::default(),
+    )
+    .map_err(|e| format!("Failed to derive the secrets key: {e}"))?;
+    let mut key = [0; KEY_LEN];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive the secrets key: {e}"))?;
+    Ok(key)
+}
+
+fn decrypt(key: &EncryptionKey, file: &VaultFile) -> Result<BTreeMap<String, String>, String> {
+    if file.version != VAULT_VERSION {
+        return Err(format!(
+            "Unsupported secrets vault version: {}",
+            file.version
+        ));
+    }
+    if file.nonce.len() != 12 {
+        return Err("Invalid secrets vault nonce".to_string());
+    }
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(&file.nonce), file.ciphertext.as_slice())
+        .map_err(|_| "Failed to decrypt the secrets vault".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Failed to parse secrets: {e}"))
+}
+
+/// Reads the keyfile, or None if there is none.
+fn read_keyfile(path: &Path) -> Result<Option<EncryptionKey>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => bytes
+            .try_into()
+            .map(Some)
+            .map_err(|_| "Invalid secrets key file".to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read the secrets key file: {e}")),
+    }
+}
+
+fn create_keyfile(path: &Path) -> Result<EncryptionKey, String> {
+    let key: EncryptionKey = ChaCha20Poly1305::generate_key(&mut OsRng).into();
+    write_private(path, &key).map_err(|e| format!("Failed to write the secrets key file: {e}"))?;
+    Ok(key)
+}
+
+/// Reads the vault file. A missing file means there are no secrets.
+fn read_vault_file(path: &Path) -> Result<Option<VaultFile>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read the secrets vault: {e}")),
+    };
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse the secrets vault: {e}"))
+}
+
+/// Encrypts the secrets with a new nonce and writes the vault file,
+/// replacing it atomically (temp file + rename).
+fn write_vault_file(dir: &Path, salt: Option<Vec<u8>>, unlocked: &Unlocked) -> Result<(), String> {
+    let plaintext = serde_json::to_vec(&unlocked.secrets)
+        .map_err(|e| format!("Failed to serialize secrets: {e}"))?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&unlocked.key))
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt secrets".to_string())?;
+
+    let file = VaultFile {
+        version: VAULT_VERSION,
+        salt,
+        nonce: nonce.to_vec(),
+        ciphertext,
+    };
+    let json_content = serde_json::to_string_pretty(&file)
+        .map_err(|e| format!("Failed to serialize the secrets vault: {e}"))?;
+
+    replace_private(&dir.join(VAULT_FILE), json_content.as_bytes())
+        .map_err(|e| format!("Failed to write the secrets vault: {e}"))
+}
+
+/// Serializes optional byte buffers as base64 strings.
+mod optional_base64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => crate::types::base64_bytes::serialize(bytes, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "crate::types::base64_bytes")] Vec<u8>);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(bytes)| bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("secrets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_secrets_are_encrypted_at_rest() {
+        let dir = temp_dir();
+        let vault = SecretsVault::default();
+        vault.open(&dir).unwrap();
+        vault.set("anthropic", "sk-ant-secret".to_string()).unwrap();
+        vault.set("github", "ghp-secret".to_string()).unwrap();
+        vault.delete("github").unwrap();
+        assert!(vault.delete("github").is_err());
+
+        let contents = std::fs::read_to_string(dir.join(VAULT_FILE)).unwrap();
+        assert!(!contents.contains("sk-ant-secret"));
+        assert!(!contents.contains("anthropic"));
+
+        #[cfg(unix)]
+        for file in [KEY_FILE, VAULT_FILE] {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join(file))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600, "{file}");
+        }
+
+        // Reopening decrypts with the keyfile
+        let reopened = SecretsVault::default();
+        reopened.open(&dir).unwrap();
+        let status = reopened.status().unwrap();
+        assert_eq!(status.names, vec!["anthropic"]);
+        assert!(!status.locked);
+
+        // Without the keyfile the vault cannot be opened
+        std::fs::remove_file(dir.join(KEY_FILE)).unwrap();
+        assert!(SecretsVault::default().open(&dir).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_passphrase_locks_vault() {
+        let dir = temp_dir();
+        let vault = SecretsVault::default();
+        vault.open(&dir).unwrap();
+        vault.set("anthropic", "sk-ant-secret".to_string()).unwrap();
+        assert!(vault.set_passphrase(Some("")).is_err());
+        vault.set_passphrase(Some("correct horse")).unwrap();
+
+        let reopened = SecretsVault::default();
+        reopened.open(&dir).unwrap();
+        let status = reopened.status().unwrap();
+        assert!(status.locked && status.has_passphrase);
+        assert!(status.names.is_empty());
+        assert!(reopened.set("other", "value".to_string()).is_err());
+        assert_eq!(
+            reopened.resolve_env(&env(&[("KEY", "${secret:anthropic}")])),
+            Err("The secrets vault is locked".to_string())
+        );
+
+        assert_eq!(
+            reopened.unlock("wrong"),
+            Err("Wrong passphrase".to_string())
+        );
+        reopened.unlock("correct horse").unwrap();
+        assert_eq!(reopened.status().unwrap().names, vec!["anthropic"]);
+
+        // Removing the passphrase unlocks the vault on the next open
+        reopened.set_passphrase(None).unwrap();
+        let reopened = SecretsVault::default();
+        reopened.open(&dir).unwrap();
+        assert!(!reopened.status().unwrap().locked);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_env_references() {
+        let dir = temp_dir();
+        let vault = SecretsVault::default();
+        vault.open(&dir).unwrap();
+        vault.set("anthropic", "sk-ant-secret".to_string()).unwrap();
+
+        let resolved = vault
+            .resolve_env(&env(&[
+                ("ANTHROPIC_API_KEY", "${secret:anthropic}"),
+                ("PLAIN", "secret:anthropic"),
+                ("EMBEDDED", "x${secret:anthropic}"),
+            ]))
+            .unwrap();
+        assert_eq!(resolved["ANTHROPIC_API_KEY"], "sk-ant-secret");
+        assert_eq!(resolved["PLAIN"], "secret:anthropic");
+        assert_eq!(resolved["EMBEDDED"], "x${secret:anthropic}");
+
+        assert_eq!(
+            vault.resolve_env(&env(&[("KEY", "${secret:missing}")])),
+            Err("Unknown secret: missing".to_string())
+        );
+
+        // Values without references do not need an open vault
+        let unopened = SecretsVault::default();
+        assert!(unopened.resolve_env(&env(&[("A", "1")])).is_ok());
+        assert!(unopened
+            .resolve_env(&env(&[("A", "${secret:anthropic}")]))
+            .is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -34,6 +34,13 @@ pub struct AppPreferences {
     /// User's preferred language (e.g., "en", "es", "de")
     /// If None, uses system locale detection
     pub language: Option<String>,
+    /// Shells terminal sessions may be spawned with (absolute paths)
+    /// Defaults to the installed shells listed in /etc/shells
+    #[serde(default = "crate::pty_manager::default_allowed_shells")]
+    pub allowed_shells: Vec<String>,
+    /// Redaction of secrets from session output and files written to disk
+    #[serde(default)]
+    pub redaction: RedactionSettings,
 }
 
 impl Default for AppPreferences {
@@ -42,10 +49,42 @@ impl Default for AppPreferences {
             theme: "system".to_string(),
             quick_pane_shortcut: None, // None means use default
             language: None,            // None means use system locale
+            allowed_shells: crate::pty_manager::default_allowed_shells(),
+            redaction: RedactionSettings::default(),
         }
     }
 }
 
+/// Which secrets are redacted, and where
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RedactionSettings {
+    /// Redact the built-in patterns: API keys of Anthropic, OpenAI, AWS and
+    /// GitHub, JWTs, `Authorization` header credentials and private keys
+    #[serde(default = "default_true")]
+    pub builtin_patterns: bool,
+    /// Additional regular expressions to redact
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Also redact the output shown in the terminal. Applies to sessions
+    /// spawned afterwards.
+    #[serde(default)]
+    pub live_output: bool,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            builtin_patterns: true,
+            patterns: Vec::new(),
+            live_output: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
 // ============================================================================
 // Recovery Errors
 // ============================================================================
@@ -127,12 +166,51 @@ impl std::fmt::Display for PtyError {
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 #[serde(tag = "event", content = "data")]
 pub enum PtyEvent {
-    /// Raw output bytes from the PTY
-    Output { data: Vec<u8> },
-    /// PTY process exited
-    Exit { code: Option<i32> },
+    /// Raw output bytes from the PTY (base64-encoded on the wire),
+    /// tagged with the scrollback sequence number
+    Output {
+        #[serde(with = "base64_bytes")]
+        #[specta(type = String)]
+        data: Vec<u8>,
+        seq: u32,
+    },
+    /// PTY process exited.
+    /// `code` is set on a normal exit, `signal` (e.g. "SIGINT") if the
+    /// process was terminated by a signal.
+    Exit {
+        code: Option<i32>,
+        signal: Option<String>,
+    },
     /// Error occurred in the PTY
     Error { message: String },
+    /// The shell is about to draw its prompt (OSC 133;A)
+    PromptStart,
+    /// A command started running (OSC 133;C).
+    /// `command` is the command line, if the shell reported it.
+    CommandStart { command: Option<String> },
+    /// A command finished (OSC 133;D).
+    /// `duration_ms` is measured from the matching `CommandStart`.
+    CommandFinished {
+        exit_code: Option<i32>,
+        duration_ms: Option<u32>,
+    },
+    /// The shell's working directory changed (OSC 7)
+    CwdChanged { cwd: String },
+    /// The program in the terminal set the window title (OSC 0/2).
+    /// An empty title means the title was cleared.
+    TitleChanged { title: String },
+    /// The process in the foreground of the terminal changed.
+    /// None if it could not be determined.
+    ForegroundChanged { process: Option<ForegroundProcess> },
+    /// The terminal was resized. Only sent when a recording is played back.
+    Resize { cols: u16, rows: u16 },
+    /// Playback of a recording was paused, resumed, sought or stepped, or
+    /// reached the end. `position_ms` is the position in the recording.
+    PlaybackState {
+        position_ms: u32,
+        paused: bool,
+        finished: bool,
+    },
 }
 
 /// Options for spawning a new PTY session
@@ -140,18 +218,79 @@ pub enum PtyEvent {
 pub struct SpawnOptions {
     /// Shell command to run (defaults to $SHELL or /bin/zsh)
     pub command: Option<String>,
-    /// Arguments to pass to the command
+    /// Allowed program to run directly instead of a shell (e.g. "claude"),
+    /// looked up by name. Cannot be combined with `command`. The session
+    /// exits when the program does.
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Arguments to pass to the command, or to the program (validated
+    /// against the options it accepts)
     #[serde(default)]
     pub args: Vec<String>,
     /// Working directory (defaults to user's home)
     pub cwd: Option<String>,
-    /// Additional environment variables
+    /// Additional environment variables. A value of the form
+    /// `${secret:NAME}` is replaced with the secret NAME from the vault.
     #[serde(default)]
     pub env: HashMap<String, String>,
     /// Terminal columns
     pub cols: u16,
     /// Terminal rows
     pub rows: u16,
+    /// Scrollback buffer size in bytes (defaults to 1MB)
+    #[serde(default)]
+    pub scrollback_limit: Option<u32>,
+    /// Keep a headless model of the terminal screen, required for `pty_snapshot`
+    #[serde(default)]
+    pub track_screen: bool,
+    /// Load shell integration scripts (bash, zsh, fish) that report command
+    /// boundaries. Only applied when no shell arguments are given.
+    #[serde(default)]
+    pub shell_integration: bool,
+    /// Name of a saved environment profile to apply. Variables in `env`
+    /// take precedence over the profile's.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Write the session's output to a compressed transcript under app data
+    #[serde(default)]
+    pub transcript: bool,
+}
+
+/// Result of attaching to a PTY session
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AttachInfo {
+    /// Whether output newer than `since` was evicted from the buffer, so the
+    /// replay has a gap. The terminal should be rebuilt from
+    /// `pty_get_scrollback` instead.
+    pub truncated: bool,
+}
+
+/// Buffered output of a PTY session, used to rebuild the terminal after a reload
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScrollbackSnapshot {
+    /// Buffered output bytes, oldest first (base64-encoded on the wire)
+    #[serde(with = "base64_bytes")]
+    #[specta(type = String)]
+    pub data: Vec<u8>,
+    /// Sequence number of the last chunk included in `data`.
+    /// Live `Output` events with a greater `seq` follow this snapshot.
+    pub seq: u32,
+    /// Whether older output was evicted because the buffer limit was reached
+    pub truncated: bool,
+}
+
+/// Leader of the foreground process group of a PTY session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ForegroundProcess {
+    /// Process ID
+    pub pid: u32,
+    /// Executable name (e.g. "claude", "vim")
+    pub name: String,
+    /// Command line arguments, including the program name
+    pub argv: Vec<String>,
+    /// Whether this is the process the session was spawned with,
+    /// i.e. the shell is idle at its prompt
+    pub is_shell: bool,
 }
 
 /// Information about an active PTY session
@@ -159,10 +298,346 @@ pub struct SpawnOptions {
 pub struct SessionInfo {
     /// Unique session identifier
     pub id: String,
-    /// Process ID of the shell
+    /// Process ID of the shell or program
     pub pid: Option<u32>,
     /// Whether the session is still alive
     pub is_alive: bool,
+    /// Executable the session was spawned with: the shell, or the resolved
+    /// path of the program
+    pub shell: String,
+    /// Program the session runs directly, or None for a shell session
+    pub program: Option<String>,
+    /// Arguments passed to the executable
+    pub args: Vec<String>,
+    /// Working directory the session was started in
+    pub cwd: Option<String>,
+    /// Current working directory, as last reported by the shell (OSC 7).
+    /// Same as `cwd` until the shell reports a change.
+    pub current_cwd: Option<String>,
+    /// Creation time in milliseconds since the Unix epoch
+    pub created_at: f64,
+    /// Current terminal columns
+    pub cols: u16,
+    /// Current terminal rows
+    pub rows: u16,
+    /// User-assigned title
+    pub title: Option<String>,
+    /// Title last set by the program in the terminal (OSC 0/2),
+    /// independent of the user-assigned title
+    pub terminal_title: Option<String>,
+}
+
+/// Level of detail of a screen snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum SnapshotFormat {
+    /// Text of each row only
+    Text,
+    /// Text of each row plus every cell with its attributes
+    Cells,
+}
+
+/// Options for killing a PTY session
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct KillOptions {
+    /// How long the session's processes are given to exit after SIGHUP and
+    /// SIGTERM before they are killed with SIGKILL, in milliseconds.
+    /// Defaults to 3 seconds and is at most 60 seconds; 0 kills them right
+    /// away.
+    #[serde(default)]
+    pub timeout_ms: Option<u32>,
+}
+
+/// Options for recording a PTY session
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct RecordOptions {
+    /// Also record the input written to the session. Off by default, since
+    /// input includes anything typed at a password prompt.
+    #[serde(default)]
+    pub record_input: bool,
+    /// Maximum size of the recording file in bytes (defaults to 50MB, at
+    /// most 500MB). Recording stops when it is reached.
+    #[serde(default)]
+    pub max_bytes: Option<u32>,
+}
+
+/// A recording of a PTY session, in asciicast v2 format
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RecordingInfo {
+    /// Session being recorded
+    pub session_id: String,
+    /// File name of the recording, used to play it back
+    pub name: String,
+    /// Path of the `.cast` file
+    pub path: String,
+    /// When recording started (milliseconds since Unix epoch)
+    pub started_at: f64,
+    /// Size of the recording file in bytes
+    pub bytes: u32,
+    /// Whether input is recorded
+    pub record_input: bool,
+    /// Whether recording stopped early because the size limit was reached
+    pub truncated: bool,
+}
+
+/// Options for playing back a recording
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct PlaybackOptions {
+    /// Playback speed, e.g. 2.0 for twice as fast (defaults to 1.0)
+    #[serde(default)]
+    pub speed: Option<f64>,
+    /// Skip idle time: pauses between events longer than this are shortened
+    /// to it, in milliseconds
+    #[serde(default)]
+    pub max_idle_ms: Option<u32>,
+    /// Start paused, e.g. to step through the recording
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// A recording being played back
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PlaybackInfo {
+    /// Playback identifier, used to control it
+    pub id: String,
+    /// Terminal size at the start of the recording
+    pub cols: u16,
+    pub rows: u16,
+    /// Length of the playback in milliseconds, with idle time skipped
+    pub duration_ms: u32,
+}
+
+/// Options for searching session output
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression rather than as plain text
+    #[serde(default)]
+    pub regex: bool,
+    /// Match case exactly
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only search these sessions (all sessions if empty)
+    #[serde(default)]
+    pub session_ids: Vec<String>,
+    /// Only return output from this time on (milliseconds since Unix epoch)
+    #[serde(default)]
+    pub since: Option<f64>,
+    /// Only return output from before this time (milliseconds since Unix epoch)
+    #[serde(default)]
+    pub until: Option<f64>,
+    /// Lines of context returned before and after each match (defaults to 2)
+    #[serde(default)]
+    pub context_lines: Option<u32>,
+    /// Maximum number of matches returned (defaults to 200)
+    #[serde(default)]
+    pub max_results: Option<u32>,
+}
+
+/// Where a search match was found
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum SearchSource {
+    /// Scrollback of a running session without a transcript
+    Scrollback,
+    /// Transcript of a running or past session
+    Transcript,
+}
+
+/// A line of session output matching a search
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SearchMatch {
+    pub session_id: String,
+    pub source: SearchSource,
+    /// Offset of the line in the session's output, counted in bytes from
+    /// the start of the session
+    pub offset: f64,
+    /// When the line was output (milliseconds since Unix epoch, accurate
+    /// to about a second in transcripts)
+    pub time: Option<f64>,
+    /// The matching line, with escape sequences removed
+    pub line: String,
+    /// Lines before and after the matching line
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+/// Signal that can be sent to the foreground of a PTY session
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum Signal {
+    /// Interrupt, as sent by `Ctrl+C`
+    #[serde(rename = "SIGINT")]
+    Interrupt,
+    /// Polite request to terminate
+    #[serde(rename = "SIGTERM")]
+    Terminate,
+    /// The terminal hung up
+    #[serde(rename = "SIGHUP")]
+    Hangup,
+    /// Stop (pause), as sent by `Ctrl+Z`
+    #[serde(rename = "SIGTSTP")]
+    Stop,
+    /// Resume a stopped process
+    #[serde(rename = "SIGCONT")]
+    Continue,
+    /// Quit with a core dump, as sent by `Ctrl+\`
+    #[serde(rename = "SIGQUIT")]
+    Quit,
+    /// The terminal size changed
+    #[serde(rename = "SIGWINCH")]
+    WindowChange,
+}
+
+/// Rendered state of a session's terminal screen
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScreenSnapshot {
+    /// Number of rows on screen
+    pub rows: u16,
+    /// Number of columns on screen
+    pub cols: u16,
+    /// Cursor row (0-based)
+    pub cursor_row: u16,
+    /// Cursor column (0-based)
+    pub cursor_col: u16,
+    /// Whether the cursor is shown
+    pub cursor_visible: bool,
+    /// Whether a full-screen program has switched to the alternate screen
+    pub alternate_screen: bool,
+    /// Text of each row, up to the last cell that was written to
+    pub lines: Vec<String>,
+    /// Cells of each row, if requested with `SnapshotFormat::Cells`
+    pub cells: Option<Vec<Vec<ScreenCell>>>,
+}
+
+/// A single cell of the terminal screen
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct ScreenCell {
+    /// Character in the cell (empty if blank or covered by a wide character)
+    pub text: String,
+    /// Foreground color
+    pub fg: CellColor,
+    /// Background color
+    pub bg: CellColor,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub inverse: bool,
+    /// Whether the character spans two columns
+    pub wide: bool,
+}
+
+/// Color of a terminal cell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type")]
+pub enum CellColor {
+    /// The terminal's default color
+    #[default]
+    Default,
+    /// One of the 256 indexed palette colors
+    Indexed { index: u8 },
+    /// A 24-bit color
+    Rgb { r: u8, g: u8, b: u8 },
+}
+
+/// A named set of environment variables that sessions can be spawned with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct EnvProfile {
+    /// Unique name shown to the user (e.g. "staging proxy")
+    pub name: String,
+    /// Variables set by the profile
+    pub variables: Vec<EnvVariable>,
+}
+
+/// An environment variable of a profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct EnvVariable {
+    /// Variable name
+    pub key: String,
+    /// Value of the variable. Secret values are not sent to the frontend,
+    /// which sees None; saving a secret variable without a value keeps the
+    /// value saved before.
+    pub value: Option<String>,
+    /// Whether the value is secret (e.g. an API key)
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// State of the secrets vault. Secret values are never sent to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SecretsStatus {
+    /// Names of the stored secrets, empty while the vault is locked
+    pub names: Vec<String>,
+    /// Whether the vault is protected by a passphrase
+    pub has_passphrase: bool,
+    /// Whether the vault must be unlocked with its passphrase before its
+    /// secrets can be used
+    pub locked: bool,
+}
+
+/// Shells, login shell and Claude CLI found on the system
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct EnvironmentInfo {
+    /// Installed and allowed shells
+    pub shells: Vec<ShellInfo>,
+    /// The user's login shell, from the password database
+    pub login_shell: Option<String>,
+    /// The Claude CLI, if it was found on the login shell's PATH
+    pub claude: Option<ClaudeCliInfo>,
+}
+
+/// A shell found on the system
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ShellInfo {
+    /// Path of the shell, as listed in /etc/shells or the allowlist
+    pub path: String,
+    /// Shell name (e.g. "zsh")
+    pub name: String,
+    /// First line of `--version` output, if the shell reports one
+    pub version: Option<String>,
+    /// Whether sessions may be spawned with this shell
+    pub allowed: bool,
+}
+
+/// An installation of the Claude CLI
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ClaudeCliInfo {
+    /// Resolved path of the executable
+    pub path: String,
+    /// Reported version (e.g. "1.0.0 (Claude Code)")
+    pub version: Option<String>,
+    /// How the CLI was installed, guessed from its path
+    pub install_method: ClaudeInstallMethod,
+}
+
+/// How the Claude CLI was installed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub enum ClaudeInstallMethod {
+    /// Native installer
+    Native,
+    /// Local npm installation under ~/.claude/local
+    Local,
+    /// Global npm package
+    Npm,
+    /// Homebrew
+    Homebrew,
+    /// Anything else
+    Other,
+}
+
+/// Serializes byte buffers as base64 strings.
+///
+/// A JSON integer array costs up to four bytes per output byte; base64
+/// costs four per three.
+pub(crate) mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
 }
 
 // ============================================================================
@@ -197,3 +672,67 @@ pub fn validate_theme(theme: &str) -> Result<(), String> {
         _ => Err("Invalid theme: must be 'light', 'dark', or 'system'".to_string()),
     }
 }
+
+/// Validates an environment profile.
+/// Secret values may be missing; they are filled in from the saved profile.
+pub fn validate_env_profile(profile: &EnvProfile) -> Result<(), String> {
+    let name = profile.name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if name.chars().count() > 100 {
+        return Err("Profile name too long (max 100 characters)".to_string());
+    }
+
+    let mut keys = std::collections::HashSet::new();
+    for variable in &profile.variables {
+        let key = &variable.key;
+        if key.is_empty() || key.contains(['=', '\0']) {
+            return Err(format!("Invalid environment variable name: {key:?}"));
+        }
+        if !keys.insert(key) {
+            return Err(format!("Duplicate environment variable: {key}"));
+        }
+        if variable.value.as_deref().is_some_and(|v| v.contains('\0')) {
+            return Err(format!("Invalid value for {key}: contains a NUL character"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates the name of a secret. Names are referenced from environment
+/// values as `${secret:NAME}`, so they are limited to letters, digits, `_`,
+/// `-` and `.`.
+pub fn validate_secret_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Secret name cannot be empty".to_string());
+    }
+    if name.len() > 100 {
+        return Err("Secret name too long (max 100 characters)".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!(
+            "Invalid secret name: {name:?} (use letters, digits, '_', '-' and '.')"
+        ));
+    }
+    Ok(())
+}
+
+/// Validates the shell allowlist.
+/// Entries not in `previous` must be executable files; existing entries are
+/// kept even if the shell has since been uninstalled.
+pub fn validate_allowed_shells(shells: &[String], previous: &[String]) -> Result<(), String> {
+    if shells.is_empty() {
+        return Err("At least one shell must be allowed".to_string());
+    }
+
+    for shell in shells.iter().filter(|shell| !previous.contains(shell)) {
+        crate::pty_manager::verify_shell(shell)?;
+    }
+
+    Ok(())
+}
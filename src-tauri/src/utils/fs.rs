@@ -0,0 +1,42 @@
+//! File helpers for data that must stay private to the user.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Creates (or truncates) a file that only the user can read.
+pub fn create_private(path: &Path) -> std::io::Result<File> {
+    let file = File::create(path)?;
+    // Restrict the file before anything is written to it. The mode given at
+    // creation would not apply to a file left over from an earlier attempt.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
+
+/// Writes a file that only the user can read.
+pub fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = create_private(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Replaces a file atomically with one that only the user can read: the
+/// contents are written to a temp file next to it, which is then renamed
+/// over it.
+pub fn replace_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    write_private(&temp_path, contents)?;
+
+    if let Err(rename_err) = std::fs::rename(&temp_path, path) {
+        // Clean up the temp file to avoid leaving orphaned files on disk
+        if let Err(remove_err) = std::fs::remove_file(&temp_path) {
+            log::warn!("Failed to remove temp file after rename failure: {remove_err}");
+        }
+        return Err(rename_err);
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
 //! Utility modules for cross-platform support and common operations.
 
+pub mod fs;
 pub mod platform;
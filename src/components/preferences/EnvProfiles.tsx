@@ -0,0 +1,199 @@
+import { useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { Plus, X } from 'lucide-react'
+import { Button } from '@/components/ui/button'
+import { Checkbox } from '@/components/ui/checkbox'
+import { Input } from '@/components/ui/input'
+import type { EnvProfile, EnvVariable } from '@/lib/tauri-bindings'
+import {
+  useDeleteEnvProfile,
+  useEnvProfiles,
+  useSaveEnvProfile,
+} from '@/services/env-profiles'
+
+const emptyVariable = (): EnvVariable => ({ key: '', value: '', secret: false })
+
+/**
+ * Editor for the named environment profiles sessions can be spawned with.
+ * Saved secret values are not shown; leaving one empty keeps it.
+ */
+export function EnvProfiles() {
+  const { t } = useTranslation()
+  const { data: profiles = [] } = useEnvProfiles()
+  const saveProfile = useSaveEnvProfile()
+  const deleteProfile = useDeleteEnvProfile()
+
+  // Profile being edited, and whether it is already saved
+  const [draft, setDraft] = useState<EnvProfile | null>(null)
+  const [isNew, setIsNew] = useState(false)
+
+  const isBusy = saveProfile.isPending || deleteProfile.isPending
+
+  const updateVariable = (index: number, change: Partial<EnvVariable>) => {
+    if (!draft) return
+    setDraft({
+      ...draft,
+      variables: draft.variables.map((variable, i) =>
+        i === index ? { ...variable, ...change } : variable
+      ),
+    })
+  }
+
+  const handleSave = () => {
+    if (!draft) return
+    const variables = draft.variables
+      .filter(variable => variable.key.trim() !== '')
+      // An empty secret keeps its saved value
+      .map(variable =>
+        variable.secret && !variable.value
+          ? { ...variable, value: null }
+          : variable
+      )
+    saveProfile.mutate(
+      { ...draft, variables },
+      { onSuccess: () => setDraft(null) }
+    )
+  }
+
+  const handleDelete = () => {
+    if (!draft) return
+    deleteProfile.mutate(draft.name, { onSuccess: () => setDraft(null) })
+  }
+
+  if (!draft) {
+    return (
+      <div className="space-y-2">
+        {profiles.length > 0 && (
+          <ul className="divide-y rounded-md border">
+            {profiles.map(profile => (
+              <li key={profile.name}>
+                <button
+                  type="button"
+                  className="w-full px-3 py-1.5 text-left text-sm hover:bg-accent/50"
+                  onClick={() => {
+                    setDraft(profile)
+                    setIsNew(false)
+                  }}
+                >
+                  {profile.name}
+                  <span className="ml-2 text-muted-foreground">
+                    {t('preferences.advanced.profileVariables', {
+                      count: profile.variables.length,
+                    })}
+                  </span>
+                </button>
+              </li>
+            ))}
+          </ul>
+        )}
+        <Button
+          variant="outline"
+          onClick={() => {
+            setDraft({ name: '', variables: [emptyVariable()] })
+            setIsNew(true)
+          }}
+        >
+          <Plus />
+          {t('preferences.advanced.newProfile')}
+        </Button>
+      </div>
+    )
+  }
+
+  return (
+    <div className="space-y-2 rounded-md border p-3">
+      <Input
+        value={draft.name}
+        onChange={e => setDraft({ ...draft, name: e.target.value })}
+        placeholder={t('preferences.advanced.profileName')}
+        // Profiles are saved by name, so an existing profile keeps its name
+        disabled={!isNew || isBusy}
+      />
+
+      {draft.variables.map((variable, index) => (
+        <div key={index} className="flex items-center gap-2">
+          <Input
+            className="font-mono"
+            value={variable.key}
+            onChange={e => updateVariable(index, { key: e.target.value })}
+            placeholder={t('preferences.advanced.variableName')}
+            disabled={isBusy}
+          />
+          <Input
+            className="font-mono"
+            type={variable.secret ? 'password' : 'text'}
+            value={variable.value ?? ''}
+            onChange={e => updateVariable(index, { value: e.target.value })}
+            placeholder={
+              variable.secret && variable.value === null
+                ? t('preferences.advanced.secretSaved')
+                : t('preferences.advanced.variableValue')
+            }
+            disabled={isBusy}
+          />
+          <label className="flex items-center gap-1.5 text-sm">
+            <Checkbox
+              checked={!!variable.secret}
+              onCheckedChange={checked =>
+                updateVariable(index, { secret: checked === true })
+              }
+              disabled={isBusy}
+            />
+            {t('preferences.advanced.secret')}
+          </label>
+          <Button
+            variant="ghost"
+            size="icon-sm"
+            aria-label={t('preferences.advanced.removeVariable', {
+              name: variable.key,
+            })}
+            onClick={() =>
+              setDraft({
+                ...draft,
+                variables: draft.variables.filter((_, i) => i !== index),
+              })
+            }
+            disabled={isBusy}
+          >
+            <X />
+          </Button>
+        </div>
+      ))}
+
+      <div className="flex gap-2">
+        <Button
+          variant="ghost"
+          onClick={() =>
+            setDraft({
+              ...draft,
+              variables: [...draft.variables, emptyVariable()],
+            })
+          }
+          disabled={isBusy}
+        >
+          <Plus />
+          {t('preferences.advanced.addVariable')}
+        </Button>
+        <div className="flex-1" />
+        {!isNew && (
+          <Button variant="ghost" onClick={handleDelete} disabled={isBusy}>
+            {t('preferences.advanced.deleteProfile')}
+          </Button>
+        )}
+        <Button
+          variant="ghost"
+          onClick={() => setDraft(null)}
+          disabled={isBusy}
+        >
+          {t('common.cancel')}
+        </Button>
+        <Button
+          onClick={handleSave}
+          disabled={isBusy || draft.name.trim() === ''}
+        >
+          {t('common.save')}
+        </Button>
+      </div>
+    </div>
+  )
+}
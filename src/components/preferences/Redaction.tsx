@@ -0,0 +1,114 @@
+import { useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { X } from 'lucide-react'
+import { Button } from '@/components/ui/button'
+import { Input } from '@/components/ui/input'
+import { Label } from '@/components/ui/label'
+import { Switch } from '@/components/ui/switch'
+import type { RedactionSettings } from '@/lib/tauri-bindings'
+
+interface RedactionProps {
+  value: RedactionSettings
+  onChange: (settings: RedactionSettings) => void
+  disabled?: boolean
+}
+
+/**
+ * Settings for redacting secrets from transcripts, recordings, logs and
+ * recovery files. Patterns are regular expressions, which the backend
+ * checks when preferences are saved.
+ */
+export function Redaction({ value, onChange, disabled }: RedactionProps) {
+  const { t } = useTranslation()
+  const [newPattern, setNewPattern] = useState('')
+
+  const patterns = value.patterns ?? []
+  const canAdd = newPattern !== '' && !patterns.includes(newPattern)
+
+  const handleAdd = () => {
+    if (!canAdd) return
+    onChange({ ...value, patterns: [...patterns, newPattern] })
+    setNewPattern('')
+  }
+
+  return (
+    <div className="space-y-3">
+      <div className="flex items-center space-x-2">
+        <Switch
+          id="redaction-builtin-patterns"
+          checked={value.builtin_patterns ?? true}
+          onCheckedChange={checked =>
+            onChange({ ...value, builtin_patterns: checked })
+          }
+          disabled={disabled}
+        />
+        <Label htmlFor="redaction-builtin-patterns" className="text-sm">
+          {t('preferences.advanced.redactionBuiltinPatterns')}
+        </Label>
+      </div>
+
+      <div className="flex items-center space-x-2">
+        <Switch
+          id="redaction-live-output"
+          checked={value.live_output ?? false}
+          onCheckedChange={checked =>
+            onChange({ ...value, live_output: checked })
+          }
+          disabled={disabled}
+        />
+        <Label htmlFor="redaction-live-output" className="text-sm">
+          {t('preferences.advanced.redactionLiveOutput')}
+        </Label>
+      </div>
+
+      {patterns.length > 0 && (
+        <ul className="divide-y rounded-md border">
+          {patterns.map(pattern => (
+            <li
+              key={pattern}
+              className="flex items-center justify-between px-3 py-1.5"
+            >
+              <code className="text-sm break-all">{pattern}</code>
+              <Button
+                variant="ghost"
+                size="icon-sm"
+                aria-label={t('preferences.advanced.removeRedactionPattern', {
+                  pattern,
+                })}
+                disabled={disabled}
+                onClick={() =>
+                  onChange({
+                    ...value,
+                    patterns: patterns.filter(p => p !== pattern),
+                  })
+                }
+              >
+                <X />
+              </Button>
+            </li>
+          ))}
+        </ul>
+      )}
+
+      <div className="flex gap-2">
+        <Input
+          value={newPattern}
+          onChange={e => setNewPattern(e.target.value)}
+          onKeyDown={e => {
+            if (e.key === 'Enter') handleAdd()
+          }}
+          placeholder={t('preferences.advanced.redactionPatternPlaceholder')}
+          className="font-mono"
+          disabled={disabled}
+        />
+        <Button
+          variant="outline"
+          onClick={handleAdd}
+          disabled={disabled || !canAdd}
+        >
+          {t('preferences.advanced.addRedactionPattern')}
+        </Button>
+      </div>
+    </div>
+  )
+}
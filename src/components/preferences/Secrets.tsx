@@ -0,0 +1,165 @@
+import { useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { X } from 'lucide-react'
+import { Button } from '@/components/ui/button'
+import { Input } from '@/components/ui/input'
+import {
+  useDeleteSecret,
+  useSecrets,
+  useSetSecret,
+  useSetSecretsPassphrase,
+  useUnlockSecrets,
+} from '@/services/secrets'
+
+const SECRET_NAME = /^[A-Za-z0-9_.-]{1,100}$/
+
+/**
+ * Manages the secrets vault. Values are write-only: once saved, only the
+ * secret's name is shown, for use as `${secret:NAME}` in a session's
+ * environment.
+ */
+export function Secrets() {
+  const { t } = useTranslation()
+  const { data: status } = useSecrets()
+  const setSecret = useSetSecret()
+  const deleteSecret = useDeleteSecret()
+  const unlockSecrets = useUnlockSecrets()
+  const setPassphrase = useSetSecretsPassphrase()
+
+  const [name, setName] = useState('')
+  const [value, setValue] = useState('')
+  const [passphrase, setPassphraseInput] = useState('')
+
+  const isBusy =
+    setSecret.isPending ||
+    deleteSecret.isPending ||
+    unlockSecrets.isPending ||
+    setPassphrase.isPending
+
+  const canAdd = SECRET_NAME.test(name) && value !== ''
+
+  const handleAdd = () => {
+    if (!canAdd) return
+    setSecret.mutate(
+      { name, value },
+      {
+        onSuccess: () => {
+          setName('')
+          setValue('')
+        },
+      }
+    )
+  }
+
+  if (!status) return null
+
+  if (status.locked) {
+    return (
+      <div className="flex gap-2">
+        <Input
+          type="password"
+          value={passphrase}
+          onChange={e => setPassphraseInput(e.target.value)}
+          placeholder={t('preferences.advanced.passphrase')}
+          disabled={isBusy}
+        />
+        <Button
+          variant="outline"
+          onClick={() =>
+            unlockSecrets.mutate(passphrase, {
+              onSuccess: () => setPassphraseInput(''),
+            })
+          }
+          disabled={isBusy || passphrase === ''}
+        >
+          {t('preferences.advanced.unlockSecrets')}
+        </Button>
+      </div>
+    )
+  }
+
+  return (
+    <div className="space-y-2">
+      {status.names.length > 0 && (
+        <ul className="divide-y rounded-md border">
+          {status.names.map(secret => (
+            <li
+              key={secret}
+              className="flex items-center justify-between px-3 py-1.5"
+            >
+              <code className="text-sm">{`\${secret:${secret}}`}</code>
+              <Button
+                variant="ghost"
+                size="icon-sm"
+                aria-label={t('preferences.advanced.removeSecret', {
+                  name: secret,
+                })}
+                disabled={isBusy}
+                onClick={() => deleteSecret.mutate(secret)}
+              >
+                <X />
+              </Button>
+            </li>
+          ))}
+        </ul>
+      )}
+
+      <div className="flex gap-2">
+        <Input
+          className="font-mono"
+          value={name}
+          onChange={e => setName(e.target.value)}
+          placeholder={t('preferences.advanced.secretName')}
+          disabled={isBusy}
+        />
+        <Input
+          type="password"
+          value={value}
+          onChange={e => setValue(e.target.value)}
+          onKeyDown={e => {
+            if (e.key === 'Enter') handleAdd()
+          }}
+          placeholder={t('preferences.advanced.secretValue')}
+          disabled={isBusy}
+        />
+        <Button
+          variant="outline"
+          onClick={handleAdd}
+          disabled={isBusy || !canAdd}
+        >
+          {t('preferences.advanced.addSecret')}
+        </Button>
+      </div>
+
+      <div className="flex gap-2">
+        <Input
+          type="password"
+          value={passphrase}
+          onChange={e => setPassphraseInput(e.target.value)}
+          placeholder={t('preferences.advanced.newPassphrase')}
+          disabled={isBusy}
+        />
+        <Button
+          variant="outline"
+          onClick={() =>
+            setPassphrase.mutate(passphrase, {
+              onSuccess: () => setPassphraseInput(''),
+            })
+          }
+          disabled={isBusy || passphrase === ''}
+        >
+          {t('preferences.advanced.setPassphrase')}
+        </Button>
+        {status.has_passphrase && (
+          <Button
+            variant="ghost"
+            onClick={() => setPassphrase.mutate(null)}
+            disabled={isBusy}
+          >
+            {t('preferences.advanced.removePassphrase')}
+          </Button>
+        )}
+      </div>
+    </div>
+  )
+}
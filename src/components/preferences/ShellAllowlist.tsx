@@ -0,0 +1,86 @@
+import { useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { X } from 'lucide-react'
+import { Button } from '@/components/ui/button'
+import { Input } from '@/components/ui/input'
+
+interface ShellAllowlistProps {
+  value: string[]
+  defaultValue: string[]
+  onChange: (shells: string[]) => void
+  disabled?: boolean
+}
+
+/**
+ * Editable list of shells that terminal sessions may be spawned with.
+ * New entries are checked by the backend when preferences are saved.
+ */
+export function ShellAllowlist({
+  value,
+  defaultValue,
+  onChange,
+  disabled,
+}: ShellAllowlistProps) {
+  const { t } = useTranslation()
+  const [newShell, setNewShell] = useState('')
+
+  const trimmed = newShell.trim()
+  const canAdd = trimmed.startsWith('/') && !value.includes(trimmed)
+
+  const handleAdd = () => {
+    if (!canAdd) return
+    onChange([...value, trimmed])
+    setNewShell('')
+  }
+
+  return (
+    <div className="space-y-2">
+      <ul className="divide-y rounded-md border">
+        {value.map(shell => (
+          <li
+            key={shell}
+            className="flex items-center justify-between px-3 py-1.5"
+          >
+            <code className="text-sm">{shell}</code>
+            <Button
+              variant="ghost"
+              size="icon-sm"
+              aria-label={t('preferences.advanced.removeShell', { shell })}
+              // The last shell cannot be removed: at least one must be allowed
+              disabled={disabled || value.length <= 1}
+              onClick={() => onChange(value.filter(s => s !== shell))}
+            >
+              <X />
+            </Button>
+          </li>
+        ))}
+      </ul>
+
+      <div className="flex gap-2">
+        <Input
+          value={newShell}
+          onChange={e => setNewShell(e.target.value)}
+          onKeyDown={e => {
+            if (e.key === 'Enter') handleAdd()
+          }}
+          placeholder={t('preferences.advanced.shellPlaceholder')}
+          disabled={disabled}
+        />
+        <Button
+          variant="outline"
+          onClick={handleAdd}
+          disabled={disabled || !canAdd}
+        >
+          {t('preferences.advanced.addShell')}
+        </Button>
+        <Button
+          variant="ghost"
+          onClick={() => onChange(defaultValue)}
+          disabled={disabled || defaultValue.length === 0}
+        >
+          {t('common.reset')}
+        </Button>
+      </div>
+    </div>
+  )
+}
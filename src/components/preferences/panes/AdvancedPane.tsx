@@ -1,5 +1,6 @@
 import { useState } from 'react'
 import { useTranslation } from 'react-i18next'
+import { useQuery } from '@tanstack/react-query'
 import { Label } from '@/components/ui/label'
 import { Switch } from '@/components/ui/switch'
 import {
@@ -9,7 +10,14 @@ import {
   SelectTrigger,
   SelectValue,
 } from '@/components/ui/select'
+import { EnvProfiles } from '../EnvProfiles'
+import { Redaction } from '../Redaction'
+import { Secrets } from '../Secrets'
+import { ShellAllowlist } from '../ShellAllowlist'
 import { SettingsField, SettingsSection } from '../shared/SettingsComponents'
+import { usePreferences, useSavePreferences } from '@/services/preferences'
+import { commands } from '@/lib/tauri-bindings'
+import type { RedactionSettings } from '@/lib/tauri-bindings'
 
 export function AdvancedPane() {
   const { t } = useTranslation()
@@ -20,8 +28,69 @@ export function AdvancedPane() {
   const [exampleAdvancedToggle, setExampleAdvancedToggle] = useState(false)
   const [exampleDropdown, setExampleDropdown] = useState('option1')
 
+  const { data: preferences } = usePreferences()
+  const savePreferences = useSavePreferences()
+
+  // Installed shells from /etc/shells, used for the initial and reset lists
+  const { data: defaultShells } = useQuery({
+    queryKey: ['default-allowed-shells'],
+    queryFn: async () => {
+      return await commands.getDefaultAllowedShells()
+    },
+    staleTime: Infinity,
+  })
+
+  const handleAllowedShellsChange = (shells: string[]) => {
+    if (!preferences) return
+    savePreferences.mutate({ ...preferences, allowed_shells: shells })
+  }
+
+  const handleRedactionChange = (redaction: RedactionSettings) => {
+    if (!preferences) return
+    savePreferences.mutate({ ...preferences, redaction })
+  }
+
   return (
     <div className="space-y-6">
+      <SettingsSection title={t('preferences.advanced.terminal')}>
+        <SettingsField
+          label={t('preferences.advanced.allowedShells')}
+          description={t('preferences.advanced.allowedShellsDescription')}
+        >
+          <ShellAllowlist
+            value={preferences?.allowed_shells ?? defaultShells ?? []}
+            defaultValue={defaultShells ?? []}
+            onChange={handleAllowedShellsChange}
+            disabled={!preferences || savePreferences.isPending}
+          />
+        </SettingsField>
+
+        <SettingsField
+          label={t('preferences.advanced.envProfiles')}
+          description={t('preferences.advanced.envProfilesDescription')}
+        >
+          <EnvProfiles />
+        </SettingsField>
+
+        <SettingsField
+          label={t('preferences.advanced.secrets')}
+          description={t('preferences.advanced.secretsDescription')}
+        >
+          <Secrets />
+        </SettingsField>
+
+        <SettingsField
+          label={t('preferences.advanced.redaction')}
+          description={t('preferences.advanced.redactionDescription')}
+        >
+          <Redaction
+            value={preferences?.redaction ?? {}}
+            onChange={handleRedactionChange}
+            disabled={!preferences || savePreferences.isPending}
+          />
+        </SettingsField>
+      </SettingsSection>
+
       <SettingsSection title={t('preferences.advanced.title')}>
         <SettingsField
           label={t('preferences.advanced.toggle')}
@@ -0,0 +1,83 @@
+import { render, screen, waitFor } from '@/test/test-utils'
+import userEvent from '@testing-library/user-event'
+import { describe, it, expect, vi } from 'vitest'
+import { commands } from '@/lib/tauri-bindings'
+import { EnvironmentCheck } from './EnvironmentCheck'
+
+const zsh = {
+  path: '/bin/zsh',
+  name: 'zsh',
+  version: 'zsh 5.9',
+  allowed: true,
+}
+
+describe('EnvironmentCheck', () => {
+  it('shows the login shell and the installed Claude CLI', async () => {
+    vi.mocked(commands.detectEnvironment).mockResolvedValueOnce({
+      status: 'ok',
+      data: {
+        shells: [zsh],
+        login_shell: '/bin/zsh',
+        claude: {
+          path: '/Users/me/.local/share/claude/versions/1.0.0',
+          version: '1.0.0 (Claude Code)',
+          install_method: 'Native',
+        },
+      },
+    })
+    render(<EnvironmentCheck />)
+
+    expect(await screen.findByText('/bin/zsh — zsh 5.9')).toBeInTheDocument()
+    expect(
+      screen.getByText('1.0.0 (Claude Code) (native installer)')
+    ).toBeInTheDocument()
+  })
+
+  it('explains how to install a missing Claude CLI', async () => {
+    render(<EnvironmentCheck />)
+
+    expect(
+      await screen.findByText('Not found on your PATH. Install it with:')
+    ).toBeInTheDocument()
+    expect(
+      screen.getByText('npm install -g @anthropic-ai/claude-code')
+    ).toBeInTheDocument()
+    expect(
+      screen.getByText(
+        'No allowed shell is installed. Add one to the allowed shells in Preferences.'
+      )
+    ).toBeInTheDocument()
+  })
+
+  it('warns when the login shell is not allowed', async () => {
+    vi.mocked(commands.detectEnvironment).mockResolvedValueOnce({
+      status: 'ok',
+      data: {
+        shells: [zsh, { ...zsh, path: '/bin/fish', allowed: false }],
+        login_shell: '/bin/fish',
+        claude: null,
+      },
+    })
+    render(<EnvironmentCheck />)
+
+    expect(
+      await screen.findByText(
+        'Your login shell /bin/fish is not in the allowed shells.'
+      )
+    ).toBeInTheDocument()
+  })
+
+  it('refreshes the login environment before checking again', async () => {
+    const user = userEvent.setup()
+    render(<EnvironmentCheck />)
+
+    const checkAgain = await screen.findByLabelText('Check again')
+    const detections = vi.mocked(commands.detectEnvironment).mock.calls.length
+    await user.click(checkAgain)
+
+    expect(commands.refreshLoginEnvironment).toHaveBeenCalled()
+    await waitFor(() =>
+      expect(commands.detectEnvironment).toHaveBeenCalledTimes(detections + 1)
+    )
+  })
+})
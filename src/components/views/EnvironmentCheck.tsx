@@ -0,0 +1,142 @@
+import { useTranslation } from 'react-i18next'
+import { CircleCheck, RefreshCw, TriangleAlert } from 'lucide-react'
+import { Button } from '@/components/ui/button'
+import {
+  Item,
+  ItemActions,
+  ItemContent,
+  ItemDescription,
+  ItemGroup,
+  ItemMedia,
+  ItemTitle,
+} from '@/components/ui/item'
+import { Spinner } from '@/components/ui/spinner'
+import { useEnvironment, useRefreshEnvironment } from '@/services/environment'
+import { useUIStore } from '@/store/ui-store'
+
+const CLAUDE_INSTALL_COMMAND = 'npm install -g @anthropic-ai/claude-code'
+
+function StatusIcon({ ok }: { ok: boolean }) {
+  return ok ? (
+    <CircleCheck className="text-green-600" />
+  ) : (
+    <TriangleAlert className="text-amber-600" />
+  )
+}
+
+/**
+ * Onboarding checklist: whether a terminal session can be started and
+ * whether the Claude CLI is installed, with the next step when it is not.
+ */
+export function EnvironmentCheck() {
+  const { t } = useTranslation()
+  const setPreferencesOpen = useUIStore(state => state.setPreferencesOpen)
+  const { data: environment, isPending, isError, isFetching } =
+    useEnvironment()
+  const refreshEnvironment = useRefreshEnvironment()
+  const isRefreshing = isFetching || refreshEnvironment.isPending
+
+  if (isPending) {
+    return (
+      <div className="flex items-center gap-2 text-sm text-muted-foreground">
+        <Spinner />
+        {t('views.welcome.checkingEnvironment')}
+      </div>
+    )
+  }
+
+  if (isError) {
+    return (
+      <div className="flex items-center gap-2 text-sm text-muted-foreground">
+        {t('views.welcome.environmentError')}
+        <Button
+          variant="ghost"
+          size="sm"
+          disabled={isRefreshing}
+          onClick={() => refreshEnvironment.mutate()}
+        >
+          {t('views.welcome.checkAgain')}
+        </Button>
+      </div>
+    )
+  }
+
+  const allowedShells = environment.shells.filter(shell => shell.allowed)
+  const loginShell = environment.shells.find(
+    shell => shell.path === environment.login_shell
+  )
+  const shell = loginShell?.allowed ? loginShell : allowedShells[0]
+  const { claude } = environment
+  const installMethod =
+    claude && t(`views.welcome.installMethod.${claude.install_method}`)
+
+  return (
+    <ItemGroup className="w-full max-w-md gap-2">
+      <Item variant="outline" size="sm">
+        <ItemMedia>
+          <StatusIcon ok={!!shell} />
+        </ItemMedia>
+        <ItemContent>
+          <ItemTitle>{t('views.welcome.shell')}</ItemTitle>
+          <ItemDescription>
+            {shell
+              ? `${shell.path}${shell.version ? ` — ${shell.version}` : ''}`
+              : t('views.welcome.noAllowedShell')}
+          </ItemDescription>
+          {environment.login_shell && shell && !loginShell?.allowed && (
+            <ItemDescription>
+              {t('views.welcome.loginShellNotAllowed', {
+                shell: environment.login_shell,
+              })}
+            </ItemDescription>
+          )}
+        </ItemContent>
+        {(!shell || !loginShell?.allowed) && (
+          <ItemActions>
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={() => setPreferencesOpen(true)}
+            >
+              {t('views.welcome.openPreferences')}
+            </Button>
+          </ItemActions>
+        )}
+      </Item>
+
+      <Item variant="outline" size="sm">
+        <ItemMedia>
+          <StatusIcon ok={!!claude} />
+        </ItemMedia>
+        <ItemContent>
+          <ItemTitle>{t('views.welcome.claudeCli')}</ItemTitle>
+          {claude ? (
+            <ItemDescription>
+              {claude.version ?? claude.path} ({installMethod})
+            </ItemDescription>
+          ) : (
+            <>
+              <ItemDescription>
+                {t('views.welcome.claudeNotFound')}
+              </ItemDescription>
+              <code className="text-xs">{CLAUDE_INSTALL_COMMAND}</code>
+            </>
+          )}
+        </ItemContent>
+        {!claude && (
+          <ItemActions>
+            <Button
+              variant="ghost"
+              size="icon-sm"
+              aria-label={t('views.welcome.checkAgain')}
+              disabled={isRefreshing}
+              onClick={() => refreshEnvironment.mutate()}
+            >
+              <RefreshCw />
+            </Button>
+          </ItemActions>
+        )}
+      </Item>
+    </ItemGroup>
+  )
+}
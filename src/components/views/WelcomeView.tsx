@@ -1,9 +1,17 @@
 import { Bot, Plus, FolderOpen } from 'lucide-react'
 import { Button } from '@/components/ui/button'
 import { useTranslation } from 'react-i18next'
+import { EnvironmentCheck } from './EnvironmentCheck'
+import { useEnvironment } from '@/services/environment'
+import { useUIStore } from '@/store/ui-store'
 
 export function WelcomeView() {
   const { t } = useTranslation()
+  const setActiveView = useUIStore(state => state.setActiveView)
+  const { data: environment } = useEnvironment()
+
+  // Starting a session without an allowed shell would fail to spawn
+  const canStartSession = !!environment?.shells.some(shell => shell.allowed)
 
   return (
     <div className="flex flex-1 flex-col items-center justify-center gap-6">
@@ -15,8 +23,14 @@ export function WelcomeView() {
         </p>
       </div>
 
+      <EnvironmentCheck />
+
       <div className="flex gap-3">
-        <Button variant="outline" disabled>
+        <Button
+          variant="outline"
+          disabled={!canStartSession}
+          onClick={() => setActiveView('sessions')}
+        >
           <Plus className="size-4" />
           {t('views.welcome.newSession')}
         </Button>
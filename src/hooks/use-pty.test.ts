@@ -137,7 +137,7 @@ describe('usePty', () => {
     act(() => {
       sendChannelEvent({
         event: 'Output',
-        data: { data: [72, 101, 108, 108, 111] }, // "Hello"
+        data: { data: 'SGVsbG8=', seq: 1 }, // "Hello"
       })
     })
 
@@ -172,7 +172,7 @@ describe('usePty', () => {
     act(() => {
       sendChannelEvent({
         event: 'Exit',
-        data: { code: null },
+        data: { code: null, signal: null },
       })
     })
 
@@ -204,14 +204,14 @@ describe('usePty', () => {
     act(() => {
       sendChannelEvent({
         event: 'Output',
-        data: { data: [36, 32] }, // "$ "
+        data: { data: 'JCA=', seq: 1 }, // "$ "
       })
     })
 
     act(() => {
       sendChannelEvent({
         event: 'Exit',
-        data: { code: 0 },
+        data: { code: 0, signal: null },
       })
     })
 
@@ -303,7 +303,7 @@ describe('usePty', () => {
       if (capturedOnMessage) {
         capturedOnMessage({
           event: 'Output',
-          data: { data: [37, 32] }, // "% "
+          data: { data: 'JSA=', seq: 1 }, // "% "
         })
       }
       return { status: 'ok', data: 'session-early-data' }
@@ -330,8 +330,8 @@ describe('usePty', () => {
   // ===== Rust JSON shape matching =====
 
   it('should correctly decode Output event matching Rust serde JSON shape', async () => {
-    // Rust: PtyEvent::Output { data: vec![72,101,108,108,111] }
-    // serde(tag="event", content="data") → {"event":"Output","data":{"data":[72,101,108,108,111]}}
+    // Rust: PtyEvent::Output { data: vec![72,101,108,108,111], seq: 1 }
+    // serde(tag="event", content="data"), data as base64 → {"event":"Output","data":{"data":"SGVsbG8=","seq":1}}
     const onData = vi.fn()
     const { result } = await importAndRender({ onData })
 
@@ -349,7 +349,7 @@ describe('usePty', () => {
     act(() => {
       sendChannelEvent({
         event: 'Output',
-        data: { data: [72, 101, 108, 108, 111] },
+        data: { data: 'SGVsbG8=', seq: 1 },
       })
     })
 
@@ -371,7 +371,7 @@ describe('usePty', () => {
       if (capturedOnMessage) {
         capturedOnMessage({
           event: 'Exit',
-          data: { code: 1 },
+          data: { code: 1, signal: null },
         })
       }
       return { status: 'ok', data: 'session-exits-early' }
@@ -3,6 +3,7 @@ import { Channel } from '@tauri-apps/api/core'
 import { commands } from '@/lib/tauri-bindings'
 import type { PtyEvent, SpawnOptions } from '@/lib/tauri-bindings'
 import { debug, error as logError } from '@/lib/logger'
+import { decodeBase64 } from '@/lib/utils'
 import { useTerminalStore } from '@/store/terminal-store'
 
 const encoder = new TextEncoder()
@@ -48,14 +49,16 @@ export function usePty(options: UsePtyOptions = {}): UsePtyReturn {
     const channel = new Channel<PtyEvent>()
     channelRef.current = channel
     channel.onmessage = event => {
+      const bytes =
+        event.event === 'Output' ? decodeBase64(event.data.data) : null
       debug(
         `[pty] channel event: ${event.event}`,
-        event.event === 'Output'
-          ? { bytes: event.data.data.length }
-          : { data: event.data }
+        bytes
+          ? { bytes: bytes.length }
+          : { data: 'data' in event ? event.data : null }
       )
-      if (event.event === 'Output') {
-        onDataRef.current?.(new Uint8Array(event.data.data))
+      if (bytes) {
+        onDataRef.current?.(bytes)
       } else if (event.event === 'Exit') {
         exitedRef.current = true
         setConnectionStatus('disconnected')
@@ -135,7 +138,7 @@ export function usePty(options: UsePtyOptions = {}): UsePtyReturn {
     const id = sessionIdRef.current
     if (!id) return
 
-    const result = await commands.ptyKill(id)
+    const result = await commands.ptyKill(id, null)
     if (result.status === 'error') {
       logError('[pty] kill failed', { error: result.error })
     }
@@ -149,7 +152,7 @@ export function usePty(options: UsePtyOptions = {}): UsePtyReturn {
       const id = sessionIdRef.current
       if (id) {
         sessionIdRef.current = null
-        commands.ptyKill(id).catch(() => {
+        commands.ptyKill(id, null).catch(() => {
           // Ignore errors during cleanup — session may already be dead
         })
       }
@@ -32,6 +32,127 @@ async savePreferences(preferences: AppPreferences) : Promise<Result<null, string
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Returns the default shell allowlist (installed shells from /etc/shells)
+ * for frontend use.
+ */
+async getDefaultAllowedShells() : Promise<string[]> {
+    return await TAURI_INVOKE("get_default_allowed_shells");
+},
+/**
+ * Detects the installed shells, the user's login shell and the Claude CLI.
+ * Runs the shells and the CLI, so detection happens on a blocking thread.
+ */
+async detectEnvironment() : Promise<Result<EnvironmentInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("detect_environment") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Runs the login shell again and caches its environment, e.g. after the
+ * user installed a tool. Applies to sessions spawned afterwards.
+ */
+async refreshLoginEnvironment() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("refresh_login_environment") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists the saved environment profiles, without their secret values.
+ */
+async listEnvProfiles() : Promise<Result<EnvProfile[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_env_profiles") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Saves an environment profile, replacing the profile with the same name.
+ * Secret variables without a value keep their saved value.
+ */
+async saveEnvProfile(profile: EnvProfile) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_env_profile", { profile }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Deletes an environment profile.
+ */
+async deleteEnvProfile(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_env_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists the names of the stored secrets and whether the vault is locked.
+ */
+async listSecrets() : Promise<Result<SecretsStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_secrets") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stores a secret, replacing the secret with the same name.
+ */
+async setSecret(name: string, value: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_secret", { name, value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Deletes a secret.
+ */
+async deleteSecret(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_secret", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Unlocks a vault protected by a passphrase, until the app quits.
+ */
+async unlockSecrets(passphrase: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unlock_secrets", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets the vault's passphrase, or removes it with None.
+ * The vault must be unlocked.
+ */
+async setSecretsPassphrase(passphrase: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_secrets_passphrase", { passphrase }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * Sends a native system notification.
  * On mobile platforms, returns an error as notifications are not yet supported.
@@ -144,8 +265,23 @@ async ptySpawn(onEvent: TAURI_CHANNEL<PtyEvent>, options: SpawnOptions) : Promis
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Attaches a new channel to an existing PTY session, e.g. after a webview reload.
+ * Buffered output newer than `since` is replayed before live output resumes.
+ * If some of it was already evicted, the result is marked as truncated.
+ */
+async ptyAttach(sessionId: string, onEvent: TAURI_CHANNEL<PtyEvent>, since: number | null) : Promise<Result<AttachInfo, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_attach", { sessionId, onEvent, since }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * Writes data to a PTY session's stdin.
+ * The data is queued for the session's writer thread, so backpressure from
+ * a session that is not reading its input cannot freeze the main thread.
  */
 async ptyWrite(sessionId: string, data: number[]) : Promise<Result<null, PtyError>> {
     try {
@@ -168,10 +304,184 @@ async ptyResize(sessionId: string, cols: number, rows: number) : Promise<Result<
 },
 /**
  * Kills a PTY session and cleans up resources.
+ * Returns right away; the session's processes are given `options.timeout_ms`
+ * to exit after SIGHUP/SIGTERM before they are killed.
+ */
+async ptyKill(sessionId: string, options: KillOptions | null) : Promise<Result<null, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_kill", { sessionId, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns the buffered output of a PTY session.
+ * Used to rebuild the terminal before resuming the live output stream.
+ */
+async ptyGetScrollback(sessionId: string) : Promise<Result<ScrollbackSnapshot, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_get_scrollback", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists all PTY sessions, oldest first.
+ * Includes sessions spawned before a webview reload.
+ */
+async ptyList() : Promise<SessionInfo[]> {
+    return await TAURI_INVOKE("pty_list");
+},
+/**
+ * Sets or clears the user-assigned title of a PTY session.
+ */
+async ptySetTitle(sessionId: string, title: string | null) : Promise<Result<null, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_set_title", { sessionId, title }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns the rendered screen of a PTY session, as text or as cells.
+ * The session must have been spawned with `track_screen` enabled.
+ */
+async ptySnapshot(sessionId: string, format: SnapshotFormat) : Promise<Result<ScreenSnapshot, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_snapshot", { sessionId, format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns the process in the foreground of a PTY session (e.g. `claude`
+ * running, or the shell idle at its prompt). None if it cannot be determined.
+ */
+async ptyForegroundProcess(sessionId: string) : Promise<Result<ForegroundProcess | null, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_foreground_process", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sends a signal to the foreground process group of a PTY session (the
+ * running job, or the shell if it is idle). Not supported on Windows.
+ */
+async ptySignal(sessionId: string, signal: Signal) : Promise<Result<null, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_signal", { sessionId, signal }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts recording a PTY session to an asciicast v2 file in the
+ * `recordings` directory of the app data. Output and resizes are always
+ * recorded, input only if `options.record_input` is set.
+ */
+async ptyRecordStart(sessionId: string, options: RecordOptions | null) : Promise<Result<RecordingInfo, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_record_start", { sessionId, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stops recording a PTY session and returns the finished recording.
+ */
+async ptyRecordStop(sessionId: string) : Promise<Result<RecordingInfo, PtyError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pty_record_stop", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Searches the scrollback of running sessions and the transcripts of
+ * running and past sessions, newest session first.
+ * The search runs on a blocking thread, since transcripts can be large.
  */
-async ptyKill(sessionId: string) : Promise<Result<null, PtyError>> {
+async searchSessions(query: string, options: SearchOptions | null) : Promise<Result<SearchMatch[], PtyError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("pty_kill", { sessionId }) };
+    return { status: "ok", data: await TAURI_INVOKE("search_sessions", { query, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts playing back a recording, given its file name (`RecordingInfo::name`).
+ * Output and resizes are sent to the channel as `PtyEvent`s, followed by a
+ * `PlaybackState` event whenever the playback is paused, sought or ends.
+ */
+async playbackStart(name: string, onEvent: TAURI_CHANNEL<PtyEvent>, options: PlaybackOptions | null) : Promise<Result<PlaybackInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("playback_start", { name, onEvent, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Pauses a playback.
+ */
+async playbackPause(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("playback_pause", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Resumes a paused playback.
+ */
+async playbackResume(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("playback_resume", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Seeks a playback to `position_ms`. The terminal is reset and the output
+ * up to that position is sent at once.
+ */
+async playbackSeek(id: string, positionMs: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("playback_seek", { id, positionMs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Pauses a playback and plays its next event.
+ */
+async playbackStep(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("playback_step", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stops a playback.
+ */
+async playbackStop(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("playback_stop", { id }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -203,8 +513,184 @@ quick_pane_shortcut: string | null;
  * User's preferred language (e.g., "en", "es", "de")
  * If None, uses system locale detection
  */
-language: string | null }
+language: string | null; 
+/**
+ * Shells terminal sessions may be spawned with (absolute paths)
+ * Defaults to the installed shells listed in /etc/shells
+ */
+allowed_shells?: string[]; 
+/**
+ * Redaction of secrets from session output and files written to disk
+ */
+redaction?: RedactionSettings }
+/**
+ * Color of a terminal cell
+ */
+export type CellColor = 
+/**
+ * The terminal's default color
+ */
+{ type: "Default" } | 
+/**
+ * One of the 256 indexed palette colors
+ */
+{ type: "Indexed"; index: number } | 
+/**
+ * A 24-bit color
+ */
+{ type: "Rgb"; r: number; g: number; b: number }
+/**
+ * An installation of the Claude CLI
+ */
+export type ClaudeCliInfo = { 
+/**
+ * Resolved path of the executable
+ */
+path: string; 
+/**
+ * Reported version (e.g. "1.0.0 (Claude Code)")
+ */
+version: string | null; 
+/**
+ * How the CLI was installed, guessed from its path
+ */
+install_method: ClaudeInstallMethod }
+/**
+ * How the Claude CLI was installed
+ */
+export type ClaudeInstallMethod = 
+/**
+ * Native installer
+ */
+"Native" | 
+/**
+ * Local npm installation under ~/.claude/local
+ */
+"Local" | 
+/**
+ * Global npm package
+ */
+"Npm" | 
+/**
+ * Homebrew
+ */
+"Homebrew" | 
+/**
+ * Anything else
+ */
+"Other"
+/**
+ * A named set of environment variables that sessions can be spawned with
+ */
+export type EnvProfile = { 
+/**
+ * Unique name shown to the user (e.g. "staging proxy")
+ */
+name: string; 
+/**
+ * Variables set by the profile
+ */
+variables: EnvVariable[] }
+/**
+ * An environment variable of a profile
+ */
+export type EnvVariable = { 
+/**
+ * Variable name
+ */
+key: string; 
+/**
+ * Value of the variable. Secret values are not sent to the frontend,
+ * which sees None; saving a secret variable without a value keeps the
+ * value saved before.
+ */
+value: string | null; 
+/**
+ * Whether the value is secret (e.g. an API key)
+ */
+secret?: boolean }
+/**
+ * Shells, login shell and Claude CLI found on the system
+ */
+export type EnvironmentInfo = { 
+/**
+ * Installed and allowed shells
+ */
+shells: ShellInfo[]; 
+/**
+ * The user's login shell, from the password database
+ */
+login_shell: string | null; 
+/**
+ * The Claude CLI, if it was found on the login shell's PATH
+ */
+claude: ClaudeCliInfo | null }
+/**
+ * Leader of the foreground process group of a PTY session
+ */
+export type ForegroundProcess = { 
+/**
+ * Process ID
+ */
+pid: number; 
+/**
+ * Executable name (e.g. "claude", "vim")
+ */
+name: string; 
+/**
+ * Command line arguments, including the program name
+ */
+argv: string[]; 
+/**
+ * Whether this is the process the session was spawned with,
+ * i.e. the shell is idle at its prompt
+ */
+is_shell: boolean }
 export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
+/**
+ * Options for killing a PTY session
+ */
+export type KillOptions = { 
+/**
+ * How long the session's processes are given to exit after SIGHUP and
+ * SIGTERM before they are killed with SIGKILL, in milliseconds.
+ * Defaults to 3 seconds and is at most 60 seconds; 0 kills them right
+ * away.
+ */
+timeout_ms?: number | null }
+/**
+ * A recording being played back
+ */
+export type PlaybackInfo = { 
+/**
+ * Playback identifier, used to control it
+ */
+id: string; 
+/**
+ * Terminal size at the start of the recording
+ */
+cols: number; rows: number; 
+/**
+ * Length of the playback in milliseconds, with idle time skipped
+ */
+duration_ms: number }
+/**
+ * Options for playing back a recording
+ */
+export type PlaybackOptions = { 
+/**
+ * Playback speed, e.g. 2.0 for twice as fast (defaults to 1.0)
+ */
+speed?: number | null; 
+/**
+ * Skip idle time: pauses between events longer than this are shortened
+ * to it, in milliseconds
+ */
+max_idle_ms?: number | null; 
+/**
+ * Start paused, e.g. to step through the recording
+ */
+paused?: boolean }
 /**
  * Error types for PTY operations (typed for frontend matching)
  */
@@ -246,17 +732,103 @@ export type PtyError =
  */
 export type PtyEvent = 
 /**
- * Raw output bytes from the PTY
+ * Raw output bytes from the PTY (base64-encoded on the wire),
+ * tagged with the scrollback sequence number
  */
-{ event: "Output"; data: { data: number[] } } | 
+{ event: "Output"; data: { data: string; seq: number } } | 
 /**
- * PTY process exited
+ * PTY process exited.
+ * `code` is set on a normal exit, `signal` (e.g. "SIGINT") if the
+ * process was terminated by a signal.
  */
-{ event: "Exit"; data: { code: number | null } } | 
+{ event: "Exit"; data: { code: number | null; signal: string | null } } | 
 /**
  * Error occurred in the PTY
  */
-{ event: "Error"; data: { message: string } }
+{ event: "Error"; data: { message: string } } | 
+/**
+ * The shell is about to draw its prompt (OSC 133;A)
+ */
+{ event: "PromptStart" } | 
+/**
+ * A command started running (OSC 133;C).
+ * `command` is the command line, if the shell reported it.
+ */
+{ event: "CommandStart"; data: { command: string | null } } | 
+/**
+ * A command finished (OSC 133;D).
+ * `duration_ms` is measured from the matching `CommandStart`.
+ */
+{ event: "CommandFinished"; data: { exit_code: number | null; duration_ms: number | null } } | 
+/**
+ * The shell's working directory changed (OSC 7)
+ */
+{ event: "CwdChanged"; data: { cwd: string } } | 
+/**
+ * The program in the terminal set the window title (OSC 0/2).
+ * An empty title means the title was cleared.
+ */
+{ event: "TitleChanged"; data: { title: string } } | 
+/**
+ * The process in the foreground of the terminal changed.
+ * None if it could not be determined.
+ */
+{ event: "ForegroundChanged"; data: { process: ForegroundProcess | null } } | 
+/**
+ * The terminal was resized. Only sent when a recording is played back.
+ */
+{ event: "Resize"; data: { cols: number; rows: number } } | 
+/**
+ * Playback of a recording was paused, resumed, sought or stepped, or
+ * reached the end. `position_ms` is the position in the recording.
+ */
+{ event: "PlaybackState"; data: { position_ms: number; paused: boolean; finished: boolean } }
+/**
+ * Options for recording a PTY session
+ */
+export type RecordOptions = { 
+/**
+ * Also record the input written to the session. Off by default, since
+ * input includes anything typed at a password prompt.
+ */
+record_input?: boolean; 
+/**
+ * Maximum size of the recording file in bytes (defaults to 50MB, at
+ * most 500MB). Recording stops when it is reached.
+ */
+max_bytes?: number | null }
+/**
+ * A recording of a PTY session, in asciicast v2 format
+ */
+export type RecordingInfo = { 
+/**
+ * Session being recorded
+ */
+session_id: string; 
+/**
+ * File name of the recording, used to play it back
+ */
+name: string; 
+/**
+ * Path of the `.cast` file
+ */
+path: string; 
+/**
+ * When recording started (milliseconds since Unix epoch)
+ */
+started_at: number; 
+/**
+ * Size of the recording file in bytes
+ */
+bytes: number; 
+/**
+ * Whether input is recorded
+ */
+record_input: boolean; 
+/**
+ * Whether recording stopped early because the size limit was reached
+ */
+truncated: boolean }
 /**
  * Error types for recovery operations (typed for frontend matching)
  */
@@ -281,6 +853,313 @@ export type RecoveryError =
  * JSON serialization/deserialization error
  */
 { type: "ParseError"; message: string }
+/**
+ * Which secrets are redacted, and where
+ */
+export type RedactionSettings = { 
+/**
+ * Redact the built-in patterns: API keys of Anthropic, OpenAI, AWS and
+ * GitHub, JWTs, `Authorization` header credentials and private keys
+ */
+builtin_patterns?: boolean; 
+/**
+ * Additional regular expressions to redact
+ */
+patterns?: string[]; 
+/**
+ * Also redact the output shown in the terminal. Applies to sessions
+ * spawned afterwards.
+ */
+live_output?: boolean }
+/**
+ * A single cell of the terminal screen
+ */
+export type ScreenCell = { 
+/**
+ * Character in the cell (empty if blank or covered by a wide character)
+ */
+text: string; 
+/**
+ * Foreground color
+ */
+fg: CellColor; 
+/**
+ * Background color
+ */
+bg: CellColor; bold: boolean; italic: boolean; underline: boolean; inverse: boolean; 
+/**
+ * Whether the character spans two columns
+ */
+wide: boolean }
+/**
+ * Rendered state of a session's terminal screen
+ */
+export type ScreenSnapshot = { 
+/**
+ * Number of rows on screen
+ */
+rows: number; 
+/**
+ * Number of columns on screen
+ */
+cols: number; 
+/**
+ * Cursor row (0-based)
+ */
+cursor_row: number; 
+/**
+ * Cursor column (0-based)
+ */
+cursor_col: number; 
+/**
+ * Whether the cursor is shown
+ */
+cursor_visible: boolean; 
+/**
+ * Whether a full-screen program has switched to the alternate screen
+ */
+alternate_screen: boolean; 
+/**
+ * Text of each row, up to the last cell that was written to
+ */
+lines: string[]; 
+/**
+ * Cells of each row, if requested with `SnapshotFormat::Cells`
+ */
+cells: ScreenCell[][] | null }
+/**
+ * Result of attaching to a PTY session
+ */
+export type AttachInfo = { 
+/**
+ * Whether output newer than `since` was evicted from the buffer, so the
+ * replay has a gap. The terminal should be rebuilt from
+ * `pty_get_scrollback` instead.
+ */
+truncated: boolean }
+/**
+ * Buffered output of a PTY session, used to rebuild the terminal after a reload
+ */
+export type ScrollbackSnapshot = { 
+/**
+ * Buffered output bytes, oldest first (base64-encoded on the wire)
+ */
+data: string; 
+/**
+ * Sequence number of the last chunk included in `data`.
+ * Live `Output` events with a greater `seq` follow this snapshot.
+ */
+seq: number; 
+/**
+ * Whether older output was evicted because the buffer limit was reached
+ */
+truncated: boolean }
+/**
+ * A line of session output matching a search
+ */
+export type SearchMatch = { session_id: string; source: SearchSource; 
+/**
+ * Offset of the line in the session's output, counted in bytes from
+ * the start of the session
+ */
+offset: number; 
+/**
+ * When the line was output (milliseconds since Unix epoch, accurate
+ * to about a second in transcripts)
+ */
+time: number | null; 
+/**
+ * The matching line, with escape sequences removed
+ */
+line: string; 
+/**
+ * Lines before and after the matching line
+ */
+context_before: string[]; context_after: string[] }
+/**
+ * Options for searching session output
+ */
+export type SearchOptions = { 
+/**
+ * Treat the query as a regular expression rather than as plain text
+ */
+regex?: boolean; 
+/**
+ * Match case exactly
+ */
+case_sensitive?: boolean; 
+/**
+ * Only search these sessions (all sessions if empty)
+ */
+session_ids?: string[]; 
+/**
+ * Only return output from this time on (milliseconds since Unix epoch)
+ */
+since?: number | null; 
+/**
+ * Only return output from before this time (milliseconds since Unix epoch)
+ */
+until?: number | null; 
+/**
+ * Lines of context returned before and after each match (defaults to 2)
+ */
+context_lines?: number | null; 
+/**
+ * Maximum number of matches returned (defaults to 200)
+ */
+max_results?: number | null }
+/**
+ * Where a search match was found
+ */
+export type SearchSource = 
+/**
+ * Scrollback of a running session without a transcript
+ */
+"Scrollback" | 
+/**
+ * Transcript of a running or past session
+ */
+"Transcript"
+/**
+ * State of the secrets vault. Secret values are never sent to the frontend.
+ */
+export type SecretsStatus = { 
+/**
+ * Names of the stored secrets, empty while the vault is locked
+ */
+names: string[]; 
+/**
+ * Whether the vault is protected by a passphrase
+ */
+has_passphrase: boolean; 
+/**
+ * Whether the vault must be unlocked with its passphrase before its
+ * secrets can be used
+ */
+locked: boolean }
+/**
+ * Information about an active PTY session
+ */
+export type SessionInfo = { 
+/**
+ * Unique session identifier
+ */
+id: string; 
+/**
+ * Process ID of the shell or program
+ */
+pid: number | null; 
+/**
+ * Whether the session is still alive
+ */
+is_alive: boolean; 
+/**
+ * Executable the session was spawned with: the shell, or the resolved
+ * path of the program
+ */
+shell: string; 
+/**
+ * Program the session runs directly, or None for a shell session
+ */
+program: string | null; 
+/**
+ * Arguments passed to the executable
+ */
+args: string[]; 
+/**
+ * Working directory the session was started in
+ */
+cwd: string | null; 
+/**
+ * Current working directory, as last reported by the shell (OSC 7).
+ * Same as `cwd` until the shell reports a change.
+ */
+current_cwd: string | null; 
+/**
+ * Creation time in milliseconds since the Unix epoch
+ */
+created_at: number; 
+/**
+ * Current terminal columns
+ */
+cols: number; 
+/**
+ * Current terminal rows
+ */
+rows: number; 
+/**
+ * User-assigned title
+ */
+title: string | null; 
+/**
+ * Title last set by the program in the terminal (OSC 0/2),
+ * independent of the user-assigned title
+ */
+terminal_title: string | null }
+/**
+ * A shell found on the system
+ */
+export type ShellInfo = { 
+/**
+ * Path of the shell, as listed in /etc/shells or the allowlist
+ */
+path: string; 
+/**
+ * Shell name (e.g. "zsh")
+ */
+name: string; 
+/**
+ * First line of `--version` output, if the shell reports one
+ */
+version: string | null; 
+/**
+ * Whether sessions may be spawned with this shell
+ */
+allowed: boolean }
+/**
+ * Signal that can be sent to the foreground of a PTY session
+ */
+export type Signal = 
+/**
+ * Interrupt, as sent by `Ctrl+C`
+ */
+"SIGINT" | 
+/**
+ * Polite request to terminate
+ */
+"SIGTERM" | 
+/**
+ * The terminal hung up
+ */
+"SIGHUP" | 
+/**
+ * Stop (pause), as sent by `Ctrl+Z`
+ */
+"SIGTSTP" | 
+/**
+ * Resume a stopped process
+ */
+"SIGCONT" | 
+/**
+ * Quit with a core dump, as sent by `Ctrl+\`
+ */
+"SIGQUIT" | 
+/**
+ * The terminal size changed
+ */
+"SIGWINCH"
+/**
+ * Level of detail of a screen snapshot
+ */
+export type SnapshotFormat = 
+/**
+ * Text of each row only
+ */
+"Text" | 
+/**
+ * Text of each row plus every cell with its attributes
+ */
+"Cells"
 /**
  * Options for spawning a new PTY session
  */
@@ -290,7 +1169,14 @@ export type SpawnOptions = {
  */
 command: string | null; 
 /**
- * Arguments to pass to the command
+ * Allowed program to run directly instead of a shell (e.g. "claude"),
+ * looked up by name. Cannot be combined with `command`. The session
+ * exits when the program does.
+ */
+program?: string | null; 
+/**
+ * Arguments to pass to the command, or to the program (validated
+ * against the options it accepts)
  */
 args?: string[]; 
 /**
@@ -298,7 +1184,8 @@ args?: string[];
  */
 cwd: string | null; 
 /**
- * Additional environment variables
+ * Additional environment variables. A value of the form
+ * `${secret:NAME}` is replaced with the secret NAME from the vault.
  */
 env?: Partial<{ [key in string]: string }>; 
 /**
@@ -308,7 +1195,29 @@ cols: number;
 /**
  * Terminal rows
  */
-rows: number }
+rows: number; 
+/**
+ * Scrollback buffer size in bytes (defaults to 1MB)
+ */
+scrollback_limit?: number | null; 
+/**
+ * Keep a headless model of the terminal screen, required for `pty_snapshot`
+ */
+track_screen?: boolean; 
+/**
+ * Load shell integration scripts (bash, zsh, fish) that report command
+ * boundaries. Only applied when no shell arguments are given.
+ */
+shell_integration?: boolean; 
+/**
+ * Name of a saved environment profile to apply. Variables in `env`
+ * take precedence over the profile's.
+ */
+profile?: string | null; 
+/**
+ * Write the session's output to a compressed transcript under app data
+ */
+transcript?: boolean }
 export type TAURI_CHANNEL<TSend> = null
 
 /** tauri-specta globals **/
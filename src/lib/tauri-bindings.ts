@@ -24,10 +24,35 @@
 export { commands, type Result } from './bindings'
 export type {
   AppPreferences,
+  AttachInfo,
+  CellColor,
+  ClaudeCliInfo,
+  ClaudeInstallMethod,
+  EnvProfile,
+  EnvVariable,
+  EnvironmentInfo,
+  ForegroundProcess,
   JsonValue,
+  KillOptions,
+  PlaybackInfo,
+  PlaybackOptions,
   PtyError,
   PtyEvent,
+  RecordOptions,
+  RecordingInfo,
   RecoveryError,
+  RedactionSettings,
+  ScreenCell,
+  ScreenSnapshot,
+  ScrollbackSnapshot,
+  SearchMatch,
+  SearchOptions,
+  SearchSource,
+  SecretsStatus,
+  SessionInfo,
+  ShellInfo,
+  Signal,
+  SnapshotFormat,
   SpawnOptions,
 } from './bindings'
 
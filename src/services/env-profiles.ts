@@ -0,0 +1,73 @@
+import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
+import { toast } from 'sonner'
+import { logger } from '@/lib/logger'
+import {
+  commands,
+  unwrapResult,
+  type EnvProfile,
+} from '@/lib/tauri-bindings'
+
+// Query keys for environment profiles
+export const envProfilesQueryKeys = {
+  all: ['env-profiles'] as const,
+  list: () => [...envProfilesQueryKeys.all] as const,
+}
+
+/**
+ * Saved environment profiles. Secret values are never sent by the backend:
+ * their `value` is null.
+ */
+export function useEnvProfiles() {
+  return useQuery({
+    queryKey: envProfilesQueryKeys.list(),
+    queryFn: async (): Promise<EnvProfile[]> => {
+      logger.debug('Loading environment profiles')
+      return unwrapResult(await commands.listEnvProfiles())
+    },
+  })
+}
+
+export function useSaveEnvProfile() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async (profile: EnvProfile) => {
+      logger.debug('Saving environment profile', { name: profile.name })
+      const result = await commands.saveEnvProfile(profile)
+
+      if (result.status === 'error') {
+        logger.error('Failed to save environment profile', {
+          error: result.error,
+        })
+        toast.error('Failed to save profile', { description: result.error })
+        throw new Error(result.error)
+      }
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: envProfilesQueryKeys.all })
+      toast.success('Profile saved')
+    },
+  })
+}
+
+export function useDeleteEnvProfile() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async (name: string) => {
+      logger.debug('Deleting environment profile', { name })
+      const result = await commands.deleteEnvProfile(name)
+
+      if (result.status === 'error') {
+        logger.error('Failed to delete environment profile', {
+          error: result.error,
+        })
+        toast.error('Failed to delete profile', { description: result.error })
+        throw new Error(result.error)
+      }
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: envProfilesQueryKeys.all })
+    },
+  })
+}
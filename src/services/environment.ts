@@ -0,0 +1,55 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { logger } from '@/lib/logger'
+import {
+  commands,
+  unwrapResult,
+  type EnvironmentInfo,
+} from '@/lib/tauri-bindings'
+
+// Query keys for environment detection
+export const environmentQueryKeys = {
+  all: ['environment'] as const,
+  environment: () => [...environmentQueryKeys.all] as const,
+}
+
+/**
+ * Installed shells and Claude CLI. Detection runs the shells, so the result
+ * is kept until it is explicitly refetched (e.g. after installing the CLI).
+ */
+export function useEnvironment() {
+  return useQuery({
+    queryKey: environmentQueryKeys.environment(),
+    queryFn: async (): Promise<EnvironmentInfo> => {
+      logger.debug('Detecting environment')
+      const environment = unwrapResult(await commands.detectEnvironment())
+      logger.info('Environment detected', { environment })
+      return environment
+    },
+    staleTime: Infinity,
+  })
+}
+
+/**
+ * Runs the login shell again, then detects the environment anew. Used after
+ * the user installs the Claude CLI or changes their shell startup files.
+ */
+export function useRefreshEnvironment() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async () => {
+      logger.debug('Refreshing login shell environment')
+      const result = await commands.refreshLoginEnvironment()
+      if (result.status === 'error') {
+        // Detection falls back to the app's own PATH
+        logger.warn('Failed to refresh login shell environment', {
+          error: result.error,
+        })
+      }
+    },
+    onSettled: () =>
+      queryClient.invalidateQueries({
+        queryKey: environmentQueryKeys.environment(),
+      }),
+  })
+}
@@ -0,0 +1,82 @@
+import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
+import { toast } from 'sonner'
+import { logger } from '@/lib/logger'
+import {
+  commands,
+  unwrapResult,
+  type SecretsStatus,
+} from '@/lib/tauri-bindings'
+
+// Query keys for the secrets vault
+export const secretsQueryKeys = {
+  all: ['secrets'] as const,
+  status: () => [...secretsQueryKeys.all, 'status'] as const,
+}
+
+/**
+ * Names of the stored secrets and the vault's lock state.
+ * Secret values are never sent by the backend.
+ */
+export function useSecrets() {
+  return useQuery({
+    queryKey: secretsQueryKeys.status(),
+    queryFn: async (): Promise<SecretsStatus> => {
+      logger.debug('Loading secrets')
+      return unwrapResult(await commands.listSecrets())
+    },
+  })
+}
+
+/**
+ * Wraps a vault command in a mutation that reports failures with a toast
+ * and refreshes the vault status.
+ */
+function useSecretsMutation<T>(
+  action: string,
+  run: (
+    variables: T
+  ) => Promise<{ status: 'ok'; data: null } | { status: 'error'; error: string }>
+) {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: async (variables: T) => {
+      const result = await run(variables)
+
+      if (result.status === 'error') {
+        logger.error(`Failed to ${action}`, { error: result.error })
+        toast.error(`Failed to ${action}`, { description: result.error })
+        throw new Error(result.error)
+      }
+    },
+    onSettled: () => {
+      queryClient.invalidateQueries({ queryKey: secretsQueryKeys.all })
+    },
+  })
+}
+
+export function useSetSecret() {
+  return useSecretsMutation(
+    'save secret',
+    ({ name, value }: { name: string; value: string }) =>
+      commands.setSecret(name, value)
+  )
+}
+
+export function useDeleteSecret() {
+  return useSecretsMutation('delete secret', (name: string) =>
+    commands.deleteSecret(name)
+  )
+}
+
+export function useUnlockSecrets() {
+  return useSecretsMutation('unlock secrets', (passphrase: string) =>
+    commands.unlockSecrets(passphrase)
+  )
+}
+
+export function useSetSecretsPassphrase() {
+  return useSecretsMutation('change passphrase', (passphrase: string | null) =>
+    commands.setSecretsPassphrase(passphrase)
+  )
+}
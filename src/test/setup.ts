@@ -81,6 +81,26 @@ vi.mock('@/lib/tauri-bindings', () => ({
     cleanupOldRecoveryFiles: vi
       .fn()
       .mockResolvedValue({ status: 'ok', data: 0 }),
+    detectEnvironment: vi.fn().mockResolvedValue({
+      status: 'ok',
+      data: { shells: [], login_shell: null, claude: null },
+    }),
+    refreshLoginEnvironment: vi
+      .fn()
+      .mockResolvedValue({ status: 'ok', data: null }),
+    listEnvProfiles: vi.fn().mockResolvedValue({ status: 'ok', data: [] }),
+    saveEnvProfile: vi.fn().mockResolvedValue({ status: 'ok', data: null }),
+    deleteEnvProfile: vi.fn().mockResolvedValue({ status: 'ok', data: null }),
+    listSecrets: vi.fn().mockResolvedValue({
+      status: 'ok',
+      data: { names: [], has_passphrase: false, locked: false },
+    }),
+    setSecret: vi.fn().mockResolvedValue({ status: 'ok', data: null }),
+    deleteSecret: vi.fn().mockResolvedValue({ status: 'ok', data: null }),
+    unlockSecrets: vi.fn().mockResolvedValue({ status: 'ok', data: null }),
+    setSecretsPassphrase: vi
+      .fn()
+      .mockResolvedValue({ status: 'ok', data: null }),
   },
   unwrapResult: vi.fn((result: { status: string; data?: unknown }) => {
     if (result.status === 'ok') return result.data